@@ -0,0 +1,836 @@
+use std::{collections::HashMap, fmt, fs, io::ErrorKind, str::FromStr};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::graph::formats::{ExportFormat, ImportFormat};
+
+/// Everything the editor can do, whether it is run by a shortcut, from
+/// the command palette or by a mouse gesture on the canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EditorAction {
+    Undo,
+    Redo,
+    Copy,
+    Cut,
+    Paste,
+    Duplicate,
+    SelectAll,
+    ClearSelection,
+    FrameAll,
+    FrameSelection,
+    Search,
+    CommandPalette,
+    Save,
+    Open,
+    /// Written next to the document, with the extension of the format.
+    Export(ExportFormat),
+    /// Read from next to the document, with the extension of the format.
+    Import(ImportFormat),
+    ExportMetrics,
+    Generate,
+    MergeVertices,
+    Settings,
+    ToggleStatistics,
+    ToggleMetrics,
+    ToggleInspector,
+    ToggleMinimap,
+    ToggleTextEditor,
+    ToggleAdjacencyMatrix,
+    ToggleTables,
+    SpawnVertex,
+    MoveVertex,
+    DrawEdge,
+    RenameVertex,
+    DeleteVertex,
+    DeleteEdge,
+    SplitEdge,
+    ReconnectEdge,
+    ContractEdge,
+    ToggleSelected,
+    RectangleSelection,
+    LassoSelection,
+}
+
+impl EditorAction {
+    /// In the order the command palette lists them without a query.
+    pub const ALL: [EditorAction; 48] = [
+        EditorAction::Undo,
+        EditorAction::Redo,
+        EditorAction::Copy,
+        EditorAction::Cut,
+        EditorAction::Paste,
+        EditorAction::Duplicate,
+        EditorAction::SelectAll,
+        EditorAction::ClearSelection,
+        EditorAction::FrameAll,
+        EditorAction::FrameSelection,
+        EditorAction::Search,
+        EditorAction::CommandPalette,
+        EditorAction::Save,
+        EditorAction::Open,
+        EditorAction::Export(ExportFormat::Svg),
+        EditorAction::Export(ExportFormat::Tikz),
+        EditorAction::Export(ExportFormat::GraphMl),
+        EditorAction::Export(ExportFormat::Gexf),
+        EditorAction::Export(ExportFormat::EdgeList),
+        EditorAction::Export(ExportFormat::AdjacencyMatrix),
+        EditorAction::Export(ExportFormat::Mermaid),
+        EditorAction::Import(ImportFormat::GraphMl),
+        EditorAction::Import(ImportFormat::EdgeList),
+        EditorAction::Import(ImportFormat::AdjacencyMatrix),
+        EditorAction::Import(ImportFormat::Mermaid),
+        EditorAction::ExportMetrics,
+        EditorAction::Generate,
+        EditorAction::MergeVertices,
+        EditorAction::Settings,
+        EditorAction::ToggleStatistics,
+        EditorAction::ToggleMetrics,
+        EditorAction::ToggleInspector,
+        EditorAction::ToggleMinimap,
+        EditorAction::ToggleTextEditor,
+        EditorAction::ToggleAdjacencyMatrix,
+        EditorAction::ToggleTables,
+        EditorAction::SpawnVertex,
+        EditorAction::MoveVertex,
+        EditorAction::DrawEdge,
+        EditorAction::RenameVertex,
+        EditorAction::DeleteVertex,
+        EditorAction::DeleteEdge,
+        EditorAction::SplitEdge,
+        EditorAction::ReconnectEdge,
+        EditorAction::ContractEdge,
+        EditorAction::ToggleSelected,
+        EditorAction::RectangleSelection,
+        EditorAction::LassoSelection,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EditorAction::Undo => "Undo",
+            EditorAction::Redo => "Redo",
+            EditorAction::Copy => "Copy selection",
+            EditorAction::Cut => "Cut selection",
+            EditorAction::Paste => "Paste",
+            EditorAction::Duplicate => "Duplicate selection",
+            EditorAction::SelectAll => "Select all",
+            EditorAction::ClearSelection => "Clear selection",
+            EditorAction::FrameAll => "Frame all",
+            EditorAction::FrameSelection => "Frame selection",
+            EditorAction::Search => "Search vertices",
+            EditorAction::CommandPalette => "Command palette",
+            EditorAction::Save => "Save graph",
+            EditorAction::Open => "Open graph",
+            EditorAction::Export(format) => match format {
+                ExportFormat::Svg => "Export as SVG",
+                ExportFormat::Tikz => "Export as TikZ",
+                ExportFormat::GraphMl => "Export as GraphML",
+                ExportFormat::Gexf => "Export as GEXF",
+                ExportFormat::EdgeList => "Export as edge list CSV",
+                ExportFormat::AdjacencyMatrix => "Export as adjacency matrix CSV",
+                ExportFormat::Mermaid => "Export as Mermaid",
+            },
+            EditorAction::Import(format) => match format {
+                ImportFormat::GraphMl => "Import GraphML",
+                ImportFormat::EdgeList => "Import edge list CSV",
+                ImportFormat::AdjacencyMatrix => "Import adjacency matrix CSV",
+                ImportFormat::Mermaid => "Import Mermaid",
+            },
+            EditorAction::ExportMetrics => "Export metrics as CSV",
+            EditorAction::Generate => "Generate graph",
+            EditorAction::MergeVertices => "Merge selected vertices",
+            EditorAction::Settings => "Settings",
+            EditorAction::ToggleStatistics => "Toggle statistics panel",
+            EditorAction::ToggleMetrics => "Toggle metrics panel",
+            EditorAction::ToggleInspector => "Toggle inspector",
+            EditorAction::ToggleMinimap => "Toggle minimap",
+            EditorAction::ToggleTextEditor => "Toggle text editor",
+            EditorAction::ToggleAdjacencyMatrix => "Toggle adjacency matrix",
+            EditorAction::ToggleTables => "Toggle tables",
+            EditorAction::SpawnVertex => "Add vertex",
+            EditorAction::MoveVertex => "Move vertex",
+            EditorAction::DrawEdge => "Draw edge",
+            EditorAction::RenameVertex => "Rename vertex",
+            EditorAction::DeleteVertex => "Delete vertex",
+            EditorAction::DeleteEdge => "Delete edge",
+            EditorAction::SplitEdge => "Insert vertex on edge",
+            EditorAction::ReconnectEdge => "Reconnect edge",
+            EditorAction::ContractEdge => "Contract edge",
+            EditorAction::ToggleSelected => "Toggle vertex selection",
+            EditorAction::RectangleSelection => "Rectangle selection",
+            EditorAction::LassoSelection => "Lasso selection",
+        }
+    }
+
+    /// Gestures need a target on the canvas and are handled by the
+    /// picking observers, so they can't be run from a shortcut or the palette.
+    pub fn is_gesture(&self) -> bool {
+        self.gesture().is_some()
+    }
+
+    /// The gesture whose observer runs the action. Only its button and
+    /// modifiers can be rebound.
+    pub fn gesture(&self) -> Option<Gesture> {
+        match self {
+            EditorAction::SpawnVertex => Some(Gesture::ClickCanvas),
+            EditorAction::RectangleSelection | EditorAction::LassoSelection => {
+                Some(Gesture::DragCanvas)
+            }
+            EditorAction::DeleteVertex | EditorAction::ToggleSelected => Some(Gesture::ClickVertex),
+            EditorAction::RenameVertex => Some(Gesture::DoubleClickVertex),
+            EditorAction::MoveVertex | EditorAction::DrawEdge => Some(Gesture::DragVertex),
+            EditorAction::DeleteEdge | EditorAction::SplitEdge | EditorAction::ContractEdge => {
+                Some(Gesture::ClickEdge)
+            }
+            EditorAction::ReconnectEdge => Some(Gesture::DragEdge),
+            _ => None,
+        }
+    }
+}
+
+/// Ctrl, Shift, Alt and Cmd (the Super or Windows key), the left
+/// and right keys count the same.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub command: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers {
+        ctrl: false,
+        shift: false,
+        alt: false,
+        command: false,
+    };
+    pub const CTRL: Modifiers = Modifiers {
+        ctrl: true,
+        ..Modifiers::NONE
+    };
+    pub const SHIFT: Modifiers = Modifiers {
+        shift: true,
+        ..Modifiers::NONE
+    };
+    pub const ALT: Modifiers = Modifiers {
+        alt: true,
+        ..Modifiers::NONE
+    };
+    pub const COMMAND: Modifiers = Modifiers {
+        command: true,
+        ..Modifiers::NONE
+    };
+
+    /// Cmd on macOS and Ctrl everywhere else, for the usual shortcuts.
+    pub const PRIMARY: Modifiers = if cfg!(target_os = "macos") {
+        Modifiers::COMMAND
+    } else {
+        Modifiers::CTRL
+    };
+
+    pub fn held(keyboard: &ButtonInput<KeyCode>) -> Self {
+        Modifiers {
+            ctrl: keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]),
+            shift: keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+            alt: keyboard.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]),
+            command: keyboard.any_pressed([KeyCode::SuperLeft, KeyCode::SuperRight]),
+        }
+    }
+
+    fn with_shift(self) -> Self {
+        Modifiers {
+            shift: true,
+            ..self
+        }
+    }
+
+    /// Adds a modifier by its name in a binding, `false` if it is not one.
+    fn insert(&mut self, name: &str) -> bool {
+        let held = match name.to_lowercase().as_str() {
+            "ctrl" | "control" => &mut self.ctrl,
+            "shift" => &mut self.shift,
+            "alt" | "option" => &mut self.alt,
+            "cmd" | "command" | "super" | "meta" => &mut self.command,
+            _ => return false,
+        };
+        *held = true;
+        true
+    }
+}
+
+impl fmt::Display for Modifiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [
+            (self.ctrl, "Ctrl"),
+            (self.shift, "Shift"),
+            (self.alt, "Alt"),
+            (self.command, "Cmd"),
+        ] {
+            if held {
+                write!(f, "{name} + ")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Mouse gestures handled by the picking observers. Only their button
+/// and modifiers are looked up in the registry, so they can't be run
+/// from the palette, and each action keeps its own gesture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    ClickCanvas,
+    DragCanvas,
+    ClickVertex,
+    DoubleClickVertex,
+    DragVertex,
+    ClickEdge,
+    DragEdge,
+}
+
+impl Gesture {
+    const ALL: [Gesture; 7] = [
+        Gesture::ClickCanvas,
+        Gesture::DragCanvas,
+        Gesture::ClickVertex,
+        Gesture::DoubleClickVertex,
+        Gesture::DragVertex,
+        Gesture::ClickEdge,
+        Gesture::DragEdge,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Gesture::ClickCanvas => "click canvas",
+            Gesture::DragCanvas => "drag canvas",
+            Gesture::ClickVertex => "click vertex",
+            Gesture::DoubleClickVertex => "double click vertex",
+            Gesture::DragVertex => "drag vertex",
+            Gesture::ClickEdge => "click edge",
+            Gesture::DragEdge => "drag edge",
+        }
+    }
+}
+
+/// A key or mouse gesture with exactly these modifiers held. Written like
+/// `Ctrl + Shift + Z` or `Ctrl + Left click vertex` in the keybindings file
+/// and shown the same way in the command palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    Key(Modifiers, KeyCode),
+    Gesture(Modifiers, PointerButton, Gesture),
+}
+
+impl Binding {
+    pub fn modifiers(&self) -> Modifiers {
+        match self {
+            Binding::Key(modifiers, _) | Binding::Gesture(modifiers, ..) => *modifiers,
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(modifiers, key) => write!(f, "{modifiers}{}", key_name(*key)),
+            Binding::Gesture(modifiers, button, gesture) => {
+                let button = match button {
+                    PointerButton::Primary => "Left",
+                    PointerButton::Secondary => "Right",
+                    PointerButton::Middle => "Middle",
+                };
+                write!(f, "{modifiers}{button} {}", gesture.name())
+            }
+        }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let last = parts.pop().filter(|last| !last.is_empty());
+        let Some(last) = last else {
+            return Err("missing key or gesture".to_string());
+        };
+
+        let mut modifiers = Modifiers::NONE;
+        if let Some(unknown) = parts.iter().find(|part| !modifiers.insert(part)) {
+            return Err(format!("unknown modifier `{unknown}`"));
+        }
+
+        let Some((button, gesture)) = last.split_once(char::is_whitespace) else {
+            return parse_key(last)
+                .map(|key| Binding::Key(modifiers, key))
+                .ok_or_else(|| format!("unknown key `{last}`"));
+        };
+        let button = match button.to_lowercase().as_str() {
+            "left" => PointerButton::Primary,
+            "right" => PointerButton::Secondary,
+            "middle" => PointerButton::Middle,
+            _ => return Err(format!("unknown mouse button `{button}`")),
+        };
+        let gesture_name = gesture.split_whitespace().collect::<Vec<_>>().join(" ");
+        Gesture::ALL
+            .into_iter()
+            .find(|gesture| gesture.name().eq_ignore_ascii_case(&gesture_name))
+            .map(|gesture| Binding::Gesture(modifiers, button, gesture))
+            .ok_or_else(|| format!("unknown gesture `{gesture}`"))
+    }
+}
+
+/// Names of the keys that can be bound, besides the letters and digits.
+const KEY_NAMES: [(&str, KeyCode); 30] = [
+    ("Esc", KeyCode::Escape),
+    ("Enter", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("Space", KeyCode::Space),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("ArrowUp", KeyCode::ArrowUp),
+    ("ArrowDown", KeyCode::ArrowDown),
+    ("ArrowLeft", KeyCode::ArrowLeft),
+    ("ArrowRight", KeyCode::ArrowRight),
+    ("Minus", KeyCode::Minus),
+    ("Equal", KeyCode::Equal),
+    ("Comma", KeyCode::Comma),
+    ("Period", KeyCode::Period),
+    ("Slash", KeyCode::Slash),
+    ("F1", KeyCode::F1),
+    ("F2", KeyCode::F2),
+    ("F3", KeyCode::F3),
+    ("F4", KeyCode::F4),
+    ("F5", KeyCode::F5),
+    ("F6", KeyCode::F6),
+    ("F7", KeyCode::F7),
+    ("F8", KeyCode::F8),
+    ("F9", KeyCode::F9),
+    ("F10", KeyCode::F10),
+];
+
+const LETTER_KEYS: [KeyCode; 26] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+];
+
+const DIGIT_KEYS: [KeyCode; 10] = [
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+/// `KeyX` and `Digit1` are shown as `X` and `1`.
+fn key_name(key: KeyCode) -> String {
+    if let Some(index) = LETTER_KEYS.iter().position(|letter| *letter == key) {
+        return char::from(b'A' + index as u8).to_string();
+    }
+    if let Some(index) = DIGIT_KEYS.iter().position(|digit| *digit == key) {
+        return index.to_string();
+    }
+    KEY_NAMES
+        .iter()
+        .find(|(_, named)| *named == key)
+        .map_or_else(|| format!("{key:?}"), |(name, _)| name.to_string())
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(single), None) = (chars.next(), chars.next()) {
+        let single = single.to_ascii_uppercase();
+        if single.is_ascii_uppercase() {
+            return Some(LETTER_KEYS[(single as u8 - b'A') as usize]);
+        }
+        if let Some(digit) = single.to_digit(10) {
+            return Some(DIGIT_KEYS[digit as usize]);
+        }
+    }
+    KEY_NAMES
+        .iter()
+        .find(|(named, _)| named.eq_ignore_ascii_case(name))
+        .map(|(_, key)| *key)
+}
+
+/// The keybindings file. Every listed action replaces its default
+/// bindings, an empty list unbinds it, e.g.
+/// `(bindings: { Redo: ["Ctrl + Y", "Ctrl + Shift + Z"] })`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct KeybindingsFile {
+    pub bindings: HashMap<EditorAction, Vec<String>>,
+}
+
+/// The bindings of every action. The shortcut system, the command
+/// palette and the modifier checks of the picking observers all
+/// look them up here, so they can't disagree.
+#[derive(Resource, Debug)]
+pub struct ActionRegistry {
+    bindings: HashMap<EditorAction, Vec<Binding>>,
+}
+
+impl Default for ActionRegistry {
+    fn default() -> Self {
+        use EditorAction::*;
+        let primary = Modifiers::PRIMARY;
+        let key = |modifiers, key| vec![Binding::Key(modifiers, key)];
+        let left =
+            |modifiers, gesture| vec![Binding::Gesture(modifiers, PointerButton::Primary, gesture)];
+
+        let bindings = EditorAction::ALL
+            .into_iter()
+            .map(|action| {
+                let bindings = match action {
+                    Undo => key(primary, KeyCode::KeyZ),
+                    Redo => vec![
+                        Binding::Key(primary, KeyCode::KeyY),
+                        Binding::Key(primary.with_shift(), KeyCode::KeyZ),
+                    ],
+                    Copy => key(primary, KeyCode::KeyC),
+                    Cut => key(primary, KeyCode::KeyX),
+                    Paste => key(primary, KeyCode::KeyV),
+                    Duplicate => key(primary, KeyCode::KeyD),
+                    SelectAll => key(primary, KeyCode::KeyA),
+                    ClearSelection => key(Modifiers::NONE, KeyCode::Escape),
+                    FrameAll => key(Modifiers::NONE, KeyCode::Home),
+                    FrameSelection => key(Modifiers::NONE, KeyCode::KeyF),
+                    Search => key(primary, KeyCode::KeyF),
+                    CommandPalette => key(primary.with_shift(), KeyCode::KeyP),
+                    Save => key(primary, KeyCode::KeyS),
+                    Open => key(primary, KeyCode::KeyO),
+                    Settings => key(primary, KeyCode::Comma),
+                    MergeVertices => key(primary, KeyCode::KeyM),
+                    Export(_)
+                    | Import(_)
+                    | ExportMetrics
+                    | Generate
+                    | ToggleStatistics
+                    | ToggleMetrics
+                    | ToggleInspector
+                    | ToggleMinimap
+                    | ToggleTextEditor
+                    | ToggleAdjacencyMatrix
+                    | ToggleTables => Vec::new(),
+                    SpawnVertex => left(Modifiers::NONE, Gesture::ClickCanvas),
+                    MoveVertex => left(Modifiers::NONE, Gesture::DragVertex),
+                    DrawEdge => vec![Binding::Gesture(
+                        Modifiers::NONE,
+                        PointerButton::Secondary,
+                        Gesture::DragVertex,
+                    )],
+                    RenameVertex => left(Modifiers::NONE, Gesture::DoubleClickVertex),
+                    DeleteVertex => left(primary, Gesture::ClickVertex),
+                    DeleteEdge => left(primary, Gesture::ClickEdge),
+                    SplitEdge => left(Modifiers::NONE, Gesture::ClickEdge),
+                    ReconnectEdge => left(Modifiers::NONE, Gesture::DragEdge),
+                    ContractEdge => left(Modifiers::ALT, Gesture::ClickEdge),
+                    ToggleSelected => left(Modifiers::SHIFT, Gesture::ClickVertex),
+                    RectangleSelection => left(Modifiers::SHIFT, Gesture::DragCanvas),
+                    LassoSelection => left(Modifiers::ALT, Gesture::DragCanvas),
+                };
+                (action, bindings)
+            })
+            .collect();
+
+        ActionRegistry { bindings }
+    }
+}
+
+impl ActionRegistry {
+    /// The defaults with the overrides of the keybindings file at `path`.
+    /// A missing file keeps the defaults, invalid bindings are skipped.
+    pub fn load(path: &str) -> Self {
+        let mut registry = ActionRegistry::default();
+        let file = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == ErrorKind::NotFound => return registry,
+            Err(error) => {
+                warn!("Could not read {path}: {error}");
+                return registry;
+            }
+        };
+        match ron::from_str::<KeybindingsFile>(&file) {
+            Ok(file) => registry.apply(file),
+            Err(error) => warn!("Could not parse {path}: {error}"),
+        }
+        for (binding, actions) in registry.conflicts() {
+            warn!("{binding} is bound to several actions: {actions:?}");
+        }
+        registry
+    }
+
+    fn apply(&mut self, file: KeybindingsFile) {
+        for (action, bindings) in file.bindings {
+            let bindings = bindings
+                .iter()
+                .filter_map(|text| {
+                    let binding = text.parse::<Binding>().and_then(|binding| {
+                        match (binding, action.gesture()) {
+                            (Binding::Key(..), Some(gesture)) => {
+                                Err(format!("expected a `{}` gesture", gesture.name()))
+                            }
+                            (Binding::Gesture(..), None) => Err("expected a key".to_string()),
+                            (Binding::Gesture(_, _, bound), Some(gesture)) if bound != gesture => {
+                                Err(format!("expected a `{}` gesture", gesture.name()))
+                            }
+                            _ => Ok(binding),
+                        }
+                    });
+                    binding
+                        .inspect_err(|error| warn!("Skipping {action:?} binding {text:?}: {error}"))
+                        .ok()
+                })
+                .collect();
+            self.bindings.insert(action, bindings);
+        }
+    }
+
+    pub fn bindings(&self, action: EditorAction) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Bindings shared by several actions, which all run at once.
+    pub fn conflicts(&self) -> Vec<(Binding, Vec<EditorAction>)> {
+        let mut bound: Vec<(Binding, Vec<EditorAction>)> = Vec::new();
+        for action in EditorAction::ALL {
+            for binding in self.bindings(action) {
+                match bound.iter_mut().find(|(other, _)| other == binding) {
+                    Some((_, actions)) => actions.push(action),
+                    None => bound.push((*binding, vec![action])),
+                }
+            }
+        }
+        bound.retain(|(_, actions)| actions.len() > 1);
+        bound
+    }
+
+    /// The actions with a key that was just pressed with exactly its modifiers.
+    pub fn just_pressed(&self, keyboard: &ButtonInput<KeyCode>) -> Vec<EditorAction> {
+        let held = Modifiers::held(keyboard);
+        EditorAction::ALL
+            .into_iter()
+            .filter(|action| {
+                self.bindings(*action).iter().any(|binding| {
+                    matches!(binding, Binding::Key(modifiers, key)
+                        if *modifiers == held && keyboard.just_pressed(*key))
+                })
+            })
+            .collect()
+    }
+
+    /// Whether exactly the modifiers of one of the action's bindings are
+    /// held, e.g. to show that clicking the hovered vertex deletes it.
+    pub fn modifiers_held(&self, action: EditorAction, keyboard: &ButtonInput<KeyCode>) -> bool {
+        let held = Modifiers::held(keyboard);
+        self.bindings(action)
+            .iter()
+            .any(|binding| binding.modifiers() == held)
+    }
+
+    /// Whether one of the action's gestures uses `button` while
+    /// exactly its modifiers are held.
+    pub fn gesture_held(
+        &self,
+        action: EditorAction,
+        button: PointerButton,
+        keyboard: &ButtonInput<KeyCode>,
+    ) -> bool {
+        let held = Modifiers::held(keyboard);
+        self.bindings(action).iter().any(|binding| {
+            matches!(binding, Binding::Gesture(modifiers, bound, _)
+                if *modifiers == held && *bound == button)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(file: &str) -> ActionRegistry {
+        let mut registry = ActionRegistry::default();
+        registry.apply(ron::from_str(file).unwrap());
+        registry
+    }
+
+    #[test]
+    fn default_bindings_round_trip() {
+        for action in EditorAction::ALL {
+            for binding in ActionRegistry::default().bindings(action) {
+                let text = binding.to_string();
+                assert_eq!(text.parse::<Binding>(), Ok(*binding), "{text}");
+            }
+        }
+    }
+
+    #[test]
+    fn bindings_parse_loosely() {
+        assert_eq!(
+            "ctrl+shift+z".parse(),
+            Ok(Binding::Key(Modifiers::CTRL.with_shift(), KeyCode::KeyZ))
+        );
+        assert_eq!(
+            " Alt +  Right   double click  VERTEX ".parse(),
+            Ok(Binding::Gesture(
+                Modifiers::ALT,
+                PointerButton::Secondary,
+                Gesture::DoubleClickVertex
+            ))
+        );
+        assert_eq!(
+            "esc".parse(),
+            Ok(Binding::Key(Modifiers::NONE, KeyCode::Escape))
+        );
+        assert_eq!(
+            "7".parse(),
+            Ok(Binding::Key(Modifiers::NONE, KeyCode::Digit7))
+        );
+    }
+
+    #[test]
+    fn bad_bindings_are_rejected() {
+        for (text, error) in [
+            ("", "missing key or gesture"),
+            ("Ctrl +", "missing key or gesture"),
+            ("Hyper + A", "unknown modifier `Hyper`"),
+            ("Ctrl + Pause", "unknown key `Pause`"),
+            ("Up click vertex", "unknown mouse button `Up`"),
+            ("Left poke vertex", "unknown gesture `poke vertex`"),
+        ] {
+            assert_eq!(text.parse::<Binding>(), Err(error.to_string()), "{text}");
+        }
+    }
+
+    #[test]
+    fn keybindings_file_replaces_defaults() {
+        let registry = registry(
+            r#"(bindings: {
+                Redo: ["Ctrl + Y", "Nonsense"],
+                DeleteVertex: ["Alt + Left click vertex"],
+                Copy: [],
+            })"#,
+        );
+        assert_eq!(
+            registry.bindings(EditorAction::Redo),
+            [Binding::Key(Modifiers::CTRL, KeyCode::KeyY)]
+        );
+        assert_eq!(
+            registry.bindings(EditorAction::DeleteVertex),
+            [Binding::Gesture(
+                Modifiers::ALT,
+                PointerButton::Primary,
+                Gesture::ClickVertex
+            )]
+        );
+        assert!(registry.bindings(EditorAction::Copy).is_empty());
+        assert_eq!(
+            registry.bindings(EditorAction::Undo),
+            ActionRegistry::default().bindings(EditorAction::Undo)
+        );
+    }
+
+    #[test]
+    fn gestures_keep_their_kind() {
+        let registry = registry(
+            r#"(bindings: {
+                DeleteVertex: ["Alt + Left click edge", "Ctrl + Right click vertex"],
+                SplitEdge: ["Ctrl + K"],
+                Undo: ["Left click canvas"],
+            })"#,
+        );
+        assert_eq!(
+            registry.bindings(EditorAction::DeleteVertex),
+            [Binding::Gesture(
+                Modifiers::CTRL,
+                PointerButton::Secondary,
+                Gesture::ClickVertex
+            )]
+        );
+        assert!(registry.bindings(EditorAction::SplitEdge).is_empty());
+        assert!(registry.bindings(EditorAction::Undo).is_empty());
+    }
+
+    #[test]
+    fn conflicts_are_found() {
+        assert!(ActionRegistry::default().conflicts().is_empty());
+        // Swapping two bindings is not a conflict.
+        let swapped = registry(r#"(bindings: { Undo: ["Ctrl + Y"], Redo: ["Ctrl + Z"] })"#);
+        assert!(swapped.conflicts().is_empty());
+
+        let undo = ActionRegistry::default().bindings(EditorAction::Undo)[0];
+        let delete_edge = ActionRegistry::default().bindings(EditorAction::DeleteEdge)[0];
+        let conflicting = registry(&format!(
+            r#"(bindings: {{ Copy: ["{undo}"], SplitEdge: ["{delete_edge}"] }})"#
+        ));
+        assert_eq!(
+            conflicting.conflicts(),
+            [
+                (undo, vec![EditorAction::Undo, EditorAction::Copy]),
+                (
+                    delete_edge,
+                    vec![EditorAction::DeleteEdge, EditorAction::SplitEdge]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn every_format_has_an_action() {
+        for format in ExportFormat::ALL {
+            assert!(EditorAction::ALL.contains(&EditorAction::Export(format)));
+        }
+        for format in ImportFormat::ALL {
+            assert!(EditorAction::ALL.contains(&EditorAction::Import(format)));
+        }
+        let mut names: Vec<_> = EditorAction::ALL.iter().map(EditorAction::name).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), EditorAction::ALL.len());
+
+        let registry =
+            registry(r#"(bindings: { Export(Svg): ["Ctrl + E"], Import(Mermaid): ["Ctrl + I"] })"#);
+        assert_eq!(
+            registry.bindings(EditorAction::Export(ExportFormat::Svg)),
+            [Binding::Key(Modifiers::CTRL, KeyCode::KeyE)]
+        );
+        assert_eq!(
+            registry.bindings(EditorAction::Import(ImportFormat::Mermaid)),
+            [Binding::Key(Modifiers::CTRL, KeyCode::KeyI)]
+        );
+    }
+}
@@ -0,0 +1,870 @@
+use std::collections::{HashMap, VecDeque};
+
+use bevy::prelude::*;
+
+use crate::graph::{
+    constants::{PAGERANK_DAMPING, PAGERANK_MAX_ITERATIONS, PAGERANK_TOLERANCE},
+    helpers::escape_csv_field,
+};
+
+/// An index based copy of the graph structure.
+/// The ECS is not really suited for graph algorithms, so
+/// whenever vertices or edges change, this is rebuilt and
+/// every algorithm works on it instead of the queries.
+#[derive(Resource, Default, Debug)]
+pub struct GraphSnapshot {
+    /// Increased on every rebuild, so consumers can
+    /// tell if their cached results are outdated.
+    pub revision: u64,
+    /// The vertex entities, their index is used everywhere else.
+    pub vertices: Vec<Entity>,
+    /// Reverse lookup of `vertices`.
+    pub index: HashMap<Entity, usize>,
+    /// Edges as (from, to) index pairs.
+    pub edges: Vec<(usize, usize)>,
+    /// Outgoing neighbours for each vertex.
+    pub out_adjacency: Vec<Vec<usize>>,
+    /// Incoming neighbours for each vertex.
+    pub in_adjacency: Vec<Vec<usize>>,
+}
+
+impl GraphSnapshot {
+    /// Edges whose vertices are not part of `vertices` are skipped,
+    /// as those are logically deleted ones waiting in the undo stack.
+    pub fn rebuild(
+        &mut self,
+        vertices: impl Iterator<Item = Entity>,
+        edges: impl Iterator<Item = (Entity, Entity)>,
+    ) {
+        self.revision += 1;
+        self.vertices = vertices.collect();
+        self.index = self
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(index, entity)| (*entity, index))
+            .collect();
+        self.edges = edges
+            .filter_map(|(from, to)| Some((*self.index.get(&from)?, *self.index.get(&to)?)))
+            .collect();
+
+        self.out_adjacency = vec![Vec::new(); self.vertices.len()];
+        self.in_adjacency = vec![Vec::new(); self.vertices.len()];
+        for &(from, to) in &self.edges {
+            self.out_adjacency[from].push(to);
+            self.in_adjacency[to].push(from);
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+}
+
+/// Every metric computed for a single vertex.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VertexMetrics {
+    pub in_degree: usize,
+    pub out_degree: usize,
+    pub degree: usize,
+    pub closeness: f32,
+    pub betweenness: f32,
+    pub pagerank: f32,
+}
+
+/// The metrics that can be selected for sorting
+/// or for mapping onto the canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Degree,
+    InDegree,
+    OutDegree,
+    Closeness,
+    Betweenness,
+    PageRank,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 6] = [
+        Metric::Degree,
+        Metric::InDegree,
+        Metric::OutDegree,
+        Metric::Closeness,
+        Metric::Betweenness,
+        Metric::PageRank,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::Degree => "Degree",
+            Metric::InDegree => "In-degree",
+            Metric::OutDegree => "Out-degree",
+            Metric::Closeness => "Closeness",
+            Metric::Betweenness => "Betweenness",
+            Metric::PageRank => "PageRank",
+        }
+    }
+
+    /// Degrees are whole numbers, the rest are ratios.
+    pub fn format(&self, metrics: &VertexMetrics) -> String {
+        match self {
+            Metric::Degree | Metric::InDegree | Metric::OutDegree => {
+                format!("{}", self.value(metrics))
+            }
+            _ => format!("{:.4}", self.value(metrics)),
+        }
+    }
+
+    pub fn value(&self, metrics: &VertexMetrics) -> f32 {
+        match self {
+            Metric::Degree => metrics.degree as f32,
+            Metric::InDegree => metrics.in_degree as f32,
+            Metric::OutDegree => metrics.out_degree as f32,
+            Metric::Closeness => metrics.closeness,
+            Metric::Betweenness => metrics.betweenness,
+            Metric::PageRank => metrics.pagerank,
+        }
+    }
+}
+
+/// Computes all the metrics, the result is indexed the same
+/// way as the snapshot vertices.
+pub fn vertex_metrics(snapshot: &GraphSnapshot) -> Vec<VertexMetrics> {
+    let closeness = closeness_centrality(snapshot);
+    let betweenness = betweenness_centrality(snapshot);
+    let pagerank = pagerank(snapshot);
+
+    (0..snapshot.vertex_count())
+        .map(|vertex| {
+            let in_degree = snapshot.in_adjacency[vertex].len();
+            let out_degree = snapshot.out_adjacency[vertex].len();
+            VertexMetrics {
+                in_degree,
+                out_degree,
+                degree: in_degree + out_degree,
+                closeness: closeness[vertex],
+                betweenness: betweenness[vertex],
+                pagerank: pagerank[vertex],
+            }
+        })
+        .collect()
+}
+
+/// One row per vertex with a header, ready to be opened
+/// next to an exported graph.
+pub fn metrics_to_csv<'a>(rows: impl Iterator<Item = (&'a str, &'a VertexMetrics)>) -> String {
+    let mut csv = String::from("label");
+    for metric in Metric::ALL {
+        csv.push(',');
+        csv.push_str(metric.name());
+    }
+    csv.push('\n');
+
+    for (label, metrics) in rows {
+        csv.push_str(&escape_csv_field(label));
+        for metric in Metric::ALL {
+            csv.push(',');
+            csv.push_str(&metric.format(metrics));
+        }
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// Unweighted shortest path lengths following edge directions.
+/// Unreachable vertices are `None`.
+pub fn bfs_distances(adjacency: &[Vec<usize>], source: usize) -> Vec<Option<usize>> {
+    let mut distances = vec![None; adjacency.len()];
+    let mut queue = VecDeque::from([source]);
+    distances[source] = Some(0);
+
+    while let Some(vertex) = queue.pop_front() {
+        let distance = distances[vertex].unwrap_or_default();
+        for &neighbour in &adjacency[vertex] {
+            if distances[neighbour].is_none() {
+                distances[neighbour] = Some(distance + 1);
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    distances
+}
+
+/// Closeness based on outgoing distances. Graphs are rarely strongly
+/// connected while drawing, so the Wasserman-Faust variant is used,
+/// which scales by the fraction of reachable vertices.
+pub fn closeness_centrality(snapshot: &GraphSnapshot) -> Vec<f32> {
+    let n = snapshot.vertex_count();
+    if n < 2 {
+        return vec![0.0; n];
+    }
+
+    (0..n)
+        .map(|source| {
+            let distances = bfs_distances(&snapshot.out_adjacency, source);
+            let (reachable, total) = distances
+                .iter()
+                .flatten()
+                .filter(|distance| **distance > 0)
+                .fold((0usize, 0usize), |(count, sum), distance| {
+                    (count + 1, sum + distance)
+                });
+            if total == 0 {
+                return 0.0;
+            }
+            let reachable = reachable as f32;
+            (reachable / total as f32) * (reachable / (n - 1) as f32)
+        })
+        .collect()
+}
+
+/// Brandes' algorithm on the directed, unweighted graph,
+/// normalized by the number of ordered vertex pairs.
+pub fn betweenness_centrality(snapshot: &GraphSnapshot) -> Vec<f32> {
+    let n = snapshot.vertex_count();
+    let mut centrality = vec![0.0f64; n];
+
+    for source in 0..n {
+        let mut stack = Vec::with_capacity(n);
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut path_counts = vec![0.0f64; n];
+        let mut distances: Vec<Option<usize>> = vec![None; n];
+        let mut queue = VecDeque::from([source]);
+        path_counts[source] = 1.0;
+        distances[source] = Some(0);
+
+        while let Some(vertex) = queue.pop_front() {
+            stack.push(vertex);
+            let distance = distances[vertex].unwrap_or_default();
+            for &neighbour in &snapshot.out_adjacency[vertex] {
+                if distances[neighbour].is_none() {
+                    distances[neighbour] = Some(distance + 1);
+                    queue.push_back(neighbour);
+                }
+                if distances[neighbour] == Some(distance + 1) {
+                    path_counts[neighbour] += path_counts[vertex];
+                    predecessors[neighbour].push(vertex);
+                }
+            }
+        }
+
+        let mut dependencies = vec![0.0f64; n];
+        while let Some(vertex) = stack.pop() {
+            for &predecessor in &predecessors[vertex] {
+                dependencies[predecessor] +=
+                    path_counts[predecessor] / path_counts[vertex] * (1.0 + dependencies[vertex]);
+            }
+            if vertex != source {
+                centrality[vertex] += dependencies[vertex];
+            }
+        }
+    }
+
+    let scale = if n > 2 {
+        1.0 / ((n - 1) * (n - 2)) as f64
+    } else {
+        1.0
+    };
+    centrality
+        .into_iter()
+        .map(|value| (value * scale) as f32)
+        .collect()
+}
+
+/// Power iteration PageRank. Vertices without outgoing edges
+/// spread their rank evenly so the total stays 1.
+pub fn pagerank(snapshot: &GraphSnapshot) -> Vec<f32> {
+    let n = snapshot.vertex_count();
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let uniform = 1.0 / n as f64;
+    let mut ranks = vec![uniform; n];
+
+    for _ in 0..PAGERANK_MAX_ITERATIONS {
+        let dangling: f64 = (0..n)
+            .filter(|vertex| snapshot.out_adjacency[*vertex].is_empty())
+            .map(|vertex| ranks[vertex])
+            .sum();
+        let base = (1.0 - PAGERANK_DAMPING) * uniform + PAGERANK_DAMPING * dangling * uniform;
+        let mut next = vec![base; n];
+
+        for (vertex, neighbours) in snapshot.out_adjacency.iter().enumerate() {
+            if neighbours.is_empty() {
+                continue;
+            }
+            let share = PAGERANK_DAMPING * ranks[vertex] / neighbours.len() as f64;
+            for &neighbour in neighbours {
+                next[neighbour] += share;
+            }
+        }
+
+        let change: f64 = next
+            .iter()
+            .zip(&ranks)
+            .map(|(next, previous)| (next - previous).abs())
+            .sum();
+        ranks = next;
+        if change < PAGERANK_TOLERANCE {
+            break;
+        }
+    }
+
+    ranks.into_iter().map(|rank| rank as f32).collect()
+}
+
+/// Structural statistics that are linear in the size of the graph,
+/// so they are cheap enough to recompute on every change.
+#[derive(Debug, Clone, Default)]
+pub struct GraphStatistics {
+    pub vertex_count: usize,
+    pub edge_count: usize,
+    pub density: f32,
+    pub weak_components: usize,
+    pub strong_components: usize,
+    pub is_dag: bool,
+    /// Forest and tree ignore the edge directions.
+    pub is_forest: bool,
+    pub is_tree: bool,
+    /// The number of vertices for each total degree.
+    pub degree_histogram: Vec<usize>,
+}
+
+pub fn graph_statistics(snapshot: &GraphSnapshot) -> GraphStatistics {
+    let n = snapshot.vertex_count();
+    let m = snapshot.edges.len();
+    let weak_components = weakly_connected_components(snapshot);
+    let strong_components = strongly_connected_components(snapshot)
+        .into_iter()
+        .max()
+        .map_or(0, |last| last + 1);
+    let has_self_loop = snapshot.edges.iter().any(|(from, to)| from == to);
+
+    let mut degree_histogram = Vec::new();
+    for vertex in 0..n {
+        let degree = snapshot.out_adjacency[vertex].len() + snapshot.in_adjacency[vertex].len();
+        if degree_histogram.len() <= degree {
+            degree_histogram.resize(degree + 1, 0);
+        }
+        degree_histogram[degree] += 1;
+    }
+
+    // Any cycle of the underlying multigraph, even a loop or a
+    // parallel edge, adds an edge above `n - components`.
+    let is_forest = m + weak_components == n;
+
+    GraphStatistics {
+        vertex_count: n,
+        edge_count: m,
+        density: if n > 1 {
+            m as f32 / (n * (n - 1)) as f32
+        } else {
+            0.0
+        },
+        weak_components,
+        strong_components,
+        is_dag: strong_components == n && !has_self_loop,
+        is_forest,
+        is_tree: is_forest && weak_components == 1,
+        degree_histogram,
+    }
+}
+
+/// Union-find over the edges, ignoring their direction.
+pub fn weakly_connected_components(snapshot: &GraphSnapshot) -> usize {
+    fn find(parents: &mut [usize], mut vertex: usize) -> usize {
+        while parents[vertex] != vertex {
+            parents[vertex] = parents[parents[vertex]];
+            vertex = parents[vertex];
+        }
+        vertex
+    }
+
+    let n = snapshot.vertex_count();
+    let mut parents: Vec<usize> = (0..n).collect();
+    let mut components = n;
+    for &(from, to) in &snapshot.edges {
+        let (from_root, to_root) = (find(&mut parents, from), find(&mut parents, to));
+        if from_root != to_root {
+            parents[from_root] = to_root;
+            components -= 1;
+        }
+    }
+
+    components
+}
+
+/// Iterative Tarjan, returns the component index of every vertex.
+/// Recursion would overflow the stack on long paths.
+pub fn strongly_connected_components(snapshot: &GraphSnapshot) -> Vec<usize> {
+    let n = snapshot.vertex_count();
+    let mut indices: Vec<Option<usize>> = vec![None; n];
+    let mut low_links = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = Vec::new();
+    let mut components = vec![0; n];
+    let mut next_index = 0;
+    let mut next_component = 0;
+
+    for root in 0..n {
+        if indices[root].is_some() {
+            continue;
+        }
+
+        // (vertex, position of the next neighbour to visit)
+        let mut call_stack = vec![(root, 0)];
+        while let Some((vertex, neighbour_position)) = call_stack.pop() {
+            if neighbour_position == 0 {
+                indices[vertex] = Some(next_index);
+                low_links[vertex] = next_index;
+                next_index += 1;
+                stack.push(vertex);
+                on_stack[vertex] = true;
+            }
+
+            let neighbours = &snapshot.out_adjacency[vertex];
+            if let Some(&neighbour) = neighbours.get(neighbour_position) {
+                call_stack.push((vertex, neighbour_position + 1));
+                match indices[neighbour] {
+                    None => call_stack.push((neighbour, 0)),
+                    Some(index) if on_stack[neighbour] => {
+                        low_links[vertex] = low_links[vertex].min(index);
+                    }
+                    Some(_) => {}
+                }
+                continue;
+            }
+
+            if Some(low_links[vertex]) == indices[vertex] {
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    components[member] = next_component;
+                    if member == vertex {
+                        break;
+                    }
+                }
+                next_component += 1;
+            }
+
+            if let Some(&(parent, _)) = call_stack.last() {
+                low_links[parent] = low_links[parent].min(low_links[vertex]);
+            }
+        }
+    }
+
+    components
+}
+
+/// Distance based statistics need a BFS from every vertex, so they
+/// are accumulated a few sources at a time over multiple frames.
+/// Distances follow the edge directions and only reachable pairs count.
+#[derive(Debug, Clone, Default)]
+pub struct DistanceStatistics {
+    pub next_source: usize,
+    pub diameter: usize,
+    pub radius: Option<usize>,
+    pub path_length_sum: u64,
+    pub path_count: u64,
+}
+
+impl DistanceStatistics {
+    pub fn is_complete(&self, snapshot: &GraphSnapshot) -> bool {
+        self.next_source >= snapshot.vertex_count()
+    }
+
+    /// Runs the BFS of the next source vertex.
+    pub fn step(&mut self, snapshot: &GraphSnapshot) {
+        if self.is_complete(snapshot) {
+            return;
+        }
+
+        let distances = bfs_distances(&snapshot.out_adjacency, self.next_source);
+        let mut eccentricity = 0;
+        for &distance in distances.iter().flatten().filter(|distance| **distance > 0) {
+            eccentricity = eccentricity.max(distance);
+            self.path_length_sum += distance as u64;
+            self.path_count += 1;
+        }
+
+        self.diameter = self.diameter.max(eccentricity);
+        if eccentricity > 0 {
+            self.radius = Some(
+                self.radius
+                    .map_or(eccentricity, |radius| radius.min(eccentricity)),
+            );
+        }
+        self.next_source += 1;
+    }
+
+    pub fn average_path_length(&self) -> Option<f32> {
+        (self.path_count > 0).then(|| self.path_length_sum as f32 / self.path_count as f32)
+    }
+}
+
+/// How the rows and columns of the adjacency matrix are ordered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatrixOrdering {
+    #[default]
+    Label,
+    Degree,
+    ReverseCuthillMcKee,
+}
+
+impl MatrixOrdering {
+    pub const ALL: [MatrixOrdering; 3] = [
+        MatrixOrdering::Label,
+        MatrixOrdering::Degree,
+        MatrixOrdering::ReverseCuthillMcKee,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MatrixOrdering::Label => "Label",
+            MatrixOrdering::Degree => "Degree",
+            MatrixOrdering::ReverseCuthillMcKee => "Reverse Cuthill–McKee",
+        }
+    }
+}
+
+/// Orders the vertices so edges end up close to the diagonal of the
+/// adjacency matrix, which makes clusters and chains stand out. Edge
+/// directions are ignored. Every component is walked breadth first from
+/// its vertex of lowest degree, visiting neighbours by increasing degree.
+pub fn reverse_cuthill_mckee(snapshot: &GraphSnapshot) -> Vec<usize> {
+    let n = snapshot.vertex_count();
+    let neighbours: Vec<Vec<usize>> = (0..n)
+        .map(|vertex| {
+            let mut neighbours: Vec<usize> = snapshot.out_adjacency[vertex]
+                .iter()
+                .chain(&snapshot.in_adjacency[vertex])
+                .copied()
+                .filter(|neighbour| *neighbour != vertex)
+                .collect();
+            neighbours.sort_unstable();
+            neighbours.dedup();
+            neighbours
+        })
+        .collect();
+    let degree = |vertex: usize| neighbours[vertex].len();
+
+    let mut starts: Vec<usize> = (0..n).collect();
+    starts.sort_by_key(|vertex| degree(*vertex));
+
+    let mut order = Vec::with_capacity(n);
+    let mut visited = vec![false; n];
+    for start in starts {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut queue = VecDeque::from([start]);
+        while let Some(vertex) = queue.pop_front() {
+            order.push(vertex);
+            let mut next: Vec<usize> = neighbours[vertex]
+                .iter()
+                .copied()
+                .filter(|neighbour| !visited[*neighbour])
+                .collect();
+            next.sort_by_key(|neighbour| degree(*neighbour));
+            for neighbour in next {
+                visited[neighbour] = true;
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    order.reverse();
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(n: usize, edges: &[(usize, usize)]) -> GraphSnapshot {
+        let mut world = World::new();
+        let vertices: Vec<Entity> = (0..n).map(|_| world.spawn_empty().id()).collect();
+        let mut snapshot = GraphSnapshot::default();
+        snapshot.rebuild(
+            vertices.iter().copied(),
+            edges
+                .iter()
+                .map(|&(from, to)| (vertices[from], vertices[to])),
+        );
+        snapshot
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!(
+                (actual - expected).abs() < 1e-4,
+                "{actual:?} != {expected:?}"
+            );
+        }
+    }
+
+    /// 0 -> 1 -> 2
+    fn path() -> GraphSnapshot {
+        snapshot(3, &[(0, 1), (1, 2)])
+    }
+
+    /// 0 in the centre, with an edge to and from each of 1, 2 and 3.
+    fn star() -> GraphSnapshot {
+        snapshot(4, &[(0, 1), (1, 0), (0, 2), (2, 0), (0, 3), (3, 0)])
+    }
+
+    /// 0 -> 1 -> 2 -> 0
+    fn cycle() -> GraphSnapshot {
+        snapshot(3, &[(0, 1), (1, 2), (2, 0)])
+    }
+
+    #[test]
+    fn closeness_scales_by_the_reachable_fraction() {
+        // 0 reaches both others at 1 and 2, 1 only reaches 2 of 2 others.
+        assert_close(&closeness_centrality(&path()), &[2.0 / 3.0, 0.5, 0.0]);
+        // The centre is next to everyone, leaves are 1, 2 and 2 away.
+        assert_close(&closeness_centrality(&star()), &[1.0, 0.6, 0.6, 0.6]);
+        assert_close(&closeness_centrality(&cycle()), &[2.0 / 3.0; 3]);
+        assert_close(&closeness_centrality(&snapshot(1, &[])), &[0.0]);
+    }
+
+    #[test]
+    fn betweenness_counts_ordered_pairs() {
+        // Only 0 -> 2 passes through 1, out of 2 * 1 pairs without it.
+        assert_close(&betweenness_centrality(&path()), &[0.0, 0.5, 0.0]);
+        // All 6 ordered pairs of leaves go through the centre.
+        assert_close(&betweenness_centrality(&star()), &[1.0, 0.0, 0.0, 0.0]);
+        assert_close(&betweenness_centrality(&cycle()), &[0.5; 3]);
+    }
+
+    #[test]
+    fn pagerank_spreads_dangling_rank() {
+        assert_close(&pagerank(&cycle()), &[1.0 / 3.0; 3]);
+
+        // The leaves only link back to the centre: r = (1 - d) / 4 + d * r0 / 3
+        // for each leaf and r0 = (1 - d) / 4 + d * 3r for the centre.
+        let ranks = pagerank(&star());
+        assert_close(&ranks, &[0.479730, 0.173423, 0.173423, 0.173423]);
+
+        // Dangling leaves give their rank to everyone, so the centre gets
+        // r0 = (1 - d) / 4 + d * (1 - r0) / 4, i.e. 1 / (4 + d).
+        let out_star = snapshot(4, &[(0, 1), (0, 2), (0, 3)]);
+        let centre = 1.0 / (4.0 + PAGERANK_DAMPING as f32);
+        let leaf = (1.0 - centre) / 3.0;
+        assert_close(&pagerank(&out_star), &[centre, leaf, leaf, leaf]);
+        assert!(pagerank(&snapshot(0, &[])).is_empty());
+    }
+
+    #[test]
+    fn parallel_edges() {
+        // A second 0 -> 1 doubles the shortest paths through 1 and back
+        // to 0 alike, so the betweenness and closeness stay the same.
+        let doubled = snapshot(3, &[(0, 1), (0, 1), (1, 2)]);
+        assert_close(&betweenness_centrality(&doubled), &[0.0, 0.5, 0.0]);
+        assert_close(&closeness_centrality(&doubled), &[2.0 / 3.0, 0.5, 0.0]);
+
+        // PageRank follows each edge, 1 gets two thirds of what 0 passes on.
+        let split = snapshot(3, &[(0, 1), (0, 1), (0, 2)]);
+        let ranks = pagerank(&split);
+        let passed_on = PAGERANK_DAMPING as f32 * ranks[0];
+        assert!((ranks[1] - ranks[2] - passed_on / 3.0).abs() < 1e-4);
+        assert!((ranks.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+
+        let metrics = vertex_metrics(&split);
+        let degrees: Vec<_> = metrics
+            .iter()
+            .map(|metrics| (metrics.in_degree, metrics.out_degree, metrics.degree))
+            .collect();
+        assert_eq!(degrees, [(0, 3, 3), (2, 0, 2), (1, 0, 1)]);
+        assert_close(
+            &metrics
+                .iter()
+                .map(|metrics| metrics.pagerank)
+                .collect::<Vec<_>>(),
+            &ranks,
+        );
+    }
+
+    #[test]
+    fn vertex_metrics_of_a_star() {
+        let metrics = vertex_metrics(&star());
+        assert_eq!(metrics[0].degree, 6);
+        assert_eq!(metrics[1].in_degree, 1);
+        assert_eq!(metrics[1].out_degree, 1);
+        assert_close(&[metrics[0].closeness, metrics[0].betweenness], &[1.0, 1.0]);
+        assert_close(&[metrics[3].closeness, metrics[3].betweenness], &[0.6, 0.0]);
+        assert_eq!(Metric::Closeness.format(&metrics[3]), "0.6000");
+        assert_eq!(Metric::Degree.format(&metrics[0]), "6");
+    }
+
+    /// Whether two vertices got the same strong component, regardless of its index.
+    fn same_components(components: &[usize]) -> Vec<Vec<bool>> {
+        components
+            .iter()
+            .map(|a| components.iter().map(|b| a == b).collect())
+            .collect()
+    }
+
+    #[test]
+    fn strong_components_group_cycles() {
+        // A cycle 0 -> 1 -> 2 -> 0, a tail to 3 and a loop on 4.
+        let components =
+            strongly_connected_components(&snapshot(5, &[(0, 1), (1, 2), (2, 0), (2, 3), (4, 4)]));
+        let same = same_components(&components);
+        assert!(same[0][1] && same[1][2]);
+        assert!(!same[0][3] && !same[0][4] && !same[3][4]);
+        assert_eq!(components.iter().max(), Some(&2));
+
+        assert_eq!(
+            strongly_connected_components(&path()).iter().max(),
+            Some(&2)
+        );
+        assert_eq!(strongly_connected_components(&star()), [0; 4]);
+        assert!(strongly_connected_components(&snapshot(0, &[])).is_empty());
+    }
+
+    #[test]
+    fn strong_components_survive_long_paths() {
+        let n = 100_000;
+        let edges: Vec<_> = (1..n).map(|vertex| (vertex - 1, vertex)).collect();
+        let components = strongly_connected_components(&snapshot(n, &edges));
+        assert_eq!(components.iter().max(), Some(&(n - 1)));
+
+        let mut edges = edges;
+        edges.push((n - 1, 0));
+        assert!(
+            strongly_connected_components(&snapshot(n, &edges))
+                .iter()
+                .all(|component| *component == 0)
+        );
+    }
+
+    #[test]
+    fn weak_components_ignore_directions() {
+        assert_eq!(
+            weakly_connected_components(&snapshot(6, &[(0, 1), (2, 1), (3, 4)])),
+            3
+        );
+        assert_eq!(weakly_connected_components(&cycle()), 1);
+        assert_eq!(weakly_connected_components(&snapshot(2, &[(0, 0)])), 2);
+        assert_eq!(weakly_connected_components(&snapshot(0, &[])), 0);
+    }
+
+    #[test]
+    fn statistics_of_a_path() {
+        let statistics = graph_statistics(&path());
+        assert_eq!(statistics.vertex_count, 3);
+        assert_eq!(statistics.edge_count, 2);
+        assert_close(&[statistics.density], &[2.0 / 6.0]);
+        assert_eq!(statistics.weak_components, 1);
+        assert_eq!(statistics.strong_components, 3);
+        assert!(statistics.is_dag && statistics.is_forest && statistics.is_tree);
+        assert_eq!(statistics.degree_histogram, [0, 2, 1]);
+    }
+
+    #[test]
+    fn statistics_flags() {
+        let cycle = graph_statistics(&cycle());
+        assert_eq!(cycle.strong_components, 1);
+        assert!(!cycle.is_dag && !cycle.is_forest && !cycle.is_tree);
+
+        // Edges in both directions are a cycle of the underlying multigraph.
+        let star = graph_statistics(&star());
+        assert!(!star.is_dag && !star.is_forest);
+        assert_eq!(star.degree_histogram, [0, 0, 3, 0, 0, 0, 1]);
+
+        let forest = graph_statistics(&snapshot(4, &[(0, 1), (2, 3)]));
+        assert_eq!(forest.weak_components, 2);
+        assert!(forest.is_dag && forest.is_forest && !forest.is_tree);
+
+        let self_loop = graph_statistics(&snapshot(2, &[(0, 0)]));
+        assert_eq!(self_loop.strong_components, 2);
+        assert!(!self_loop.is_dag && !self_loop.is_forest);
+
+        let parallel = graph_statistics(&snapshot(2, &[(0, 1), (0, 1)]));
+        assert!(parallel.is_dag && !parallel.is_forest);
+
+        let empty = graph_statistics(&snapshot(0, &[]));
+        assert_eq!(empty.density, 0.0);
+        assert!(empty.is_dag && empty.is_forest && !empty.is_tree);
+    }
+
+    #[test]
+    fn distances_of_a_path() {
+        let path = path();
+        let mut distances = DistanceStatistics::default();
+        while !distances.is_complete(&path) {
+            distances.step(&path);
+        }
+        assert_eq!(distances.diameter, 2);
+        // The sink has no eccentricity, so it does not count for the radius.
+        assert_eq!(distances.radius, Some(1));
+        assert_eq!((distances.path_length_sum, distances.path_count), (4, 3));
+        assert_close(&[distances.average_path_length().unwrap()], &[4.0 / 3.0]);
+
+        let empty = snapshot(2, &[]);
+        let mut distances = DistanceStatistics::default();
+        distances.step(&empty);
+        distances.step(&empty);
+        assert!(distances.is_complete(&empty));
+        assert_eq!((distances.diameter, distances.radius), (0, None));
+        assert_eq!(distances.average_path_length(), None);
+    }
+
+    #[test]
+    fn stepping_matches_all_pairs_distances() {
+        let graph = snapshot(
+            7,
+            &[
+                (0, 1),
+                (1, 2),
+                (2, 0),
+                (2, 3),
+                (3, 4),
+                (4, 5),
+                (5, 3),
+                (1, 5),
+                (6, 0),
+            ],
+        );
+
+        let mut stepped = DistanceStatistics::default();
+        while !stepped.is_complete(&graph) {
+            stepped.step(&graph);
+        }
+        let before = stepped.clone();
+        stepped.step(&graph);
+        assert_eq!(stepped.next_source, before.next_source);
+        assert_eq!(stepped.path_count, before.path_count);
+
+        let eccentricities: Vec<usize> = (0..graph.vertex_count())
+            .map(|source| {
+                bfs_distances(&graph.out_adjacency, source)
+                    .into_iter()
+                    .flatten()
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let distances: Vec<usize> = (0..graph.vertex_count())
+            .flat_map(|source| bfs_distances(&graph.out_adjacency, source))
+            .flatten()
+            .filter(|distance| *distance > 0)
+            .collect();
+
+        assert_eq!(stepped.diameter, *eccentricities.iter().max().unwrap());
+        assert_eq!(
+            stepped.radius,
+            eccentricities.iter().copied().filter(|e| *e > 0).min()
+        );
+        assert_eq!(stepped.path_count, distances.len() as u64);
+        assert_eq!(
+            stepped.path_length_sum,
+            distances.iter().sum::<usize>() as u64
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+
+use crate::graph::{
+    components::{Position, Vertex},
+    constants::{
+        BENCHMARK_DRAG_RADIUS, BENCHMARK_EDGES, BENCHMARK_FRAMES, BENCHMARK_OUTPUT_PATH,
+        BENCHMARK_SEED, BENCHMARK_VERTICES, BENCHMARK_WARMUP_FRAMES,
+    },
+    custom_observers::spawn_document,
+    document::{EdgeRecord, GraphDocument, VertexRecord},
+    generators::benchmark_graph,
+    helpers::{bounding_rect, frame_rect},
+    resources::GraphAssets,
+};
+
+/// The benchmark first lets the app settle, then measures frames
+/// without any changes, then while a vertex is dragged around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BenchmarkPhase {
+    Warmup,
+    Idle,
+    Dragging,
+}
+
+#[derive(Resource, Debug)]
+struct BenchmarkState {
+    phase: BenchmarkPhase,
+    frame: usize,
+    idle: Vec<f32>,
+    dragging: Vec<f32>,
+    /// The dragged vertex and where it started.
+    dragged: Option<(Entity, Vec2)>,
+    /// Bounding box of the generated graph, for framing the camera.
+    bounds: Option<Rect>,
+}
+
+/// Spawns a large generated graph, records frame times and appends
+/// them to `BENCHMARK_OUTPUT_PATH` before quitting, so runs before and
+/// after a change can be compared.
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(BenchmarkState {
+        phase: BenchmarkPhase::Warmup,
+        frame: 0,
+        idle: Vec::with_capacity(BENCHMARK_FRAMES),
+        dragging: Vec::with_capacity(BENCHMARK_FRAMES),
+        dragged: None,
+        bounds: None,
+    })
+    .add_systems(Startup, spawn_benchmark_graph)
+    .add_systems(Update, (frame_benchmark_graph, record_benchmark_frame));
+}
+
+fn spawn_benchmark_graph(
+    mut commands: Commands,
+    mut assets: GraphAssets,
+    mut state: ResMut<BenchmarkState>,
+) {
+    let graph = benchmark_graph(BENCHMARK_VERTICES, BENCHMARK_EDGES, BENCHMARK_SEED);
+    let document = GraphDocument {
+        vertices: graph
+            .positions
+            .iter()
+            .enumerate()
+            .map(|(index, position)| VertexRecord {
+                label: index.to_string(),
+                x: position.x,
+                y: position.y,
+                ..default()
+            })
+            .collect(),
+        edges: graph
+            .edges
+            .iter()
+            .map(|(from, to)| EdgeRecord {
+                from: *from,
+                to: *to,
+                weight: None,
+                attributes: Vec::new(),
+                style: None,
+            })
+            .collect(),
+    };
+
+    let spawned = spawn_document(&mut commands, &mut assets, &document, Vec2::ZERO, false);
+    state.dragged = spawned
+        .vertices
+        .get(spawned.vertices.len() / 2)
+        .map(|vertex| (vertex.entity, vertex.position));
+    state.bounds = bounding_rect(graph.positions.iter().copied());
+    info!(
+        "Benchmark graph with {} vertices and {} edges",
+        document.vertices.len(),
+        document.edges.len()
+    );
+}
+
+/// Zooms out until the whole graph fits the window, so everything is drawn.
+fn frame_benchmark_graph(
+    mut done: Local<bool>,
+    state: Res<BenchmarkState>,
+    window: Single<&Window>,
+    camera: Single<(&mut Transform, &mut Projection), With<Camera2d>>,
+) {
+    if *done {
+        return;
+    }
+    if let Some(bounds) = state.bounds {
+        let (mut transform, mut projection) = camera.into_inner();
+        frame_rect(bounds, window.size(), &mut transform, &mut projection);
+    }
+    *done = true;
+}
+
+fn record_benchmark_frame(
+    time: Res<Time>,
+    mut state: ResMut<BenchmarkState>,
+    mut positions: Query<&mut Position, With<Vertex>>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    let frame_time = time.delta_secs() * 1000.0;
+    state.frame += 1;
+
+    match state.phase {
+        BenchmarkPhase::Warmup => {
+            if state.frame >= BENCHMARK_WARMUP_FRAMES {
+                state.phase = BenchmarkPhase::Idle;
+                state.frame = 0;
+            }
+        }
+        BenchmarkPhase::Idle => {
+            state.idle.push(frame_time);
+            if state.frame >= BENCHMARK_FRAMES {
+                state.phase = BenchmarkPhase::Dragging;
+                state.frame = 0;
+            }
+        }
+        BenchmarkPhase::Dragging => {
+            state.dragging.push(frame_time);
+            if let Some((entity, start)) = state.dragged
+                && let Ok(mut position) = positions.get_mut(entity)
+            {
+                let angle = state.frame as f32 / 30.0;
+                position.0 = start + Vec2::from_angle(angle) * BENCHMARK_DRAG_RADIUS;
+            }
+            if state.frame >= BENCHMARK_FRAMES {
+                write_benchmark_report(&state);
+                exit.write(AppExit::Success);
+            }
+        }
+    }
+}
+
+fn write_benchmark_report(state: &BenchmarkState) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let line = format!(
+        "{timestamp} vertices={BENCHMARK_VERTICES} edges={BENCHMARK_EDGES} idle: {} | dragging: {}",
+        frame_time_summary(&state.idle),
+        frame_time_summary(&state.dragging),
+    );
+    info!("{line}");
+
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(BENCHMARK_OUTPUT_PATH)
+        .and_then(|mut file| writeln!(file, "{line}"));
+    if let Err(error) = written {
+        error!("Could not write {BENCHMARK_OUTPUT_PATH}: {error}");
+    }
+}
+
+/// Mean, median, 95th percentile and worst frame time in milliseconds.
+fn frame_time_summary(frame_times: &[f32]) -> String {
+    if frame_times.is_empty() {
+        return "no frames".to_string();
+    }
+    let mut sorted = frame_times.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let percentile = |p: f32| sorted[((sorted.len() - 1) as f32 * p).round() as usize];
+    let mean = sorted.iter().sum::<f32>() / sorted.len() as f32;
+    format!(
+        "mean {mean:.2} ms, p50 {:.2} ms, p95 {:.2} ms, max {:.2} ms",
+        percentile(0.5),
+        percentile(0.95),
+        sorted[sorted.len() - 1],
+    )
+}
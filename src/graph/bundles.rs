@@ -1,15 +1,11 @@
 use bevy::prelude::*;
 
 use crate::graph::{
-    components::{ClickTracker, DirectedEdge, Position, Vertex},
-    constants::{
-        EDGE_COLOR, EDGE_SHAPE, VERTEX_COLOR, VERTEX_LABEL_FONT_SIZE, VERTEX_SHAPE, VERTEX_SIZE,
-        VERTEX_TEXT_Z,
-    },
-    picking_observers::{
-        on_edge_clicked, on_edge_hovered, on_edge_out, on_vertex_clicked, on_vertex_dragged,
-        on_vertex_dragging, on_vertex_drop, on_vertex_hovered, on_vertex_out,
-    },
+    components::{ClickTracker, DirectedEdge, Position, Vertex, VertexShape},
+    constants::VERTEX_TEXT_Z,
+    helpers::vertex_label_offset,
+    resources::{GraphAssets, MeshKey},
+    settings::Settings,
 };
 
 /// A bundle for spawning a vertex in the graph.
@@ -24,85 +20,110 @@ pub struct VertexBundle {
 }
 
 impl VertexBundle {
-    /// Vertices are spawned at a given location. Meshes and materials are fix.
-    pub fn new(
-        meshes: &mut Assets<Mesh>,
-        materials: &mut Assets<ColorMaterial>,
-        position: Vec2,
-    ) -> Self {
+    /// Vertices are spawned at a given location. Meshes and materials are fix,
+    /// and shared by every vertex through the `HandleCache`.
+    pub fn new(assets: &mut GraphAssets, position: Vec2) -> Self {
         Self {
             vertex: Vertex::default(),
             click_tracker: ClickTracker::default(),
-            font: TextFont::from_font_size(VERTEX_LABEL_FONT_SIZE),
-            mesh: Mesh2d(meshes.add(VERTEX_SHAPE)),
-            material: MeshMaterial2d(materials.add(VERTEX_COLOR)),
+            font: TextFont::from_font_size(assets.settings().vertex_label_font_size),
+            mesh: Mesh2d(assets.mesh(MeshKey::vertex(
+                VertexShape::default(),
+                assets.settings().vertex_radius,
+                "",
+                assets.settings().vertex_label_font_size,
+                0.0,
+            ))),
+            material: MeshMaterial2d(assets.material(assets.palette().vertex)),
             position: Position(position),
         }
     }
 
-    pub fn new_with_label(
-        meshes: &mut Assets<Mesh>,
-        materials: &mut Assets<ColorMaterial>,
-        position: Vec2,
-        label: &str,
-    ) -> Self {
+    pub fn new_with_label(assets: &mut GraphAssets, position: Vec2, label: &str) -> Self {
         Self {
             vertex: Vertex {
                 label: label.to_string(),
             },
             click_tracker: ClickTracker::default(),
-            font: TextFont::from_font_size(VERTEX_LABEL_FONT_SIZE),
-            mesh: Mesh2d(meshes.add(VERTEX_SHAPE)),
-            material: MeshMaterial2d(materials.add(VERTEX_COLOR)),
+            font: TextFont::from_font_size(assets.settings().vertex_label_font_size),
+            mesh: Mesh2d(assets.mesh(MeshKey::vertex(
+                VertexShape::default(),
+                assets.settings().vertex_radius,
+                "",
+                assets.settings().vertex_label_font_size,
+                0.0,
+            ))),
+            material: MeshMaterial2d(assets.material(assets.palette().vertex)),
             position: Position(position),
         }
     }
 
-    /// Extends the spawning function with the observers that are true for all
-    /// vertices and adds the Text2d children.
-    pub fn spawn(
+    /// Extends the spawning function with the Text2d children and a fresh
+    /// `VertexId`. The picking observers are global (see `plugin`), so
+    /// vertices don't carry their own.
+    pub fn spawn(commands: &mut Commands, assets: &mut GraphAssets, position: Vec2) -> Entity {
+        let id = assets.vertex_ids().fresh();
+        let entity_id = commands.spawn((Self::new(assets, position), id)).id();
+
+        VertexBundle::add_children(commands, assets.settings(), entity_id);
+
+        entity_id
+    }
+
+    /// Same as `spawn`, but with a label already set.
+    pub fn spawn_with_label(
         commands: &mut Commands,
-        meshes: &mut Assets<Mesh>,
-        materials: &mut Assets<ColorMaterial>,
+        assets: &mut GraphAssets,
         position: Vec2,
+        label: &str,
     ) -> Entity {
-        let entity_id = commands.spawn(Self::new(meshes, materials, position)).id();
-
-        commands
-            .entity(entity_id)
-            .observe(on_vertex_clicked)
-            .observe(on_vertex_hovered)
-            .observe(on_vertex_out)
-            .observe(on_vertex_dragging)
-            .observe(on_vertex_drop)
-            .observe(on_vertex_dragged);
+        let id = assets.vertex_ids().fresh();
+        let entity_id = commands
+            .spawn((Self::new_with_label(assets, position, label), id))
+            .id();
 
-        VertexBundle::add_children(commands, entity_id);
+        VertexBundle::add_children_with_label(commands, assets.settings(), entity_id, label);
 
         entity_id
     }
 
-    pub fn add_children(commands: &mut Commands, entity_id: Entity) {
+    pub fn add_children(commands: &mut Commands, settings: &Settings, entity_id: Entity) {
         commands.entity(entity_id).with_children(|parent| {
             parent.spawn((
                 Text2d::new(""),
-                TextFont::from_font_size(VERTEX_LABEL_FONT_SIZE),
-                Transform::from_xyz(0.0, -VERTEX_SIZE - 15.0, VERTEX_TEXT_Z),
+                TextFont::from_font_size(settings.vertex_label_font_size),
+                TextColor(settings.palette().text),
+                Transform::from_translation(label_offset(settings).extend(VERTEX_TEXT_Z)),
             ));
         });
     }
 
-    pub fn add_children_with_label(commands: &mut Commands, entity_id: Entity, label: &str) {
+    pub fn add_children_with_label(
+        commands: &mut Commands,
+        settings: &Settings,
+        entity_id: Entity,
+        label: &str,
+    ) {
         commands.entity(entity_id).with_children(|parent| {
             parent.spawn((
                 Text2d::new(label),
-                TextFont::from_font_size(VERTEX_LABEL_FONT_SIZE),
-                Transform::from_xyz(0.0, -VERTEX_SIZE - 15.0, VERTEX_TEXT_Z),
+                TextFont::from_font_size(settings.vertex_label_font_size),
+                TextColor(settings.palette().text),
+                Transform::from_translation(label_offset(settings).extend(VERTEX_TEXT_Z)),
             ));
         });
     }
 }
 
+/// Where the label of an unstyled vertex goes.
+fn label_offset(settings: &Settings) -> Vec2 {
+    vertex_label_offset(
+        VertexShape::default(),
+        settings.vertex_radius,
+        settings.vertex_label_font_size,
+    )
+}
+
 /// A bundle for spawning edges
 #[derive(Bundle)]
 pub struct DirectedEdgeBundle {
@@ -113,48 +134,22 @@ pub struct DirectedEdgeBundle {
 
 impl DirectedEdgeBundle {
     /// Edges are defined by their from and to vertices.
-    pub fn new(
-        from: Entity,
-        to: Entity,
-        meshes: &mut Assets<Mesh>,
-        materials: &mut Assets<ColorMaterial>,
-    ) -> Self {
+    pub fn new(from: Entity, to: Entity, assets: &mut GraphAssets) -> Self {
         Self {
             directed_edge: DirectedEdge { from, to },
-            mesh: Mesh2d(meshes.add(EDGE_SHAPE)),
-            material: MeshMaterial2d(materials.add(EDGE_COLOR)),
+            mesh: Mesh2d(assets.mesh(MeshKey::Edge)),
+            material: MeshMaterial2d(assets.material(assets.palette().edge)),
         }
     }
 
-    /// Extends the spawning function with the observers that are true for all
-    /// vertices and adds the Text2d children.
+    /// Spawns the edge on its own. Like for vertices, the picking observers
+    /// are global, and edges have no children so they stay cheap in large graphs.
     pub fn spawn(
         from: Entity,
         to: Entity,
         commands: &mut Commands,
-        meshes: &mut Assets<Mesh>,
-        materials: &mut Assets<ColorMaterial>,
+        assets: &mut GraphAssets,
     ) -> Entity {
-        let entity_id = commands.spawn(Self::new(from, to, meshes, materials)).id();
-
-        commands
-            .entity(entity_id)
-            .observe(on_edge_clicked)
-            .observe(on_edge_hovered)
-            .observe(on_edge_out);
-
-        DirectedEdgeBundle::add_children(commands, entity_id);
-
-        entity_id
-    }
-
-    pub fn add_children(commands: &mut Commands, entity_id: Entity) {
-        commands.entity(entity_id).with_children(|parent| {
-            parent.spawn((
-                Text2d::new(""),
-                TextFont::from_font_size(VERTEX_LABEL_FONT_SIZE),
-                Transform::from_xyz(0.0, -VERTEX_SIZE - 15.0, VERTEX_TEXT_Z),
-            ));
-        });
+        commands.spawn(Self::new(from, to, assets)).id()
     }
 }
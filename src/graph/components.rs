@@ -1,6 +1,9 @@
+use std::collections::HashMap;
+
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::graph::constants::CANVAS_Z;
+use crate::graph::{constants::CANVAS_Z, settings::Settings};
 
 /// Custom position for better readability
 /// and in-game position representation
@@ -15,6 +18,11 @@ pub struct Vertex {
     pub label: String,
 }
 
+/// Names a vertex the same way across saves, e.g. as its Mermaid node id.
+/// It isn't part of the `VertexBundle`, so logically deleted vertices keep it.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VertexId(pub u64);
+
 /// An edge in the graph
 #[derive(Component)]
 pub struct DirectedEdge {
@@ -22,6 +30,13 @@ pub struct DirectedEdge {
     pub to: Entity,
 }
 
+/// One of the two ends of an edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeEnd {
+    From,
+    To,
+}
+
 /// The edge that should be drawn
 /// between a "dragged" vertex and
 /// the cursor.
@@ -49,3 +64,287 @@ pub struct ClickTracker {
     pub last_click_time: Option<f64>,
     pub click_count: u32,
 }
+
+/// Overrides the look of a vertex while a metric
+/// is mapped onto the canvas. Hovering restores these
+/// instead of the default vertex look.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct HeatMapStyle {
+    pub color: Option<Color>,
+    pub radius: Option<f32>,
+}
+
+/// An edge moved behind the canvas, because one of its vertices
+/// is logically deleted. Checked every frame until it comes back
+/// or its vertex is despawned for good.
+#[derive(Component, Debug)]
+pub struct HiddenEdge;
+
+/// Draws every plain edge, one without a style that is not hidden, as a
+/// quad of a single mesh. The own mesh of a plain edge is only shown while
+/// it is hovered, see `update_edge_batch`.
+#[derive(Component, Default, Debug)]
+pub struct EdgeBatch {
+    /// The index of the quad of each plain edge.
+    pub slots: HashMap<Entity, usize>,
+}
+
+/// Marks a vertex as part of the current selection.
+/// Shift + click toggles it.
+#[derive(Component, Debug)]
+pub struct Selected;
+
+/// Arbitrary metadata of a vertex or an edge, edited in the inspector.
+/// Lives outside of the bundles, so it survives logical deletion.
+#[derive(Component, Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attributes(pub Vec<Attribute>);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attribute {
+    pub key: String,
+    pub value: AttributeValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AttributeValue {
+    String(String),
+    Number(f64),
+    Bool(bool),
+    /// Non-premultiplied sRGBA.
+    Color([f32; 4]),
+}
+
+impl AttributeValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            AttributeValue::String(_) => "String",
+            AttributeValue::Number(_) => "Number",
+            AttributeValue::Bool(_) => "Bool",
+            AttributeValue::Color(_) => "Colour",
+        }
+    }
+
+    /// The default value of every type, in the order the inspector lists them.
+    pub fn defaults() -> [AttributeValue; 4] {
+        [
+            AttributeValue::String(String::new()),
+            AttributeValue::Number(0.0),
+            AttributeValue::Bool(false),
+            AttributeValue::Color([1.0, 1.0, 1.0, 1.0]),
+        ]
+    }
+}
+
+impl std::fmt::Display for AttributeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttributeValue::String(value) => write!(f, "{value}"),
+            AttributeValue::Number(value) => write!(f, "{value}"),
+            AttributeValue::Bool(value) => write!(f, "{value}"),
+            AttributeValue::Color([r, g, b, a]) => write!(
+                f,
+                "#{:02x}{:02x}{:02x}{:02x}",
+                (r * 255.0).round() as u8,
+                (g * 255.0).round() as u8,
+                (b * 255.0).round() as u8,
+                (a * 255.0).round() as u8,
+            ),
+        }
+    }
+}
+
+/// Optional weight of an edge, shown next to its middle.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct EdgeWeight(pub f32);
+
+/// The text entity showing the weight of an edge. It is not a child
+/// of the edge, as the edge transform is non-uniformly scaled.
+#[derive(Component, Debug)]
+pub struct EdgeWeightLabel {
+    pub edge: Entity,
+}
+
+/// Marks an edge whose `EdgeWeightLabel` entity is already spawned.
+#[derive(Component, Debug)]
+pub struct HasWeightLabel;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum VertexShape {
+    #[default]
+    Circle,
+    Square,
+    Diamond,
+    /// A pill around the label, the label is drawn inside of it.
+    RoundedBox,
+}
+
+impl VertexShape {
+    pub const ALL: [VertexShape; 4] = [
+        VertexShape::Circle,
+        VertexShape::Square,
+        VertexShape::Diamond,
+        VertexShape::RoundedBox,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VertexShape::Circle => "Circle",
+            VertexShape::Square => "Square",
+            VertexShape::Diamond => "Diamond",
+            VertexShape::RoundedBox => "Rounded box",
+        }
+    }
+}
+
+/// The look of a single vertex. Vertices without it
+/// look like `VertexStyle::themed` in the current theme.
+/// Colours are non-premultiplied sRGBA.
+#[derive(Component, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VertexStyle {
+    pub shape: VertexShape,
+    pub fill: [f32; 4],
+    pub stroke: [f32; 4],
+    /// No stroke is drawn when zero.
+    pub stroke_width: f32,
+    pub radius: f32,
+}
+
+impl Default for VertexStyle {
+    fn default() -> Self {
+        VertexStyle::themed(&Settings::default())
+    }
+}
+
+impl VertexStyle {
+    /// How an unstyled vertex looks with the given settings.
+    pub fn themed(settings: &Settings) -> Self {
+        let palette = settings.palette();
+        VertexStyle {
+            shape: VertexShape::Circle,
+            fill: palette.vertex.to_srgba().to_f32_array(),
+            stroke: palette.vertex_stroke.to_srgba().to_f32_array(),
+            stroke_width: 0.0,
+            radius: settings.vertex_radius,
+        }
+    }
+
+    pub fn fill_color(&self) -> Color {
+        Srgba::from_f32_array(self.fill).into()
+    }
+
+    pub fn stroke_color(&self) -> Color {
+        Srgba::from_f32_array(self.stroke).into()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl LineStyle {
+    pub const ALL: [LineStyle; 3] = [LineStyle::Solid, LineStyle::Dashed, LineStyle::Dotted];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineStyle::Solid => "Solid",
+            LineStyle::Dashed => "Dashed",
+            LineStyle::Dotted => "Dotted",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ArrowStyle {
+    #[default]
+    None,
+    Triangle,
+    Diamond,
+    Circle,
+}
+
+impl ArrowStyle {
+    pub const ALL: [ArrowStyle; 4] = [
+        ArrowStyle::None,
+        ArrowStyle::Triangle,
+        ArrowStyle::Diamond,
+        ArrowStyle::Circle,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArrowStyle::None => "None",
+            ArrowStyle::Triangle => "Triangle",
+            ArrowStyle::Diamond => "Diamond",
+            ArrowStyle::Circle => "Circle",
+        }
+    }
+}
+
+/// The look of a single edge. Edges without it look
+/// like `EdgeStyle::themed` in the current theme.
+#[derive(Component, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EdgeStyle {
+    /// Non-premultiplied sRGBA.
+    pub color: [f32; 4],
+    pub width: f32,
+    pub line: LineStyle,
+    pub arrow: ArrowStyle,
+}
+
+impl Default for EdgeStyle {
+    fn default() -> Self {
+        EdgeStyle::themed(&Settings::default())
+    }
+}
+
+impl EdgeStyle {
+    /// How an unstyled edge looks with the given settings.
+    pub fn themed(settings: &Settings) -> Self {
+        EdgeStyle {
+            color: settings.palette().edge.to_srgba().to_f32_array(),
+            width: settings.edge_width,
+            line: LineStyle::Solid,
+            arrow: ArrowStyle::None,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        Srgba::from_f32_array(self.color).into()
+    }
+}
+
+/// Either kind of style, so editing them can share one undo action.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ElementStyle {
+    Vertex(VertexStyle),
+    Edge(EdgeStyle),
+}
+
+/// The child mesh drawn behind a vertex as its stroke.
+#[derive(Component, Debug)]
+pub struct VertexStroke;
+
+/// The dash pattern the mesh of a dashed or dotted edge was built for,
+/// so it is only rebuilt when the number of dashes changes.
+#[derive(Component, Debug, PartialEq)]
+pub struct EdgePattern {
+    pub line: LineStyle,
+    pub dashes: usize,
+}
+
+/// The entity drawing the head of an edge. Like the weight label,
+/// it is not a child of the non-uniformly scaled edge.
+#[derive(Component, Debug)]
+pub struct EdgeArrowhead {
+    pub edge: Entity,
+    /// The style the current mesh was built for.
+    pub style: ArrowStyle,
+}
+
+/// Marks an edge whose `EdgeArrowhead` entity is already spawned.
+#[derive(Component, Debug)]
+pub struct HasArrowhead;
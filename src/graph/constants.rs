@@ -1,26 +1,135 @@
-use bevy::prelude::*;
-
-pub const CONSECUTIVE_CLICK_TIME: f64 = 0.3; // 300 ms
+use core::time::Duration;
 
-pub const VERTEX_SIZE: f32 = 10.;
-pub const VERTEX_SHAPE: Circle = Circle::new(VERTEX_SIZE);
-pub const VERTEX_COLOR: Color = Color::srgb(0.3, 0.6, 0.9);
-pub const HOVERED_VERTEX_COLOR: Color = Color::srgb(0.4, 0.8, 1.0);
+use bevy::prelude::*;
 
-pub const EDGE_COLOR: Color = Color::srgb(0.4, 0.45, 0.5);
-pub const HOVERED_EDGE_COLOR: Color = Color::srgb(0.6, 0.65, 0.6);
-pub const TEMP_EDGE_COLOR: Color = Color::srgb(0.95, 0.7, 0.2);
 pub const EDGE_SHAPE: Rectangle = Rectangle::from_length(1.0);
-pub const EDGE_WIDTH: f32 = 10.;
-
-pub const BG_COLOR: Color = Color::srgb(0.15, 0.15, 0.17);
-
-pub const VERTEX_LABEL_FONT_SIZE: f32 = 12.0;
 
 pub const CANVAS_Z: f32 = -2.0;
 pub const EDGE_Z: f32 = -1.0;
+/// Plain edges are batched just below the edges with their own mesh.
+pub const EDGE_BATCH_Z: f32 = -1.5;
 pub const HIDDEN_EDGE_Z: f32 = -3.0;
 pub const VERTEX_Z: f32 = 0.0;
 pub const VERTEX_TEXT_Z: f32 = 1.0;
+/// Relative to the vertex, the stroke is a child of it.
+pub const VERTEX_STROKE_Z: f32 = -0.5;
+pub const ARROWHEAD_Z: f32 = -0.5;
 
 pub const RENAME_CLICK_COUNT: u32 = 2;
+
+pub const PAGERANK_DAMPING: f64 = 0.85;
+pub const PAGERANK_MAX_ITERATIONS: usize = 100;
+pub const PAGERANK_TOLERANCE: f64 = 1e-6;
+
+pub const HEAT_MAP_COLD_COLOR: Color = Color::srgb(0.2, 0.3, 0.9);
+pub const HEAT_MAP_HOT_COLOR: Color = Color::srgb(0.95, 0.25, 0.2);
+pub const HEAT_MAP_MIN_RADIUS: f32 = 6.;
+pub const HEAT_MAP_MAX_RADIUS: f32 = 22.;
+pub const HEAT_MAP_STEPS: f32 = 32.;
+/// How often shared meshes and materials nothing uses anymore are freed.
+pub const HANDLE_CACHE_EVICTION_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Replaces the extension of the document path, e.g. `graph.metrics.csv` for `graph.ron`.
+pub const METRICS_EXTENSION: &str = "metrics.csv";
+
+/// Time spent on distance statistics per frame.
+pub const STATISTICS_FRAME_BUDGET: Duration = Duration::from_millis(2);
+pub const DEGREE_HISTOGRAM_HEIGHT: f32 = 80.;
+pub const GENERATOR_SPACING: f32 = 60.;
+pub const GENERATOR_MAX_VERTICES: usize = 5000;
+/// Dense generators get fewer vertices so they stay below this many edges.
+pub const GENERATOR_MAX_EDGES: usize = 100_000;
+
+pub const SELECTION_RING_GAP: f32 = 4.;
+/// How far duplicates (and pastes without a cursor) land from the original.
+pub const DUPLICATE_OFFSET: Vec2 = Vec2::new(30., -30.);
+
+pub const WINDOW_TITLE: &str = "Body Graph";
+pub const DOCUMENT_PATH: &str = "graph.ron";
+/// Overrides of the default bindings, see `KeybindingsFile`.
+pub const KEYBINDINGS_PATH: &str = "keybindings.ron";
+/// Subdirectory of the config and data directories of the user,
+/// see `settings_path` and `recovery_path`.
+pub const CONFIG_DIRECTORY: &str = "body_graph";
+pub const SETTINGS_FILE: &str = "settings.ron";
+pub const RECOVERY_FILE: &str = "recovery.ron";
+/// Exists while the editor runs, so a crash leaves it behind.
+pub const SESSION_LOCK_FILE: &str = "session.lock";
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+/// Distance of the weight label from the middle of its edge.
+pub const EDGE_WEIGHT_LABEL_OFFSET: f32 = 12.;
+
+/// How much lighter custom styled vertices and edges get when hovered.
+pub const STYLE_HOVER_LIGHTENING: f32 = 0.15;
+/// Rough width of a character relative to its font size.
+pub const CHARACTER_WIDTH_FACTOR: f32 = 0.6;
+pub const ROUNDED_BOX_PADDING: f32 = 8.;
+/// Distance between the bottom of a vertex and the top of its label.
+pub const VERTEX_LABEL_GAP: f32 = 9.;
+/// Dashes are this many times longer than the edge is wide, gaps are half of it.
+pub const DASH_LENGTH_FACTOR: f32 = 2.;
+pub const ARROWHEAD_MIN_SIZE: f32 = 12.;
+pub const ARROWHEAD_WIDTH_FACTOR: f32 = 1.5;
+
+/// Side of a cell of the `SpatialIndex` grid, a bit larger than most vertices.
+pub const SPATIAL_CELL_SIZE: f32 = 64.;
+/// How close to a vertex an edge has to be dropped to connect to it.
+pub const VERTEX_SNAP_RADIUS: f32 = 12.;
+/// Distance of the edge handles from the border of their vertex.
+pub const EDGE_HANDLE_GAP: f32 = 10.;
+pub const EDGE_HANDLE_RADIUS: f32 = 4.;
+/// How close to a handle a drag has to start to move that end of the edge.
+pub const EDGE_HANDLE_GRAB_RADIUS: f32 = 10.;
+/// Minimum distance between two recorded points of a lasso.
+pub const LASSO_POINT_SPACING: f32 = 4.;
+
+/// Passing this argument starts the benchmark scene instead of an empty canvas.
+pub const BENCHMARK_ARGUMENT: &str = "--bench";
+pub const BENCHMARK_VERTICES: usize = 20_000;
+pub const BENCHMARK_EDGES: usize = 50_000;
+pub const BENCHMARK_SEED: u64 = 0;
+pub const BENCHMARK_WARMUP_FRAMES: usize = 120;
+/// Frames measured per phase, idle and while a vertex is dragged around.
+pub const BENCHMARK_FRAMES: usize = 600;
+pub const BENCHMARK_OUTPUT_PATH: &str = "bench_output.txt";
+/// How far the benchmark drags its vertex away from where it started.
+pub const BENCHMARK_DRAG_RADIUS: f32 = 200.;
+
+/// World units kept free around the vertices when framing them.
+pub const FRAME_PADDING: f32 = 40.;
+/// Framing a single vertex or a tight selection doesn't zoom in further than this.
+pub const FRAME_MIN_SCALE: f32 = 0.5;
+pub const MINIMAP_SIZE: f32 = 180.;
+/// Larger graphs only show their vertices on the minimap.
+pub const MINIMAP_MAX_EDGES: usize = 5000;
+pub const MINIMAP_VERTEX_SIZE: f32 = 2.;
+
+/// Outside of the selection ring, so both stay visible.
+pub const SEARCH_RING_GAP: f32 = 8.;
+pub const SEARCH_RESULTS_HEIGHT: f32 = 240.;
+
+pub const COMMAND_PALETTE_WIDTH: f32 = 420.;
+pub const COMMAND_PALETTE_HEIGHT: f32 = 320.;
+
+/// World units kept free around the graph in exported pictures.
+pub const EXPORT_PADDING: f32 = 20.;
+/// How many world units make a centimetre in TikZ pictures.
+pub const TIKZ_UNITS_PER_CM: f32 = 50.;
+/// TikZ style names used when the export options leave them empty.
+pub const TIKZ_VERTEX_STYLE: &str = "vertex";
+pub const TIKZ_EDGE_STYLE: &str = "edge";
+pub const TIKZ_WEIGHT_STYLE: &str = "weight";
+/// Both CSV forms get an extension of their own, so they don't overwrite each other.
+pub const EDGE_LIST_EXTENSION: &str = "edges.csv";
+pub const ADJACENCY_MATRIX_EXTENSION: &str = "matrix.csv";
+/// Distance between the layers of imported flowcharts, and between the vertices in a layer.
+pub const MERMAID_LAYER_SPACING: f32 = 100.;
+pub const MERMAID_VERTEX_SPACING: f32 = 80.;
+/// Side of an adjacency matrix cell, and the room left for the labels of the rows and columns.
+pub const MATRIX_CELL_SIZE: f32 = 20.;
+pub const MATRIX_LABEL_SIZE: f32 = 80.;
+/// Rows of the tables have a fixed height, so only the visible ones are laid out,
+/// and fixed column widths, so the columns don't change width while scrolling.
+pub const TABLE_HEIGHT: f32 = 320.;
+pub const TABLE_ROW_HEIGHT: f32 = 20.;
+pub const TABLE_COLUMN_WIDTH: f32 = 90.;
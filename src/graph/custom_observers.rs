@@ -1,16 +1,47 @@
+use std::collections::{HashMap, HashSet};
+
 use bevy::prelude::*;
+use bevy_egui::EguiClipboard;
 
 use crate::graph::{
+    actions::{ActionRegistry, EditorAction},
+    analysis::{GraphSnapshot, metrics_to_csv, vertex_metrics},
     bundles::{DirectedEdgeBundle, VertexBundle},
-    components::{ClickTracker, DirectedEdge, Position, TemporaryDirectedEdge, Vertex},
-    constants::{CONSECUTIVE_CLICK_TIME, RENAME_CLICK_COUNT},
+    components::{
+        Attributes, ClickTracker, DirectedEdge, EdgeEnd, EdgeStyle, EdgeWeight, ElementStyle,
+        Position, Selected, TemporaryDirectedEdge, Vertex, VertexStyle,
+    },
+    constants::{DUPLICATE_OFFSET, GENERATOR_SPACING, RENAME_CLICK_COUNT, VERTEX_SNAP_RADIUS},
+    document::{EdgeData, GraphDocument, VertexData},
     events::{
-        CanvasClickedEvent, EdgeClickedEvent, UpdateCursorIconEvent, VertexClickedEvent,
-        VertexDragDroppedEvent, VertexDraggingEvent, VertexRenamedEvent,
+        ApplyGraphTextEvent, AttributesEditedEvent, CanvasClickedEvent, CopySelectionEvent,
+        CutSelectionEvent, DeleteElementsEvent, DocumentSource, DuplicateSelectionEvent,
+        EdgeClickedEvent, EdgeEndDroppedEvent, EdgeWeightEditedEvent, ExportDocumentEvent,
+        ExportMetricsEvent, GenerateGraphEvent, MergeVerticesEvent, OpenDocumentEvent, PasteEvent,
+        RecolorEvent, RunActionEvent, SaveDocumentEvent, SetAttributeEvent, StyleEditedEvent,
+        ToggleEdgeEvent, UpdateCursorIconEvent, VertexClickedEvent, VertexDragDroppedEvent,
+        VertexDraggingEvent, VertexRenamedEvent,
+    },
+    formats::{ExportOptions, document_from_csv},
+    generators::generate,
+    helpers::{
+        bounding_rect, cursor_world_position, frame_rect, metrics_path, update_entity_position,
+        vertex_display_name,
     },
-    helpers::update_entity_position,
-    resources::{HoveredEntity, RenamingState, UndoRedoStack},
-    undo_redo::{EdgeDeletionAction, EdgeDrawingAction, UndoAction, VertexInsertionAction, VertexRenameAction, VertexSpawnAction},
+    resources::{
+        DocumentState, GraphAssets, GraphClipboard, GraphMetrics, HoveredEntity, MergeDialogState,
+        MergeLabel, MetricsPanelState, PanelStates, RenamingState, TextEditorState, UndoRedoStack,
+    },
+    settings::Settings,
+    spatial::SpatialIndex,
+    text_editor::{parse_graph_text, vertex_id},
+    undo_redo::{
+        AttributesEditAction, BatchEditAction, EdgeDeletionAction, EdgeDrawingAction,
+        EdgeReconnectAction, EdgeWeightEditAction, GraphReplaceAction, StyleEditAction,
+        SubgraphAction, SubgraphEdge, SubgraphVertex, UndoAction, VertexInsertionAction,
+        VertexMergeAction, VertexRenameAction, VertexSpawnAction,
+    },
+    undo_redo_observers::{apply_batch_edit, remove_subgraph},
 };
 
 /// When a vertex is renamed, we update the label and
@@ -59,7 +90,13 @@ pub fn click_vertex(
     time: Res<Time>,
     renaming: ResMut<RenamingState>,
     camera: Single<(&Camera, &GlobalTransform)>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    registry: Res<ActionRegistry>,
+    settings: Res<Settings>,
 ) {
+    if !registry.gesture_held(EditorAction::RenameVertex, vertex_click.button, &keyboard) {
+        return;
+    }
     let Ok(mut tracker) = trackers.get_mut(vertex_click.entity) else {
         return;
     };
@@ -71,7 +108,7 @@ pub fn click_vertex(
         return;
     };
 
-    if current_time - last_time <= CONSECUTIVE_CLICK_TIME {
+    if current_time - last_time <= settings.consecutive_click_time {
         tracker.click_count += 1;
         tracker.last_click_time = Some(current_time);
         if tracker.click_count == RENAME_CLICK_COUNT {
@@ -121,15 +158,15 @@ fn activate_vertex_renaming(
 pub fn canvas_clicked(
     click: On<CanvasClickedEvent>,
     mut commands: Commands,
-    meshes: ResMut<Assets<Mesh>>,
-    materials: ResMut<Assets<ColorMaterial>>,
+    mut assets: GraphAssets,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    registry: Res<ActionRegistry>,
     mut undo_redo: ResMut<UndoRedoStack>,
 ) {
-    if click.button == PointerButton::Primary {
+    if registry.gesture_held(EditorAction::SpawnVertex, click.button, &keyboard) {
         let entity_id = VertexBundle::spawn(
             &mut commands,
-            meshes.into_inner(),
-            materials.into_inner(),
+            &mut assets,
             click.world_position,
         );
         undo_redo.push_undo(
@@ -143,14 +180,14 @@ pub fn canvas_clicked(
 }
 
 /// Dropping a vertex drag can mean two things
-/// depending on which button started the dragging.
+/// depending on how the dragging started.
 /// - Left: Move the vertex around.
 /// - Right: Draw an edge to a new vertex at cursor location.
 pub fn vertex_drag_dropped(
     drag: On<VertexDragDroppedEvent>,
     hovered: Res<HoveredEntity>,
-    meshes: ResMut<Assets<Mesh>>,
-    materials: ResMut<Assets<ColorMaterial>>,
+    index: Res<SpatialIndex>,
+    mut assets: GraphAssets,
     vertices: Query<Entity, With<Vertex>>,
     edge_entities: Query<Entity, With<DirectedEdge>>,
     edges: Query<&mut DirectedEdge>,
@@ -158,19 +195,22 @@ pub fn vertex_drag_dropped(
     mut temp_edge: Single<&mut TemporaryDirectedEdge>,
     mut undo_redo: ResMut<UndoRedoStack>,
 ) {
-    let materials = materials.into_inner();
-    let meshes = meshes.into_inner();
-    if drag.button == PointerButton::Secondary {
+    if drag.action == EditorAction::DrawEdge {
         let to_entity;
-        if let Some(hovered_entity) = hovered.0 {
+        // Dropping close to a vertex connects to it, it doesn't have to be hit exactly.
+        let target = hovered.0.or_else(|| {
+            index
+                .nearest_vertex(drag.world_position, VERTEX_SNAP_RADIUS)
+                .filter(|vertex| *vertex != drag.entity)
+        });
+        if let Some(hovered_entity) = target {
             if let Ok(hovered_vertex) = vertices.get(hovered_entity) {
                 to_entity = hovered_vertex;
                 let edge_entity = DirectedEdgeBundle::spawn(
                     drag.entity,
                     to_entity,
                     &mut commands,
-                    meshes,
-                    materials,
+                    &mut assets,
                 );
 
                 undo_redo.push_undo(
@@ -186,8 +226,7 @@ pub fn vertex_drag_dropped(
                 return;
             } else if let Ok(hovered_edge) = edge_entities.get(hovered_entity) {
                 if let Some(inserted_vertex) = insert_vertex_on_edge(
-                    meshes,
-                    materials,
+                    &mut assets,
                     edges,
                     &mut commands,
                     drag.world_position,
@@ -202,7 +241,7 @@ pub fn vertex_drag_dropped(
                 return;
             }
         } else {
-            to_entity = VertexBundle::spawn(&mut commands, meshes, materials, drag.world_position);
+            to_entity = VertexBundle::spawn(&mut commands, &mut assets, drag.world_position);
             undo_redo.push_undo(
                 UndoAction::UndoVertexSpawn(VertexSpawnAction {
                     entity: to_entity,
@@ -211,27 +250,82 @@ pub fn vertex_drag_dropped(
                 &mut commands,
             );
         }
-        DirectedEdgeBundle::spawn(drag.entity, to_entity, &mut commands, meshes, materials);
+        DirectedEdgeBundle::spawn(drag.entity, to_entity, &mut commands, &mut assets);
         temp_edge.from = None;
     }
 }
 
-/// Clicking on an edge can either delete it
+/// Dropping the end of an edge on (or right next to) a vertex connects
+/// it there. Anywhere else, or where it would make a loop, nothing changes.
+pub fn edge_end_dropped(
+    drop: On<EdgeEndDroppedEvent>,
+    index: Res<SpatialIndex>,
+    mut edges: Query<&mut DirectedEdge>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    mut commands: Commands,
+) {
+    let Ok(mut edge) = edges.get_mut(drop.entity) else {
+        return;
+    };
+    let Some(target) = index.nearest_vertex(drop.world_position, VERTEX_SNAP_RADIUS) else {
+        return;
+    };
+    let (from, to) = match drop.end {
+        EdgeEnd::From => (target, edge.to),
+        EdgeEnd::To => (edge.from, target),
+    };
+    if from == to || (from, to) == (edge.from, edge.to) {
+        return;
+    }
+
+    undo_redo.push_undo(
+        UndoAction::UndoEdgeReconnectAction(EdgeReconnectAction {
+            entity: drop.entity,
+            from: edge.from,
+            to: edge.to,
+        }),
+        &mut commands,
+    );
+    edge.from = from;
+    edge.to = to;
+}
+
+/// Clicking on an edge can delete it, contract it
 /// or insert a new vertex.
 pub fn edge_clicked(
     click: On<EdgeClickedEvent>,
     mut commands: Commands,
     mut hovered_entity: ResMut<HoveredEntity>,
     edges: Query<&mut DirectedEdge>,
-    meshes: ResMut<Assets<Mesh>>,
-    materials: ResMut<Assets<ColorMaterial>>,
+    vertices: Query<&Vertex>,
+    merge: Res<MergeDialogState>,
+    mut assets: GraphAssets,
     keyboard: Res<ButtonInput<KeyCode>>,
+    registry: Res<ActionRegistry>,
     mut undo_redo: ResMut<UndoRedoStack>,
 ) {
-    let is_ctrl_held =
-        { keyboard.pressed(KeyCode::ControlLeft) || keyboard.pressed(KeyCode::ControlRight) };
+    if registry.gesture_held(EditorAction::ContractEdge, click.button, &keyboard) {
+        let Ok(edge) = edges.get(click.entity) else {
+            return;
+        };
+        let labels = [edge.from, edge.to]
+            .map(|vertex| vertices.get(vertex).map_or("", |vertex| vertex.label.as_str()));
+        let label = match merge.label {
+            MergeLabel::Joined => merge.joined_label(labels),
+            MergeLabel::Vertex(_) => labels[0].to_string(),
+        };
+        // The contracted edge itself always goes, as a loop.
+        commands.trigger(MergeVerticesEvent {
+            vertices: vec![edge.from, edge.to],
+            label,
+            drop_loops: true,
+            drop_duplicates: merge.drop_duplicates,
+        });
+        hovered_entity.0 = None;
+        return;
+    }
 
-    if is_ctrl_held {
+    if registry.gesture_held(EditorAction::DeleteEdge, click.button, &keyboard) {
         let Ok(edge) = edges.get(click.entity) else {
             return
         };
@@ -249,10 +343,9 @@ pub fn edge_clicked(
         return;
     }
 
-    if click.button == PointerButton::Primary {
+    if registry.gesture_held(EditorAction::SplitEdge, click.button, &keyboard) {
         insert_vertex_on_edge(
-            meshes.into_inner(),
-            materials.into_inner(),
+            &mut assets,
             edges,
             &mut commands,
             click.world_position,
@@ -267,8 +360,7 @@ pub fn edge_clicked(
 /// `to` for the edge, and creating a new one
 /// between the new and previous vertices.
 pub fn insert_vertex_on_edge(
-    meshes: &mut Assets<Mesh>,
-    materials: &mut Assets<ColorMaterial>,
+    assets: &mut GraphAssets,
     mut edges: Query<&mut DirectedEdge>,
     commands: &mut Commands,
     world_position: Vec2,
@@ -279,12 +371,12 @@ pub fn insert_vertex_on_edge(
         return None;
     };
 
-    let new_vertex = VertexBundle::spawn(commands, meshes, materials, world_position);
+    let new_vertex = VertexBundle::spawn(commands, assets, world_position);
 
     let prev_to = edge.to;
     edge.to = new_vertex;
 
-    DirectedEdgeBundle::spawn(new_vertex, prev_to, commands, meshes, materials);
+    DirectedEdgeBundle::spawn(new_vertex, prev_to, commands, assets);
 
     undo_redo.push_undo(UndoAction::UndoVertexInsertionAction(VertexInsertionAction{
         edge_entity,
@@ -307,7 +399,7 @@ pub fn vertex_dragging(
     mut renaming: ResMut<RenamingState>,
     camera: Single<(&Camera, &GlobalTransform)>,
 ) {
-    if drag.button == PointerButton::Primary {
+    if drag.action == EditorAction::MoveVertex {
         if let Some(renaming_entity) = renaming.entity {
             if renaming_entity == drag.entity {
                 let (camera, camera_transform) = camera.into_inner();
@@ -319,7 +411,7 @@ pub fn vertex_dragging(
             }
         }
         update_entity_position(positions, drag.entity, drag.world_position);
-    } else if drag.button == PointerButton::Secondary {
+    } else if drag.action == EditorAction::DrawEdge {
         temp_edge.to = drag.world_position;
     }
 }
@@ -335,3 +427,1360 @@ pub fn update_cursor_icon(
         .entity(window.entity())
         .insert(event.new_icon.clone());
 }
+
+/// Spawns a generated graph centered on the camera,
+/// recorded as a single undo step.
+pub fn on_generate_graph(
+    event: On<GenerateGraphEvent>,
+    mut commands: Commands,
+    mut assets: GraphAssets,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    camera: Single<&GlobalTransform, With<Camera2d>>,
+) {
+    let center = camera.translation().truncate();
+    let graph = generate(event.kind, &event.parameters);
+
+    let vertices: Vec<SubgraphVertex> = graph
+        .positions
+        .iter()
+        .enumerate()
+        .map(|(index, offset)| {
+            let label = index.to_string();
+            let position = center + *offset;
+            let entity =
+                VertexBundle::spawn_with_label(&mut commands, &mut assets, position, &label);
+            SubgraphVertex {
+                entity,
+                position,
+                label,
+            }
+        })
+        .collect();
+
+    let edges = graph
+        .edges
+        .iter()
+        .map(|(from, to)| {
+            let (from, to) = (vertices[*from].entity, vertices[*to].entity);
+            let entity = DirectedEdgeBundle::spawn(from, to, &mut commands, &mut assets);
+            SubgraphEdge { entity, from, to }
+        })
+        .collect();
+
+    undo_redo.push_undo(
+        UndoAction::UndoGraphInsertionAction(SubgraphAction { vertices, edges }),
+        &mut commands,
+    );
+}
+
+/// Spawns every vertex and edge of a document moved by `offset`,
+/// and returns them as a subgraph for the undo stack. The vertices
+/// keep the ids of the document if `keep_ids` is set, when it is
+/// opened rather than pasted next to vertices that may have them.
+pub fn spawn_document(
+    commands: &mut Commands,
+    assets: &mut GraphAssets,
+    document: &GraphDocument,
+    offset: Vec2,
+    keep_ids: bool,
+) -> SubgraphAction {
+    let vertices: Vec<SubgraphVertex> = document
+        .vertices
+        .iter()
+        .map(|record| {
+            let position = Vec2::new(record.x, record.y) + offset;
+            let entity = VertexBundle::spawn_with_label(
+                commands,
+                assets,
+                position,
+                &record.label,
+            );
+            if !record.attributes.is_empty() {
+                commands
+                    .entity(entity)
+                    .insert(Attributes(record.attributes.clone()));
+            }
+            if let Some(style) = &record.style {
+                commands.entity(entity).insert(style.clone());
+            }
+            if let Some(id) = record.id.filter(|_| keep_ids) {
+                commands.entity(entity).insert(assets.vertex_ids().claim(id));
+            }
+            SubgraphVertex {
+                entity,
+                position,
+                label: record.label.clone(),
+            }
+        })
+        .collect();
+
+    let edges = document
+        .edges
+        .iter()
+        .filter_map(|record| {
+            let from = vertices.get(record.from)?.entity;
+            let to = vertices.get(record.to)?.entity;
+            let entity = DirectedEdgeBundle::spawn(from, to, commands, assets);
+            if let Some(weight) = record.weight {
+                commands.entity(entity).insert(EdgeWeight(weight));
+            }
+            if !record.attributes.is_empty() {
+                commands
+                    .entity(entity)
+                    .insert(Attributes(record.attributes.clone()));
+            }
+            if let Some(style) = &record.style {
+                commands.entity(entity).insert(style.clone());
+            }
+            Some(SubgraphEdge { entity, from, to })
+        })
+        .collect();
+
+    SubgraphAction { vertices, edges }
+}
+
+/// Replaces the attributes of the entity. Manual edits are undoable.
+pub fn on_attributes_edited(
+    event: On<AttributesEditedEvent>,
+    mut commands: Commands,
+    attributes: Query<&Attributes>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+) {
+    let Ok(mut entity) = commands.get_entity(event.entity) else {
+        return;
+    };
+    let old_attributes = attributes
+        .get(event.entity)
+        .map(|attributes| attributes.0.clone())
+        .unwrap_or_default();
+    entity.insert(Attributes(event.attributes.clone()));
+
+    if event.manual {
+        undo_redo.push_undo(
+            UndoAction::UndoAttributesEditAction(AttributesEditAction {
+                entity: event.entity,
+                attributes: old_attributes,
+            }),
+            &mut commands,
+        );
+    }
+}
+
+/// Sets or clears the weight of an edge. Manual edits are undoable.
+pub fn on_edge_weight_edited(
+    event: On<EdgeWeightEditedEvent>,
+    mut commands: Commands,
+    weights: Query<&EdgeWeight>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+) {
+    let Ok(mut entity) = commands.get_entity(event.entity) else {
+        return;
+    };
+    let old_weight = weights.get(event.entity).ok().map(|weight| weight.0);
+    match event.weight {
+        Some(weight) => entity.insert(EdgeWeight(weight)),
+        None => entity.remove::<EdgeWeight>(),
+    };
+
+    if event.manual {
+        undo_redo.push_undo(
+            UndoAction::UndoEdgeWeightEditAction(EdgeWeightEditAction {
+                entity: event.entity,
+                weight: old_weight,
+            }),
+            &mut commands,
+        );
+    }
+}
+
+/// Replaces the style of the entity. Manual edits are undoable.
+pub fn on_style_edited(
+    event: On<StyleEditedEvent>,
+    mut commands: Commands,
+    vertex_styles: Query<&VertexStyle>,
+    edge_styles: Query<&EdgeStyle>,
+    settings: Res<Settings>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+) {
+    let Ok(mut entity) = commands.get_entity(event.entity) else {
+        return;
+    };
+    let old_style = current_style(
+        event.entity,
+        &event.style,
+        &vertex_styles,
+        &edge_styles,
+        &settings,
+    );
+    match &event.style {
+        ElementStyle::Vertex(style) => entity.insert(style.clone()),
+        ElementStyle::Edge(style) => entity.insert(style.clone()),
+    };
+
+    if event.manual {
+        undo_redo.push_undo(
+            UndoAction::UndoStyleEditAction(StyleEditAction {
+                entity: event.entity,
+                style: old_style,
+            }),
+            &mut commands,
+        );
+    }
+}
+
+/// The current style of an entity of the same kind as `like`,
+/// the one of the theme if it has none yet.
+pub fn current_style(
+    entity: Entity,
+    like: &ElementStyle,
+    vertex_styles: &Query<&VertexStyle>,
+    edge_styles: &Query<&EdgeStyle>,
+    settings: &Settings,
+) -> ElementStyle {
+    match like {
+        ElementStyle::Vertex(_) => ElementStyle::Vertex(
+            vertex_styles
+                .get(entity)
+                .cloned()
+                .unwrap_or_else(|_| VertexStyle::themed(settings)),
+        ),
+        ElementStyle::Edge(_) => ElementStyle::Edge(
+            edge_styles
+                .get(entity)
+                .cloned()
+                .unwrap_or_else(|_| EdgeStyle::themed(settings)),
+        ),
+    }
+}
+
+/// Writes every live vertex and edge into a RON file.
+pub fn on_save_document(
+    save: On<SaveDocumentEvent>,
+    vertices: Query<VertexData>,
+    edges: Query<EdgeData>,
+    mut document_state: ResMut<DocumentState>,
+    undo_redo: Res<UndoRedoStack>,
+) {
+    let document = GraphDocument::from_entities(vertices.iter(), edges.iter());
+    let result = document
+        .to_ron()
+        .map_err(|error| error.to_string())
+        .and_then(|text| std::fs::write(&save.path, text).map_err(|error| error.to_string()));
+
+    document_state.status = Some(match result {
+        Ok(()) => {
+            document_state.saved_state = undo_redo.state();
+            format!("Saved to {}", save.path)
+        }
+        Err(error) => format!("Saving failed: {error}"),
+    });
+}
+
+/// Writes every live vertex and edge into a file of another format.
+/// Exporting doesn't count as saving, the document stays dirty.
+pub fn on_export_document(
+    export: On<ExportDocumentEvent>,
+    vertices: Query<VertexData>,
+    edges: Query<EdgeData>,
+    settings: Res<Settings>,
+    options: Res<ExportOptions>,
+    mut document_state: ResMut<DocumentState>,
+) {
+    let document = GraphDocument::from_entities(vertices.iter(), edges.iter());
+    let result = export
+        .format
+        .write(&document, &settings, &options)
+        .and_then(|text| std::fs::write(&export.path, text).map_err(|error| error.to_string()));
+
+    document_state.status = Some(match result {
+        Ok(()) => format!("Exported to {}", export.path),
+        Err(error) => format!("Exporting failed: {error}"),
+    });
+}
+
+/// Writes the metrics in snapshot order, computing them first
+/// if the metrics panel has not done so for this revision.
+pub fn on_export_metrics(
+    _export: On<ExportMetricsEvent>,
+    snapshot: Res<GraphSnapshot>,
+    metrics: Res<GraphMetrics>,
+    vertices: Query<&Vertex>,
+    mut panel: ResMut<MetricsPanelState>,
+    mut document_state: ResMut<DocumentState>,
+) {
+    let values = if metrics.revision == snapshot.revision {
+        metrics.values.clone()
+    } else {
+        vertex_metrics(&snapshot)
+    };
+    let labels: Vec<String> = snapshot
+        .vertices
+        .iter()
+        .map(|entity| {
+            let label = vertices
+                .get(*entity)
+                .map(|vertex| vertex.label.as_str())
+                .unwrap_or_default();
+            vertex_display_name(label, *entity)
+        })
+        .collect();
+    let csv = metrics_to_csv(labels.iter().map(String::as_str).zip(&values));
+
+    let path = metrics_path(&document_state.path);
+    let status = match std::fs::write(&path, csv) {
+        Ok(()) => format!("Exported to {path}"),
+        Err(error) => format!("Export failed: {error}"),
+    };
+    panel.export_status = Some(status.clone());
+    document_state.status = Some(status);
+}
+
+/// Replaces the whole graph with the one read from a RON file.
+/// The replaced graph is only logically deleted so opening can be undone.
+pub fn on_open_document(
+    open: On<OpenDocumentEvent>,
+    mut commands: Commands,
+    mut assets: GraphAssets,
+    vertices: Query<(Entity, &Vertex, &Position)>,
+    vertex_data: Query<(&Vertex, &Position)>,
+    edges: Query<(Entity, &DirectedEdge)>,
+    selected: Query<Entity, With<Selected>>,
+    mut hovered_entity: ResMut<HoveredEntity>,
+    mut document_state: ResMut<DocumentState>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+) {
+    let document = std::fs::read_to_string(&open.path)
+        .map_err(|error| error.to_string())
+        .and_then(|text| match open.source {
+            DocumentSource::Import(format) => format.read(&text),
+            _ => GraphDocument::from_ron(&text).map_err(|error| error.to_string()),
+        });
+    let document = match document {
+        Ok(document) => document,
+        Err(error) => {
+            let verb = match open.source {
+                DocumentSource::Import(_) => "Importing",
+                _ => "Opening",
+            };
+            document_state.status = Some(format!("{verb} failed: {error}"));
+            return;
+        }
+    };
+
+    let current = SubgraphAction {
+        vertices: vertices
+            .iter()
+            .map(|(entity, vertex, position)| SubgraphVertex {
+                entity,
+                position: position.0,
+                label: vertex.label.clone(),
+            })
+            .collect(),
+        edges: edges
+            .iter()
+            .map(|(entity, edge)| SubgraphEdge {
+                entity,
+                from: edge.from,
+                to: edge.to,
+            })
+            .collect(),
+    };
+    for entity in &selected {
+        commands.entity(entity).remove::<Selected>();
+    }
+    hovered_entity.0 = None;
+
+    let removed = remove_subgraph(&mut commands, &current, vertex_data);
+    let inserted = spawn_document(
+        &mut commands,
+        &mut assets,
+        &document,
+        Vec2::ZERO,
+        true,
+    );
+    undo_redo.push_undo(
+        UndoAction::UndoGraphReplaceAction(GraphReplaceAction {
+            removed,
+            inserted,
+            relabeled: Vec::new(),
+            reweighted: Vec::new(),
+        }),
+        &mut commands,
+    );
+    document_state.status = Some(match open.source {
+        DocumentSource::Document => {
+            document_state.saved_state = undo_redo.state();
+            format!("Opened {}", open.path)
+        }
+        DocumentSource::Recovery => "Restored unsaved work".to_string(),
+        DocumentSource::Import(_) => format!("Imported {}", open.path),
+    });
+}
+
+/// Adds the edge if there is none between the vertices in that direction,
+/// otherwise logically deletes every edge between them.
+pub fn on_toggle_edge(
+    toggle: On<ToggleEdgeEvent>,
+    mut commands: Commands,
+    mut assets: GraphAssets,
+    vertex_data: Query<(&Vertex, &Position)>,
+    edges: Query<(Entity, &DirectedEdge)>,
+    mut hovered_entity: ResMut<HoveredEntity>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+) {
+    if !vertex_data.contains(toggle.from) || !vertex_data.contains(toggle.to) {
+        return;
+    }
+
+    let existing: Vec<SubgraphEdge> = edges
+        .iter()
+        .filter(|(_, edge)| edge.from == toggle.from && edge.to == toggle.to)
+        .map(|(entity, edge)| SubgraphEdge {
+            entity,
+            from: edge.from,
+            to: edge.to,
+        })
+        .collect();
+    if existing.is_empty() {
+        let entity = DirectedEdgeBundle::spawn(toggle.from, toggle.to, &mut commands, &mut assets);
+        undo_redo.push_undo(
+            UndoAction::UndoEdgeDrawingAction(EdgeDrawingAction {
+                entity,
+                from: toggle.from,
+                to: toggle.to,
+            }),
+            &mut commands,
+        );
+        return;
+    }
+
+    if hovered_entity
+        .0
+        .is_some_and(|hovered| existing.iter().any(|edge| edge.entity == hovered))
+    {
+        hovered_entity.0 = None;
+    }
+    let removed = remove_subgraph(
+        &mut commands,
+        &SubgraphAction {
+            vertices: Vec::new(),
+            edges: existing,
+        },
+        vertex_data,
+    );
+    undo_redo.push_undo(UndoAction::UndoGraphDeletionAction(removed), &mut commands);
+}
+
+/// Logically deletes the given vertices and edges. Edges of deleted
+/// vertices are hidden with them and come back when they are restored.
+pub fn on_delete_elements(
+    delete: On<DeleteElementsEvent>,
+    mut commands: Commands,
+    vertices: Query<(&Vertex, &Position)>,
+    edges: Query<&DirectedEdge>,
+    mut hovered_entity: ResMut<HoveredEntity>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+) {
+    let mut deleted = SubgraphAction {
+        vertices: Vec::new(),
+        edges: Vec::new(),
+    };
+    for &entity in &delete.entities {
+        if let Ok((vertex, position)) = vertices.get(entity) {
+            deleted.vertices.push(SubgraphVertex {
+                entity,
+                position: position.0,
+                label: vertex.label.clone(),
+            });
+            commands.entity(entity).remove::<Selected>();
+        } else if let Ok(edge) = edges.get(entity) {
+            deleted.edges.push(SubgraphEdge {
+                entity,
+                from: edge.from,
+                to: edge.to,
+            });
+        } else {
+            continue;
+        }
+        if hovered_entity.0 == Some(entity) {
+            hovered_entity.0 = None;
+        }
+    }
+    if deleted.vertices.is_empty() && deleted.edges.is_empty() {
+        return;
+    }
+
+    let removed = remove_subgraph(&mut commands, &deleted, vertices);
+    undo_redo.push_undo(UndoAction::UndoGraphDeletionAction(removed), &mut commands);
+}
+
+/// Merges the vertices into the first one, which moves to their centre.
+/// Their edges are moved onto it, dropping the loops and duplicates this
+/// creates if asked to. Loops and parallel edges the first vertex already
+/// had are kept. The merged vertices and dropped edges are deleted
+/// logically, so undo brings back exactly what was there.
+pub fn on_merge_vertices(
+    merge: On<MergeVerticesEvent>,
+    mut commands: Commands,
+    vertices: Query<(&Vertex, &Position)>,
+    mut edges: Query<(Entity, &mut DirectedEdge)>,
+    mut hovered_entity: ResMut<HoveredEntity>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+) {
+    let Some((&survivor, others)) = merge.vertices.split_first() else {
+        return;
+    };
+    let Ok((survivor_vertex, survivor_position)) = vertices.get(survivor) else {
+        return;
+    };
+    let mut merged = HashSet::new();
+    let merged_vertices: Vec<SubgraphVertex> = others
+        .iter()
+        .filter(|entity| **entity != survivor && merged.insert(**entity))
+        .filter_map(|&entity| {
+            let (vertex, position) = vertices.get(entity).ok()?;
+            Some(SubgraphVertex {
+                entity,
+                position: position.0,
+                label: vertex.label.clone(),
+            })
+        })
+        .collect();
+    if merged_vertices.is_empty() {
+        return;
+    }
+    let center = merged_vertices
+        .iter()
+        .fold(survivor_position.0, |sum, vertex| sum + vertex.position)
+        / (merged_vertices.len() + 1) as f32;
+
+    // Edges the survivor already has come first, so they are the ones kept
+    // among duplicates. Hidden edges of deleted vertices are left alone.
+    let onto_survivor = |vertex: Entity| {
+        if merged.contains(&vertex) {
+            survivor
+        } else {
+            vertex
+        }
+    };
+    let mut incident: Vec<(Entity, Entity, Entity)> = edges
+        .iter()
+        .filter(|(_, edge)| vertices.contains(edge.from) && vertices.contains(edge.to))
+        .filter(|(_, edge)| {
+            onto_survivor(edge.from) == survivor || onto_survivor(edge.to) == survivor
+        })
+        .map(|(entity, edge)| (entity, edge.from, edge.to))
+        .collect();
+    incident.sort_by_key(|(entity, from, to)| {
+        (merged.contains(from) || merged.contains(to), entity.index())
+    });
+
+    let mut dropped = Vec::new();
+    let mut reconnected = Vec::new();
+    let mut kept = HashSet::new();
+    for (entity, from, to) in incident {
+        let ends = (onto_survivor(from), onto_survivor(to));
+        let first = kept.insert(ends);
+        if ends == (from, to) {
+            continue;
+        }
+        let is_loop = ends.0 == ends.1;
+        if (is_loop && merge.drop_loops) || (merge.drop_duplicates && !first) {
+            dropped.push(SubgraphEdge { entity, from, to });
+            continue;
+        }
+        if let Ok((_, mut edge)) = edges.get_mut(entity) {
+            reconnected.push(EdgeReconnectAction { entity, from, to });
+            (edge.from, edge.to) = ends;
+        }
+    }
+
+    for vertex in &merged_vertices {
+        commands.entity(vertex.entity).remove::<Selected>();
+        if hovered_entity.0 == Some(vertex.entity) {
+            hovered_entity.0 = None;
+        }
+    }
+    for edge in &dropped {
+        if hovered_entity.0 == Some(edge.entity) {
+            hovered_entity.0 = None;
+        }
+    }
+    let label = survivor_vertex.label.clone();
+    if label != merge.label {
+        commands.trigger(VertexRenamedEvent {
+            entity: survivor,
+            new_label: merge.label.clone(),
+            manual: false,
+        });
+    }
+    commands.entity(survivor).insert(Position(center));
+    let position = survivor_position.0;
+
+    let removed = remove_subgraph(
+        &mut commands,
+        &SubgraphAction {
+            vertices: merged_vertices,
+            edges: dropped,
+        },
+        vertices,
+    );
+    undo_redo.push_undo(
+        UndoAction::UndoVertexMergeAction(VertexMergeAction {
+            survivor,
+            label,
+            position,
+            removed,
+            reconnected,
+        }),
+        &mut commands,
+    );
+}
+
+/// Sets the attribute on every entity, keeping their other attributes.
+pub fn on_set_attribute(
+    set: On<SetAttributeEvent>,
+    mut commands: Commands,
+    elements: Query<(), Or<(With<Vertex>, With<DirectedEdge>)>>,
+    attributes: Query<&Attributes>,
+    vertex_styles: Query<&VertexStyle>,
+    edge_styles: Query<&EdgeStyle>,
+    settings: Res<Settings>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+) {
+    if set.attribute.key.is_empty() {
+        return;
+    }
+
+    let mut edit = BatchEditAction::default();
+    for &entity in set.entities.iter().filter(|entity| elements.contains(**entity)) {
+        let mut edited = attributes
+            .get(entity)
+            .map(|attributes| attributes.0.clone())
+            .unwrap_or_default();
+        match edited.iter_mut().find(|attribute| attribute.key == set.attribute.key) {
+            Some(attribute) => attribute.value = set.attribute.value.clone(),
+            None => edited.push(set.attribute.clone()),
+        }
+        edit.attributes.push(AttributesEditAction {
+            entity,
+            attributes: edited,
+        });
+    }
+    if edit.attributes.is_empty() {
+        return;
+    }
+
+    let previous = apply_batch_edit(
+        &mut commands,
+        &edit,
+        &attributes,
+        &vertex_styles,
+        &edge_styles,
+        &settings,
+    );
+    undo_redo.push_undo(UndoAction::UndoBatchEditAction(previous), &mut commands);
+}
+
+/// Recolours vertices and edges, keeping the rest of their styles.
+pub fn on_recolor(
+    recolor: On<RecolorEvent>,
+    mut commands: Commands,
+    vertices: Query<(), With<Vertex>>,
+    edges: Query<(), With<DirectedEdge>>,
+    attributes: Query<&Attributes>,
+    vertex_styles: Query<&VertexStyle>,
+    edge_styles: Query<&EdgeStyle>,
+    settings: Res<Settings>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+) {
+    let mut edit = BatchEditAction::default();
+    for &entity in &recolor.entities {
+        let style = if vertices.contains(entity) {
+            let mut style = vertex_styles
+                .get(entity)
+                .cloned()
+                .unwrap_or_else(|_| VertexStyle::themed(&settings));
+            style.fill = recolor.color;
+            ElementStyle::Vertex(style)
+        } else if edges.contains(entity) {
+            let mut style = edge_styles
+                .get(entity)
+                .cloned()
+                .unwrap_or_else(|_| EdgeStyle::themed(&settings));
+            style.color = recolor.color;
+            ElementStyle::Edge(style)
+        } else {
+            continue;
+        };
+        edit.styles.push(StyleEditAction { entity, style });
+    }
+    if edit.styles.is_empty() {
+        return;
+    }
+
+    let previous = apply_batch_edit(
+        &mut commands,
+        &edit,
+        &attributes,
+        &vertex_styles,
+        &edge_styles,
+        &settings,
+    );
+    undo_redo.push_undo(UndoAction::UndoBatchEditAction(previous), &mut commands);
+}
+
+/// Turns the graph into the one described by the text editor. Vertices
+/// whose id is still in the text keep their entity and position, edges
+/// are kept if an edge between the same vertices is still there. What
+/// differs is recorded as a single replace step.
+pub fn on_apply_graph_text(
+    apply: On<ApplyGraphTextEvent>,
+    mut commands: Commands,
+    mut assets: GraphAssets,
+    vertices: Query<(Entity, &Vertex, &Position)>,
+    vertex_data: Query<(&Vertex, &Position)>,
+    edges: Query<(Entity, &DirectedEdge, Option<&EdgeWeight>)>,
+    selected: Query<Entity, With<Selected>>,
+    mut hovered_entity: ResMut<HoveredEntity>,
+    mut editor: ResMut<TextEditorState>,
+    mut document_state: ResMut<DocumentState>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+) {
+    let graph = match parse_graph_text(&apply.text) {
+        Ok(graph) => graph,
+        Err(error) => {
+            editor.error = Some(error);
+            return;
+        }
+    };
+
+    let ids: HashMap<String, Entity> = vertices
+        .iter()
+        .map(|(entity, _, _)| (vertex_id(entity), entity))
+        .collect();
+    let kept: HashSet<Entity> = graph
+        .nodes
+        .iter()
+        .filter_map(|node| ids.get(&node.id).copied())
+        .collect();
+
+    let mut relabeled = Vec::new();
+    let mut inserted = SubgraphAction {
+        vertices: Vec::new(),
+        edges: Vec::new(),
+    };
+    // New vertices go in a row below the vertices that stay.
+    let mut next_position = bounding_rect(
+        vertices
+            .iter()
+            .filter(|(entity, _, _)| kept.contains(entity))
+            .map(|(_, _, position)| position.0),
+    )
+    .map_or(Vec2::ZERO, |rect| {
+        Vec2::new(rect.min.x, rect.min.y - GENERATOR_SPACING)
+    });
+    let entities: Vec<Entity> = graph
+        .nodes
+        .iter()
+        .map(|node| {
+            if let Some(&entity) = ids.get(&node.id) {
+                let (_, vertex, _) = vertices.get(entity).expect("the id is of a vertex");
+                if let Some(label) = &node.label
+                    && *label != vertex.label
+                {
+                    relabeled.push(VertexRenameAction {
+                        entity,
+                        name: vertex.label.clone(),
+                    });
+                    commands.trigger(VertexRenamedEvent {
+                        entity,
+                        new_label: label.clone(),
+                        manual: false,
+                    });
+                }
+                return entity;
+            }
+            let label = node.label.clone().unwrap_or_else(|| node.id.clone());
+            let position = next_position;
+            next_position.x += GENERATOR_SPACING;
+            let entity =
+                VertexBundle::spawn_with_label(&mut commands, &mut assets, position, &label);
+            inserted.vertices.push(SubgraphVertex {
+                entity,
+                position,
+                label,
+            });
+            entity
+        })
+        .collect();
+
+    // Existing edges by their endpoints. Edges with the same weight are
+    // matched first, the rest are reused with their weight changed.
+    // Edges of deleted vertices stay around hidden, they are left out.
+    let mut existing: HashMap<(Entity, Entity), Vec<(Entity, Option<f32>)>> = HashMap::new();
+    for (entity, edge, weight) in edges
+        .iter()
+        .filter(|(_, edge, _)| vertices.contains(edge.from) && vertices.contains(edge.to))
+    {
+        existing
+            .entry((edge.from, edge.to))
+            .or_default()
+            .push((entity, weight.map(|weight| weight.0)));
+    }
+    let mut unmatched = Vec::new();
+    for edge in &graph.edges {
+        let key = (entities[edge.from], entities[edge.to]);
+        let same = existing.get_mut(&key).and_then(|candidates| {
+            let index = candidates
+                .iter()
+                .position(|(_, weight)| *weight == edge.weight)?;
+            Some(candidates.swap_remove(index))
+        });
+        if same.is_none() {
+            unmatched.push((key, edge.weight));
+        }
+    }
+    let mut reweighted = Vec::new();
+    for ((from, to), weight) in unmatched {
+        if let Some((entity, old_weight)) = existing
+            .get_mut(&(from, to))
+            .and_then(|candidates| candidates.pop())
+        {
+            reweighted.push(EdgeWeightEditAction {
+                entity,
+                weight: old_weight,
+            });
+            commands.trigger(EdgeWeightEditedEvent {
+                entity,
+                weight,
+                manual: false,
+            });
+            continue;
+        }
+        let entity = DirectedEdgeBundle::spawn(from, to, &mut commands, &mut assets);
+        if let Some(weight) = weight {
+            commands.entity(entity).insert(EdgeWeight(weight));
+        }
+        inserted.edges.push(SubgraphEdge { entity, from, to });
+    }
+
+    let removed = SubgraphAction {
+        vertices: vertices
+            .iter()
+            .filter(|(entity, _, _)| !kept.contains(entity))
+            .map(|(entity, vertex, position)| SubgraphVertex {
+                entity,
+                position: position.0,
+                label: vertex.label.clone(),
+            })
+            .collect(),
+        edges: existing
+            .into_iter()
+            .flat_map(|((from, to), candidates)| {
+                candidates
+                    .into_iter()
+                    .map(move |(entity, _)| SubgraphEdge { entity, from, to })
+            })
+            .collect(),
+    };
+
+    let unchanged = removed.vertices.is_empty()
+        && removed.edges.is_empty()
+        && inserted.vertices.is_empty()
+        && inserted.edges.is_empty()
+        && relabeled.is_empty()
+        && reweighted.is_empty();
+    editor.error = None;
+    editor.reload();
+    if unchanged {
+        document_state.status = Some("No changes to apply".to_string());
+        return;
+    }
+
+    let gone: HashSet<Entity> = removed
+        .vertices
+        .iter()
+        .map(|vertex| vertex.entity)
+        .chain(removed.edges.iter().map(|edge| edge.entity))
+        .collect();
+    for entity in selected.iter().filter(|entity| gone.contains(entity)) {
+        commands.entity(entity).remove::<Selected>();
+    }
+    if hovered_entity.0.is_some_and(|entity| gone.contains(&entity)) {
+        hovered_entity.0 = None;
+    }
+
+    let removed = remove_subgraph(&mut commands, &removed, vertex_data);
+    undo_redo.push_undo(
+        UndoAction::UndoGraphReplaceAction(GraphReplaceAction {
+            removed,
+            inserted,
+            relabeled,
+            reweighted,
+        }),
+        &mut commands,
+    );
+    document_state.status = Some("Applied the text".to_string());
+}
+
+/// The selected vertices and the edges among them.
+fn selection_document(
+    selected: &Query<VertexData, With<Selected>>,
+    edges: &Query<EdgeData>,
+) -> GraphDocument {
+    GraphDocument::from_entities(selected.iter(), edges.iter())
+}
+
+/// Puts the document on both the internal and the system clipboard.
+fn copy_to_clipboard(
+    document: GraphDocument,
+    clipboard: &mut GraphClipboard,
+    system_clipboard: &mut EguiClipboard,
+) {
+    match document.to_ron() {
+        Ok(text) => system_clipboard.set_text(&text),
+        Err(error) => warn!("Could not serialize the copied graph: {error}"),
+    }
+    clipboard.0 = Some(document);
+}
+
+/// Makes the given vertices the only selected ones.
+fn replace_selection(
+    commands: &mut Commands,
+    selected: impl Iterator<Item = Entity>,
+    subgraph: &SubgraphAction,
+) {
+    for entity in selected {
+        commands.entity(entity).remove::<Selected>();
+    }
+    for vertex in &subgraph.vertices {
+        commands.entity(vertex.entity).insert(Selected);
+    }
+}
+
+pub fn on_copy_selection(
+    _copy: On<CopySelectionEvent>,
+    selected: Query<VertexData, With<Selected>>,
+    edges: Query<EdgeData>,
+    mut clipboard: ResMut<GraphClipboard>,
+    mut system_clipboard: ResMut<EguiClipboard>,
+) {
+    let document = selection_document(&selected, &edges);
+    if document.is_empty() {
+        return;
+    }
+    copy_to_clipboard(document, &mut clipboard, &mut system_clipboard);
+}
+
+/// Cutting deletes the selected vertices as one undo step. Their edges
+/// are hidden the same way as when deleting a single vertex.
+pub fn on_cut_selection(
+    _cut: On<CutSelectionEvent>,
+    mut commands: Commands,
+    selected: Query<VertexData, With<Selected>>,
+    edges: Query<EdgeData>,
+    mut clipboard: ResMut<GraphClipboard>,
+    mut system_clipboard: ResMut<EguiClipboard>,
+    mut hovered_entity: ResMut<HoveredEntity>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+) {
+    let document = selection_document(&selected, &edges);
+    if document.is_empty() {
+        return;
+    }
+    copy_to_clipboard(document, &mut clipboard, &mut system_clipboard);
+
+    let vertices = selected
+        .iter()
+        .map(|(entity, vertex, position, ..)| {
+            commands
+                .entity(entity)
+                .despawn_children()
+                .remove::<(VertexBundle, Selected)>();
+            if hovered_entity.0 == Some(entity) {
+                hovered_entity.0 = None;
+            }
+            SubgraphVertex {
+                entity,
+                position: position.0,
+                label: vertex.label.clone(),
+            }
+        })
+        .collect();
+
+    undo_redo.push_undo(
+        UndoAction::UndoGraphDeletionAction(SubgraphAction {
+            vertices,
+            edges: Vec::new(),
+        }),
+        &mut commands,
+    );
+}
+
+/// Pastes the system clipboard if it holds a graph, as RON or as CSV cells
+/// from a spreadsheet, the internal one otherwise. The pasted vertices
+/// become the selection.
+pub fn on_paste(
+    paste: On<PasteEvent>,
+    mut commands: Commands,
+    mut assets: GraphAssets,
+    clipboard: Res<GraphClipboard>,
+    mut system_clipboard: ResMut<EguiClipboard>,
+    selected: Query<Entity, With<Selected>>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+) {
+    let text = system_clipboard.get_text();
+    let document = text
+        .as_deref()
+        .and_then(|text| GraphDocument::from_ron(text).ok())
+        .or_else(|| text.as_deref().and_then(|text| document_from_csv(text).ok()))
+        .or_else(|| clipboard.0.clone());
+    let Some(document) = document.filter(|document| !document.is_empty()) else {
+        return;
+    };
+
+    let offset = match paste.world_position {
+        Some(world_position) => world_position - document.center(),
+        None => DUPLICATE_OFFSET,
+    };
+    let subgraph = spawn_document(
+        &mut commands,
+        &mut assets,
+        &document,
+        offset,
+        false,
+    );
+    replace_selection(&mut commands, selected.iter(), &subgraph);
+    undo_redo.push_undo(UndoAction::UndoGraphInsertionAction(subgraph), &mut commands);
+}
+
+pub fn on_duplicate_selection(
+    _duplicate: On<DuplicateSelectionEvent>,
+    mut commands: Commands,
+    mut assets: GraphAssets,
+    selected: Query<VertexData, With<Selected>>,
+    edges: Query<EdgeData>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+) {
+    let document = selection_document(&selected, &edges);
+    if document.is_empty() {
+        return;
+    }
+
+    let subgraph = spawn_document(
+        &mut commands,
+        &mut assets,
+        &document,
+        DUPLICATE_OFFSET,
+        false,
+    );
+    replace_selection(
+        &mut commands,
+        selected.iter().map(|(entity, ..)| entity),
+        &subgraph,
+    );
+    undo_redo.push_undo(UndoAction::UndoGraphInsertionAction(subgraph), &mut commands);
+}
+
+/// Runs an action from its shortcut or the command palette.
+pub fn on_run_action(
+    run: On<RunActionEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    mut panels: PanelStates,
+    document_state: Res<DocumentState>,
+    window: Single<&Window>,
+    camera: Single<
+        (&Camera, &GlobalTransform, &mut Transform, &mut Projection),
+        With<Camera2d>,
+    >,
+    vertices: Query<(Entity, &Position, Has<Selected>), With<Vertex>>,
+    selected: Query<Entity, With<Selected>>,
+) {
+    let (camera, camera_transform, mut transform, mut projection) = camera.into_inner();
+
+    match run.action {
+        EditorAction::Undo => undo_redo.undo(commands.reborrow()),
+        EditorAction::Redo => undo_redo.redo(commands.reborrow()),
+        EditorAction::Copy => commands.trigger(CopySelectionEvent),
+        EditorAction::Cut => commands.trigger(CutSelectionEvent),
+        EditorAction::Paste => commands.trigger(PasteEvent {
+            world_position: cursor_world_position(&window, camera, camera_transform),
+        }),
+        EditorAction::Duplicate => commands.trigger(DuplicateSelectionEvent),
+        EditorAction::SelectAll => {
+            for (entity, ..) in &vertices {
+                commands.entity(entity).insert(Selected);
+            }
+        }
+        EditorAction::ClearSelection => {
+            for entity in &selected {
+                commands.entity(entity).remove::<Selected>();
+            }
+        }
+        EditorAction::FrameAll | EditorAction::FrameSelection => {
+            let only_selected = run.action == EditorAction::FrameSelection;
+            let framed = bounding_rect(
+                vertices
+                    .iter()
+                    .filter(|(.., is_selected)| *is_selected || !only_selected)
+                    .map(|(_, position, _)| position.0),
+            );
+            if let Some(rect) = framed {
+                frame_rect(rect, window.size(), &mut transform, &mut projection);
+            }
+        }
+        EditorAction::Search => {
+            panels.search.open = true;
+            panels.search.focus = true;
+        }
+        EditorAction::CommandPalette => {
+            let palette = panels.palette.as_mut();
+            palette.open = true;
+            palette.focus = true;
+            palette.query.clear();
+            palette.highlighted = 0;
+        }
+        EditorAction::Save => commands.trigger(SaveDocumentEvent {
+            path: document_state.path.clone(),
+        }),
+        EditorAction::Open => commands.trigger(OpenDocumentEvent {
+            path: document_state.path.clone(),
+            source: DocumentSource::Document,
+        }),
+        EditorAction::Export(format) => {
+            let path =
+                std::path::Path::new(&document_state.path).with_extension(format.extension());
+            commands.trigger(ExportDocumentEvent {
+                path: path.to_string_lossy().into_owned(),
+                format,
+            });
+        }
+        EditorAction::Import(format) => {
+            let path =
+                std::path::Path::new(&document_state.path).with_extension(format.extension());
+            commands.trigger(OpenDocumentEvent {
+                path: path.to_string_lossy().into_owned(),
+                source: DocumentSource::Import(format),
+            });
+        }
+        EditorAction::ExportMetrics => commands.trigger(ExportMetricsEvent),
+        EditorAction::Generate => panels.generator.open = true,
+        EditorAction::MergeVertices => panels.merge.open = true,
+        EditorAction::Settings => panels.settings.open = true,
+        EditorAction::ToggleStatistics => panels.statistics.open = !panels.statistics.open,
+        EditorAction::ToggleMetrics => panels.metrics.open = !panels.metrics.open,
+        EditorAction::ToggleInspector => panels.inspector.open = !panels.inspector.open,
+        EditorAction::ToggleMinimap => panels.minimap.open = !panels.minimap.open,
+        EditorAction::ToggleTextEditor => panels.text_editor.open = !panels.text_editor.open,
+        EditorAction::ToggleAdjacencyMatrix => panels.matrix.open = !panels.matrix.open,
+        EditorAction::ToggleTables => panels.tables.open = !panels.tables.open,
+        // Gestures are handled by the picking observers.
+        EditorAction::SpawnVertex
+        | EditorAction::MoveVertex
+        | EditorAction::DrawEdge
+        | EditorAction::RenameVertex
+        | EditorAction::DeleteVertex
+        | EditorAction::DeleteEdge
+        | EditorAction::SplitEdge
+        | EditorAction::ReconnectEdge
+        | EditorAction::ContractEdge
+        | EditorAction::ToggleSelected
+        | EditorAction::RectangleSelection
+        | EditorAction::LassoSelection => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::graph::{
+        resources::{HandleCache, VertexIds},
+        text_editor::graph_to_text,
+        undo_redo_observers::{on_redo_vertex_merge, on_undo_vertex_merge},
+    };
+
+    /// The labels and positions of the live vertices and the ends of the
+    /// live edges, in a fixed order.
+    type Snapshot = (Vec<(Entity, String, Vec2)>, Vec<(Entity, Entity, Entity)>);
+
+    fn snapshot(app: &mut App) -> Snapshot {
+        let world = app.world_mut();
+        let mut vertices: Vec<_> = world
+            .query::<(Entity, &Vertex, &Position)>()
+            .iter(world)
+            .map(|(entity, vertex, position)| (entity, vertex.label.clone(), position.0))
+            .collect();
+        vertices.sort_by_key(|(entity, ..)| *entity);
+        let mut edges: Vec<_> = world
+            .query::<(Entity, &DirectedEdge)>()
+            .iter(world)
+            .map(|(entity, edge)| (entity, edge.from, edge.to))
+            .collect();
+        edges.sort();
+        (vertices, edges)
+    }
+
+    fn undo(app: &mut App) {
+        app.world_mut()
+            .run_system_once(|mut undo_redo: ResMut<UndoRedoStack>, commands: Commands| {
+                undo_redo.undo(commands)
+            })
+            .unwrap();
+    }
+
+    fn redo(app: &mut App) {
+        app.world_mut()
+            .run_system_once(|mut undo_redo: ResMut<UndoRedoStack>, commands: Commands| {
+                undo_redo.redo(commands)
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn merging_keeps_edges_the_survivor_had_and_undoes_exactly() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_asset::<ColorMaterial>()
+            .insert_resource(HoveredEntity(None))
+            .insert_resource(HandleCache::default())
+            .insert_resource(Settings::default())
+            .insert_resource(VertexIds::default())
+            .insert_resource(UndoRedoStack::default())
+            .add_observer(on_vertex_renamed)
+            .add_observer(on_merge_vertices)
+            .add_observer(on_undo_vertex_merge)
+            .add_observer(on_redo_vertex_merge);
+
+        let (vertices, edges) = app
+            .world_mut()
+            .run_system_once(|mut commands: Commands, mut assets: GraphAssets| {
+                let vertices = [
+                    ("s", Vec2::ZERO),
+                    ("a", Vec2::X),
+                    ("b", Vec2::Y),
+                    ("c", Vec2::ONE),
+                ]
+                .map(|(label, position)| {
+                    VertexBundle::spawn_with_label(&mut commands, &mut assets, position, label)
+                });
+                let [s, a, b, c] = vertices;
+                let edges = [
+                    (s, s),
+                    (s, c),
+                    (s, c),
+                    (a, c),
+                    (a, b),
+                    (b, a),
+                    (c, b),
+                    (b, c),
+                ]
+                .map(|(from, to)| DirectedEdgeBundle::spawn(from, to, &mut commands, &mut assets));
+                (vertices, edges)
+            })
+            .unwrap();
+        let [s, a, b, c] = vertices;
+        let before = snapshot(&mut app);
+
+        app.world_mut().trigger(MergeVerticesEvent {
+            vertices: vec![s, a, b],
+            label: "merged".to_string(),
+            drop_loops: true,
+            drop_duplicates: true,
+        });
+        app.world_mut().flush();
+        let merged = snapshot(&mut app);
+        // The loop and the parallel edges of `s` stay, of the moved edges only
+        // `c -> b` neither becomes a loop nor duplicates an edge of `s`.
+        let center = Vec2::new(1.0, 1.0) / 3.0;
+        let mut expected_vertices = vec![
+            (s, "merged".to_string(), center),
+            (c, "c".to_string(), Vec2::ONE),
+        ];
+        expected_vertices.sort_by_key(|(entity, ..)| *entity);
+        let mut expected_edges = vec![
+            (edges[0], s, s),
+            (edges[1], s, c),
+            (edges[2], s, c),
+            (edges[6], c, s),
+        ];
+        expected_edges.sort();
+        assert_eq!(merged, (expected_vertices, expected_edges));
+
+        undo(&mut app);
+        assert_eq!(snapshot(&mut app), before);
+        redo(&mut app);
+        assert_eq!(snapshot(&mut app), merged);
+        undo(&mut app);
+        assert_eq!(snapshot(&mut app), before);
+    }
+
+    #[test]
+    fn applying_the_text_of_the_live_graph_changes_nothing() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_asset::<ColorMaterial>()
+            .insert_resource(HoveredEntity(None))
+            .insert_resource(HandleCache::default())
+            .insert_resource(Settings::default())
+            .insert_resource(VertexIds::default())
+            .insert_resource(UndoRedoStack::default())
+            .insert_resource(TextEditorState::default())
+            .insert_resource(DocumentState::default())
+            .add_observer(on_delete_elements)
+            .add_observer(on_apply_graph_text);
+
+        let [_, b, _] = app
+            .world_mut()
+            .run_system_once(|mut commands: Commands, mut assets: GraphAssets| {
+                let vertices = ["a", "b", "c"].map(|label| {
+                    VertexBundle::spawn_with_label(&mut commands, &mut assets, Vec2::ZERO, label)
+                });
+                let [a, b, c] = vertices;
+                for (from, to) in [(a, b), (b, c), (a, c)] {
+                    DirectedEdgeBundle::spawn(from, to, &mut commands, &mut assets);
+                }
+                vertices
+            })
+            .unwrap();
+        app.world_mut()
+            .trigger(DeleteElementsEvent { entities: vec![b] });
+        app.world_mut().flush();
+        let deleted = snapshot(&mut app);
+        let state = app.world().resource::<UndoRedoStack>().state();
+
+        // The text the editor shows, without the edges of `b`.
+        let text = app
+            .world_mut()
+            .run_system_once(
+                |vertices: Query<(Entity, &Vertex)>, edges: Query<&DirectedEdge>| {
+                    graph_to_text(
+                        vertices
+                            .iter()
+                            .map(|(entity, vertex)| (entity, vertex.label.as_str())),
+                        edges
+                            .iter()
+                            .filter(|edge| {
+                                vertices.contains(edge.from) && vertices.contains(edge.to)
+                            })
+                            .map(|edge| (edge.from, edge.to, None)),
+                    )
+                },
+            )
+            .unwrap();
+        app.world_mut().trigger(ApplyGraphTextEvent { text });
+        app.world_mut().flush();
+
+        assert_eq!(snapshot(&mut app), deleted);
+        assert_eq!(app.world().resource::<UndoRedoStack>().state(), state);
+        assert_eq!(
+            app.world().resource::<DocumentState>().status.as_deref(),
+            Some("No changes to apply")
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::graph::components::{
+    Attribute, Attributes, DirectedEdge, EdgeStyle, EdgeWeight, Position, Vertex, VertexId,
+    VertexStyle,
+};
+
+/// What a document is built from for every vertex.
+pub type VertexData<'a> = (
+    Entity,
+    &'a Vertex,
+    &'a Position,
+    Option<&'a Attributes>,
+    Option<&'a VertexStyle>,
+    Option<&'a VertexId>,
+);
+
+/// What a document is built from for every edge.
+pub type EdgeData<'a> = (
+    &'a DirectedEdge,
+    Option<&'a EdgeWeight>,
+    Option<&'a Attributes>,
+    Option<&'a EdgeStyle>,
+);
+
+/// A plain, entity free description of a (sub)graph.
+/// This is what gets copied to the clipboard, and
+/// what text based formats are read from and written to.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GraphDocument {
+    pub vertices: Vec<VertexRecord>,
+    pub edges: Vec<EdgeRecord>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct VertexRecord {
+    /// The `VertexId`, kept when the document is opened again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    pub label: String,
+    pub x: f32,
+    pub y: f32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<Attribute>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<VertexStyle>,
+}
+
+/// Edges refer to vertices by their index in `GraphDocument::vertices`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EdgeRecord {
+    pub from: usize,
+    pub to: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<Attribute>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<EdgeStyle>,
+}
+
+impl GraphDocument {
+    /// Edges are only kept if both of their vertices are part of the document.
+    pub fn from_entities<'a>(
+        vertices: impl Iterator<Item = VertexData<'a>>,
+        edges: impl Iterator<Item = EdgeData<'a>>,
+    ) -> Self {
+        let mut index = HashMap::new();
+        let vertices = vertices
+            .enumerate()
+            .map(
+                |(position_in_document, (entity, vertex, position, attributes, style, id))| {
+                    index.insert(entity, position_in_document);
+                    VertexRecord {
+                        id: id.map(|id| id.0),
+                        label: vertex.label.clone(),
+                        x: position.0.x,
+                        y: position.0.y,
+                        attributes: attributes
+                            .map(|attributes| attributes.0.clone())
+                            .unwrap_or_default(),
+                        style: style.cloned(),
+                    }
+                },
+            )
+            .collect();
+        let edges = edges
+            .filter_map(|(edge, weight, attributes, style)| {
+                Some(EdgeRecord {
+                    from: *index.get(&edge.from)?,
+                    to: *index.get(&edge.to)?,
+                    weight: weight.map(|weight| weight.0),
+                    attributes: attributes
+                        .map(|attributes| attributes.0.clone())
+                        .unwrap_or_default(),
+                    style: style.cloned(),
+                })
+            })
+            .collect();
+
+        GraphDocument { vertices, edges }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Center of the bounding box of the vertices.
+    pub fn center(&self) -> Vec2 {
+        let (min, max) = self.vertices.iter().fold(
+            (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+            |(min, max), vertex| {
+                let position = Vec2::new(vertex.x, vertex.y);
+                (min.min(position), max.max(position))
+            },
+        );
+        if self.is_empty() {
+            Vec2::ZERO
+        } else {
+            (min + max) / 2.0
+        }
+    }
+
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, PrettyConfig::default().struct_names(true))
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+}
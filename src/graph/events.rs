@@ -1,5 +1,12 @@
 use bevy::{prelude::*, window::CursorIcon};
 
+use crate::graph::{
+    actions::EditorAction,
+    components::{Attribute, EdgeEnd, ElementStyle},
+    formats::{ExportFormat, ImportFormat},
+    generators::{GeneratorKind, GeneratorParameters},
+};
+
 /// Fires when a vertex rename is successful.
 #[derive(Event)]
 pub struct VertexRenamedEvent {
@@ -33,18 +40,29 @@ pub struct CanvasClickedEvent {
 pub struct VertexClickedEvent {
     /// Vertex entity.
     pub entity: Entity,
+    /// Which button was used for the click.
+    pub button: PointerButton,
     /// Where on the canvas.
     pub world_position: Vec2,
 }
 
+/// The end of dragging a handle of an edge.
+#[derive(Event)]
+pub struct EdgeEndDroppedEvent {
+    pub entity: Entity,
+    /// The end that was dragged.
+    pub end: EdgeEnd,
+    pub world_position: Vec2,
+}
+
 /// The end of the dragging event on
 /// a vertex.
 #[derive(Event)]
 pub struct VertexDragDroppedEvent {
     /// Which vertex entity.
     pub entity: Entity,
-    /// Which button started the drag.
-    pub button: PointerButton,
+    /// What the drag does, decided when it started.
+    pub action: EditorAction,
     /// Where did the drag end on the canvas.
     pub world_position: Vec2,
 }
@@ -54,8 +72,8 @@ pub struct VertexDragDroppedEvent {
 pub struct VertexDraggingEvent {
     /// Which vertex entity.
     pub entity: Entity,
-    /// Which button started the drag.
-    pub button: PointerButton,
+    /// What the drag does, decided when it started.
+    pub action: EditorAction,
     /// Where did the drag move on the canvas.
     pub world_position: Vec2,
 }
@@ -80,3 +98,149 @@ pub struct UpdateCursorIconEvent {
     /// into the window.
     pub new_icon: CursorIcon,
 }
+
+/// Requests a generated graph to be spawned
+/// around the center of the view.
+#[derive(Event)]
+pub struct GenerateGraphEvent {
+    pub kind: GeneratorKind,
+    pub parameters: GeneratorParameters,
+}
+
+/// Copies the selected vertices and the edges among them.
+#[derive(Event)]
+pub struct CopySelectionEvent;
+
+/// Copies, then deletes the selection as a single undo step.
+#[derive(Event)]
+pub struct CutSelectionEvent;
+
+/// Pastes the clipboard as new vertices and edges.
+#[derive(Event)]
+pub struct PasteEvent {
+    /// Where the center of the pasted graph should land,
+    /// `None` if the cursor is not over the window.
+    pub world_position: Option<Vec2>,
+}
+
+/// Copies and pastes the selection next to itself
+/// without touching the clipboard.
+#[derive(Event)]
+pub struct DuplicateSelectionEvent;
+
+/// Replaces the attributes of a vertex or an edge.
+#[derive(Event)]
+pub struct AttributesEditedEvent {
+    pub entity: Entity,
+    pub attributes: Vec<Attribute>,
+    /// `false` when triggered by the undo/redo logic.
+    pub manual: bool,
+}
+
+/// Sets or clears the weight of an edge.
+#[derive(Event)]
+pub struct EdgeWeightEditedEvent {
+    pub entity: Entity,
+    pub weight: Option<f32>,
+    /// `false` when triggered by the undo/redo logic.
+    pub manual: bool,
+}
+
+/// Replaces the style of a vertex or an edge.
+#[derive(Event)]
+pub struct StyleEditedEvent {
+    pub entity: Entity,
+    pub style: ElementStyle,
+    /// `false` when triggered by the undo/redo logic.
+    pub manual: bool,
+}
+
+/// Writes the whole graph to a file.
+#[derive(Event)]
+pub struct SaveDocumentEvent {
+    pub path: String,
+}
+
+/// Replaces the whole graph with the one in a file, as a single undo step.
+#[derive(Event)]
+pub struct OpenDocumentEvent {
+    pub path: String,
+    pub source: DocumentSource,
+}
+
+/// What kind of file an `OpenDocumentEvent` reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentSource {
+    /// A saved document, the graph counts as saved afterwards.
+    Document,
+    /// Autosaved work, which keeps the document path and stays unsaved.
+    Recovery,
+    /// A file of another format, which stays unsaved like recovered work.
+    Import(ImportFormat),
+}
+
+/// Writes the whole graph to a file in another format.
+#[derive(Event)]
+pub struct ExportDocumentEvent {
+    pub path: String,
+    pub format: ExportFormat,
+}
+
+/// Writes the metrics of every vertex into a CSV file next to the document.
+#[derive(Event)]
+pub struct ExportMetricsEvent;
+
+/// Runs an action, fired by its shortcut or from the command palette.
+#[derive(Event)]
+pub struct RunActionEvent {
+    pub action: EditorAction,
+}
+
+/// Replaces the graph with the one described in the text editor,
+/// keeping what didn't change, as a single undo step.
+#[derive(Event)]
+pub struct ApplyGraphTextEvent {
+    pub text: String,
+}
+
+/// Draws an edge between two vertices, or deletes the edges
+/// between them if there are any, as a single undo step.
+#[derive(Event)]
+pub struct ToggleEdgeEvent {
+    pub from: Entity,
+    pub to: Entity,
+}
+
+/// Deletes the vertices and edges among the entities as a single undo step.
+#[derive(Event)]
+pub struct DeleteElementsEvent {
+    pub entities: Vec<Entity>,
+}
+
+/// Adds the attribute to every entity, replacing the
+/// one with the same key, as a single undo step.
+#[derive(Event)]
+pub struct SetAttributeEvent {
+    pub entities: Vec<Entity>,
+    pub attribute: Attribute,
+}
+
+/// Sets the fill of the vertices and the colour of the
+/// edges among the entities, as a single undo step.
+#[derive(Event)]
+pub struct RecolorEvent {
+    pub entities: Vec<Entity>,
+    /// Non-premultiplied sRGBA.
+    pub color: [f32; 4],
+}
+
+/// Merges the vertices into the first one, which gets the label.
+#[derive(Event)]
+pub struct MergeVerticesEvent {
+    pub vertices: Vec<Entity>,
+    pub label: String,
+    /// Drop the edges that become loops on the merged vertex.
+    pub drop_loops: bool,
+    /// Keep only one of the edges that end up with the same ends.
+    pub drop_duplicates: bool,
+}
@@ -0,0 +1,471 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::graph::{
+    document::{EdgeRecord, GraphDocument, VertexRecord},
+    generators::circle_layout,
+    helpers::escape_csv_field,
+};
+
+/// One `source,target,weight` row per edge, below a header. Vertices
+/// without edges get a row of their own with an empty target.
+pub fn document_to_edge_list(document: &GraphDocument) -> Result<String, String> {
+    check_unique_labels(document)?;
+
+    let mut csv = String::from("source,target,weight\n");
+    let mut connected = vec![false; document.vertices.len()];
+    for edge in &document.edges {
+        connected[edge.from] = true;
+        connected[edge.to] = true;
+        csv.push_str(&escape_csv_field(&document.vertices[edge.from].label));
+        csv.push(',');
+        csv.push_str(&escape_csv_field(&document.vertices[edge.to].label));
+        csv.push(',');
+        if let Some(weight) = edge.weight {
+            csv.push_str(&weight.to_string());
+        }
+        csv.push('\n');
+    }
+    for (vertex, _) in document
+        .vertices
+        .iter()
+        .zip(connected)
+        .filter(|(_, connected)| !connected)
+    {
+        csv.push_str(&escape_csv_field(&vertex.label));
+        csv.push_str(",,\n");
+    }
+    Ok(csv)
+}
+
+/// A square matrix with the labels in the first row and column. A cell
+/// holds the weight of the edge from its row to its column, 1 for
+/// unweighted edges and 0 for none. Of parallel edges only the first counts.
+pub fn document_to_adjacency_matrix(document: &GraphDocument) -> Result<String, String> {
+    check_unique_labels(document)?;
+
+    let n = document.vertices.len();
+    let mut matrix: Vec<Option<Option<f32>>> = vec![None; n * n];
+    for edge in &document.edges {
+        let cell = &mut matrix[edge.from * n + edge.to];
+        if cell.is_none() {
+            *cell = Some(edge.weight);
+        }
+    }
+
+    let mut csv = String::new();
+    for vertex in &document.vertices {
+        csv.push(',');
+        csv.push_str(&escape_csv_field(&vertex.label));
+    }
+    csv.push('\n');
+    for (row, vertex) in document.vertices.iter().enumerate() {
+        csv.push_str(&escape_csv_field(&vertex.label));
+        for cell in &matrix[row * n..(row + 1) * n] {
+            csv.push(',');
+            match cell {
+                None => csv.push('0'),
+                Some(None) => csv.push('1'),
+                Some(Some(weight)) => csv.push_str(&weight.to_string()),
+            }
+        }
+        csv.push('\n');
+    }
+    Ok(csv)
+}
+
+/// Reads pasted or loaded CSV, as an adjacency matrix if its top left
+/// cell is empty or it is a square grid of numbers, as an edge list
+/// otherwise.
+pub fn csv_to_document(text: &str) -> Result<GraphDocument, String> {
+    let rows = parse_rows(text)?;
+    if rows[0][0].is_empty() || is_numeric_square(&rows) {
+        adjacency_matrix_to_document(text)
+    } else {
+        edge_list_to_document(text)
+    }
+}
+
+/// Like [`csv_to_document`], but refuses text that is more likely prose
+/// with a comma in it than cells: a single line, a line without a
+/// separator, or a label with spaces around it, as in `foo, bar`.
+pub fn pasted_csv_to_document(text: &str) -> Result<GraphDocument, String> {
+    let rows = parse_rows(text)?;
+    let cells = rows.len() >= 2 && rows.iter().all(|row| row.len() >= 2);
+    let padded = rows
+        .iter()
+        .flatten()
+        .any(|field| field.trim() != field && field.trim().parse::<f32>().is_err());
+    if !cells || padded {
+        return Err("The text does not look like a graph".to_string());
+    }
+    csv_to_document(text)
+}
+
+/// Reads `source,target[,weight]` rows, creating a vertex for every
+/// label the first time it shows up. A header row is skipped, a row
+/// with an empty target only creates its source.
+pub fn edge_list_to_document(text: &str) -> Result<GraphDocument, String> {
+    let rows = parse_rows(text)?;
+    let mut document = GraphDocument::default();
+    let mut indices: HashMap<String, usize> = HashMap::new();
+    let mut vertex = |document: &mut GraphDocument, label: &str| {
+        *indices.entry(label.to_string()).or_insert_with(|| {
+            document.vertices.push(VertexRecord {
+                label: label.to_string(),
+                ..Default::default()
+            });
+            document.vertices.len() - 1
+        })
+    };
+
+    for (number, row) in rows.iter().enumerate() {
+        let is_header = number == 0
+            && row.len() >= 2
+            && row[0].eq_ignore_ascii_case("source")
+            && row[1].eq_ignore_ascii_case("target");
+        if is_header {
+            continue;
+        }
+        let line = number + 1;
+        let (source, target, weight) = match row.as_slice() {
+            [source, target] => (source, target, None),
+            [source, target, weight] => (source, target, Some(weight)),
+            _ => {
+                return Err(format!(
+                    "Row {line} has {} fields, expected source,target[,weight]",
+                    row.len()
+                ));
+            }
+        };
+        if source.is_empty() {
+            return Err(format!("Row {line} has no source"));
+        }
+
+        let from = vertex(&mut document, source);
+        if target.is_empty() {
+            continue;
+        }
+        let to = vertex(&mut document, target);
+        let weight = match weight.map(|weight| weight.trim()) {
+            None | Some("") => None,
+            Some(weight) => Some(
+                weight
+                    .parse()
+                    .map_err(|_| format!("Row {line}: '{weight}' is not a weight"))?,
+            ),
+        };
+        document.edges.push(EdgeRecord {
+            from,
+            to,
+            weight,
+            attributes: Vec::new(),
+            style: None,
+        });
+    }
+
+    lay_out(&mut document);
+    Ok(document)
+}
+
+/// Reads a square matrix, with labels in the first row and column if the
+/// top left cell is empty, otherwise the vertices are numbered. Cells
+/// other than 0 or empty are edges, weighted unless they are 1.
+pub fn adjacency_matrix_to_document(text: &str) -> Result<GraphDocument, String> {
+    let mut rows = parse_rows(text)?;
+    let labelled = rows[0][0].is_empty();
+    let labels: Vec<String> = if labelled {
+        rows.remove(0).split_off(1)
+    } else {
+        (1..=rows.len()).map(|index| index.to_string()).collect()
+    };
+
+    let n = labels.len();
+    if rows.len() != n {
+        return Err(format!(
+            "The adjacency matrix is not square: {} rows, {n} columns",
+            rows.len()
+        ));
+    }
+    let mut seen = HashSet::new();
+    for label in &labels {
+        if !seen.insert(label) {
+            return Err(format!("The label '{label}' is used twice"));
+        }
+    }
+    for (index, (row, label)) in rows.iter_mut().zip(&labels).enumerate() {
+        if labelled {
+            let row_label = row.remove(0);
+            if row_label != *label {
+                return Err(format!(
+                    "Row {} is labelled '{row_label}' instead of '{label}'",
+                    index + 1
+                ));
+            }
+        }
+        if row.len() != n {
+            return Err(format!(
+                "The adjacency matrix is not square: row {} has {} values, expected {n}",
+                index + 1,
+                row.len()
+            ));
+        }
+    }
+
+    let mut document = GraphDocument {
+        vertices: labels
+            .into_iter()
+            .map(|label| VertexRecord {
+                label,
+                ..Default::default()
+            })
+            .collect(),
+        edges: Vec::new(),
+    };
+    for (from, row) in rows.iter().enumerate() {
+        for (to, cell) in row.iter().enumerate() {
+            let cell = cell.trim();
+            if cell.is_empty() {
+                continue;
+            }
+            let value: f32 = cell.parse().map_err(|_| {
+                format!(
+                    "'{cell}' in row {}, column {} is not a number",
+                    from + 1,
+                    to + 1
+                )
+            })?;
+            if value == 0.0 {
+                continue;
+            }
+            document.edges.push(EdgeRecord {
+                from,
+                to,
+                weight: (value != 1.0).then_some(value),
+                attributes: Vec::new(),
+                style: None,
+            });
+        }
+    }
+
+    lay_out(&mut document);
+    Ok(document)
+}
+
+/// Spreadsheets copy their cells separated by tabs, files use commas.
+/// Fields may be quoted, blank lines are skipped.
+fn parse_rows(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let first_line = text.lines().next().unwrap_or_default();
+    let separator = if first_line.contains('\t') { '\t' } else { ',' };
+
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut characters = text.chars().peekable();
+    while let Some(character) = characters.next() {
+        match character {
+            '"' if quoted && characters.peek() == Some(&'"') => {
+                field.push('"');
+                characters.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            _ if quoted => field.push(character),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|field| !field.is_empty()) || row.len() > 1 {
+                    rows.push(std::mem::take(&mut row));
+                } else {
+                    row.clear();
+                }
+            }
+            _ if character == separator => row.push(std::mem::take(&mut field)),
+            _ => field.push(character),
+        }
+    }
+    if quoted {
+        return Err("A quoted field is never closed".to_string());
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    if rows.is_empty() {
+        return Err("There is nothing to read".to_string());
+    }
+    Ok(rows)
+}
+
+/// An unlabelled adjacency matrix: as many rows as columns, all numbers.
+fn is_numeric_square(rows: &[Vec<String>]) -> bool {
+    rows.iter().all(|row| {
+        row.len() == rows.len()
+            && row
+                .iter()
+                .all(|cell| cell.trim().is_empty() || cell.trim().parse::<f32>().is_ok())
+    })
+}
+
+/// Labels are all a CSV file has to tell vertices apart.
+fn check_unique_labels(document: &GraphDocument) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for vertex in &document.vertices {
+        if !seen.insert(vertex.label.as_str()) {
+            return Err(format!(
+                "The label '{}' is used by more than one vertex",
+                vertex.label
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn lay_out(document: &mut GraphDocument) {
+    let layout = circle_layout(document.vertices.len());
+    for (vertex, position) in document.vertices.iter_mut().zip(layout) {
+        (vertex.x, vertex.y) = (position.x, position.y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(labels: &[&str], edges: &[(usize, usize, Option<f32>)]) -> GraphDocument {
+        GraphDocument {
+            vertices: labels
+                .iter()
+                .map(|label| VertexRecord {
+                    label: label.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            edges: edges
+                .iter()
+                .map(|&(from, to, weight)| EdgeRecord {
+                    from,
+                    to,
+                    weight,
+                    attributes: Vec::new(),
+                    style: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn labels(document: &GraphDocument) -> Vec<&str> {
+        document
+            .vertices
+            .iter()
+            .map(|vertex| vertex.label.as_str())
+            .collect()
+    }
+
+    fn edges(document: &GraphDocument) -> Vec<(usize, usize, Option<f32>)> {
+        document
+            .edges
+            .iter()
+            .map(|edge| (edge.from, edge.to, edge.weight))
+            .collect()
+    }
+
+    #[test]
+    fn edge_list_round_trip() {
+        let original = document(
+            &["a", "b, \"c\"", "lonely"],
+            &[(0, 1, Some(2.5)), (1, 0, None), (1, 1, None)],
+        );
+        let csv = document_to_edge_list(&original).unwrap();
+        let read = edge_list_to_document(&csv).unwrap();
+        assert_eq!(labels(&read), labels(&original));
+        assert_eq!(edges(&read), edges(&original));
+        assert_eq!(edges(&csv_to_document(&csv).unwrap()), edges(&original));
+    }
+
+    #[test]
+    fn adjacency_matrix_round_trip() {
+        let original = document(
+            &["a", "b", "c,d"],
+            &[(0, 1, Some(-3.0)), (2, 0, None), (1, 1, None)],
+        );
+        let csv = document_to_adjacency_matrix(&original).unwrap();
+        let read = adjacency_matrix_to_document(&csv).unwrap();
+        assert_eq!(labels(&read), labels(&original));
+        let mut expected = edges(&original);
+        expected.sort_by_key(|&(from, to, _)| (from, to));
+        assert_eq!(edges(&read), expected);
+        assert_eq!(edges(&csv_to_document(&csv).unwrap()), expected);
+    }
+
+    #[test]
+    fn duplicate_labels_are_rejected() {
+        let twice = document(&["a", "a"], &[(0, 1, None)]);
+        assert!(document_to_edge_list(&twice).is_err());
+        assert!(document_to_adjacency_matrix(&twice).is_err());
+        assert!(adjacency_matrix_to_document(",a,a\na,0,1\na,1,0").is_err());
+    }
+
+    #[test]
+    fn unlabelled_numeric_matrix_is_read_as_a_matrix() {
+        let triangle = csv_to_document("0,1,1\n1,0,1\n1,1,0").unwrap();
+        assert_eq!(labels(&triangle), ["1", "2", "3"]);
+        assert_eq!(triangle.edges.len(), 6);
+
+        // Not square, so an edge list of numbered vertices.
+        let edge_list = csv_to_document("1,2\n2,3\n3,1").unwrap();
+        assert_eq!(
+            edges(&edge_list),
+            [(0, 1, None), (1, 2, None), (2, 0, None)]
+        );
+    }
+
+    #[test]
+    fn malformed_matrices_are_rejected() {
+        assert!(adjacency_matrix_to_document(",a,b\na,0,1").is_err());
+        assert!(adjacency_matrix_to_document(",a,b\na,0,1\nb,1").is_err());
+        assert!(adjacency_matrix_to_document(",a,b\nb,0,1\na,1,0").is_err());
+        assert!(adjacency_matrix_to_document(",a,b\na,0,x\nb,1,0").is_err());
+    }
+
+    #[test]
+    fn malformed_edge_lists_are_rejected() {
+        assert!(edge_list_to_document("a").is_err());
+        assert!(edge_list_to_document("a,b,1,2").is_err());
+        assert!(edge_list_to_document(",b").is_err());
+        assert!(edge_list_to_document("a,b,heavy").is_err());
+        assert!(edge_list_to_document("").is_err());
+    }
+
+    #[test]
+    fn fields_may_be_quoted() {
+        let rows = parse_rows("\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\"\r\n").unwrap();
+        assert_eq!(rows, [["a,b", "say \"hi\"", "two\nlines"]]);
+        assert!(parse_rows("\"never closed,b").is_err());
+    }
+
+    #[test]
+    fn tabs_separate_cells_copied_from_spreadsheets() {
+        let rows = parse_rows("source\ttarget\na,b\tc\n\n").unwrap();
+        assert_eq!(rows, [["source", "target"], ["a,b", "c"]]);
+
+        let read = csv_to_document("\ta\tb\na\t0\t2\nb\t0\t0\n").unwrap();
+        assert_eq!(labels(&read), ["a", "b"]);
+        assert_eq!(edges(&read), [(0, 1, Some(2.0))]);
+    }
+
+    #[test]
+    fn prose_is_not_pasted_as_a_graph() {
+        assert!(pasted_csv_to_document("foo, bar").is_err());
+        assert!(pasted_csv_to_document("foo,bar").is_err());
+        assert!(pasted_csv_to_document("Hello, world\nGoodbye, world").is_err());
+        assert!(pasted_csv_to_document("a,b\nc").is_err());
+
+        assert!(pasted_csv_to_document("a,b\nb,c").is_ok());
+        assert!(pasted_csv_to_document("0, 1\n1, 0").is_ok());
+        let exported = document(&["a"], &[]);
+        let csv = document_to_edge_list(&exported).unwrap();
+        assert!(pasted_csv_to_document(&csv).is_ok());
+    }
+}
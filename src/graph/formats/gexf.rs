@@ -0,0 +1,105 @@
+use std::fmt::Write;
+
+use crate::graph::{
+    components::Attribute,
+    document::GraphDocument,
+    formats::{AttributeKeys, escape_xml},
+};
+
+/// GEXF 1.3 for Gephi, with positions and the colours and sizes of styled
+/// vertices in the viz namespace. Attributes are declared per name and type.
+pub fn document_to_gexf(document: &GraphDocument) -> String {
+    let vertex_keys = AttributeKeys::new(document.vertices.iter().map(|vertex| &vertex.attributes));
+    let edge_keys = AttributeKeys::new(document.edges.iter().map(|edge| &edge.attributes));
+
+    let mut gexf = String::new();
+    gexf.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    gexf.push_str(
+        "<gexf xmlns=\"http://gexf.net/1.3\" xmlns:viz=\"http://gexf.net/1.3/viz\" version=\"1.3\">\n",
+    );
+    gexf.push_str("  <graph defaultedgetype=\"directed\">\n");
+    write_attribute_declarations(&mut gexf, "node", &vertex_keys);
+    write_attribute_declarations(&mut gexf, "edge", &edge_keys);
+
+    gexf.push_str("    <nodes>\n");
+    for (index, vertex) in document.vertices.iter().enumerate() {
+        let _ = writeln!(
+            gexf,
+            "      <node id=\"{index}\" label=\"{}\">",
+            escape_xml(&vertex.label)
+        );
+        write_attribute_values(&mut gexf, &vertex_keys, &vertex.attributes);
+        let _ = writeln!(
+            gexf,
+            "        <viz:position x=\"{}\" y=\"{}\" z=\"0\"/>",
+            vertex.x, vertex.y
+        );
+        if let Some(style) = &vertex.style {
+            let [r, g, b, a] = style.fill;
+            let _ = writeln!(
+                gexf,
+                "        <viz:color r=\"{}\" g=\"{}\" b=\"{}\" a=\"{a}\"/>",
+                (r * 255.0).round() as u8,
+                (g * 255.0).round() as u8,
+                (b * 255.0).round() as u8,
+            );
+            let _ = writeln!(gexf, "        <viz:size value=\"{}\"/>", style.radius);
+        }
+        gexf.push_str("      </node>\n");
+    }
+    gexf.push_str("    </nodes>\n");
+
+    gexf.push_str("    <edges>\n");
+    for (index, edge) in document.edges.iter().enumerate() {
+        let _ = write!(
+            gexf,
+            "      <edge id=\"{index}\" source=\"{}\" target=\"{}\"",
+            edge.from, edge.to
+        );
+        if let Some(weight) = edge.weight {
+            let _ = write!(gexf, " weight=\"{weight}\"");
+        }
+        if edge.attributes.is_empty() {
+            gexf.push_str("/>\n");
+        } else {
+            gexf.push_str(">\n");
+            write_attribute_values(&mut gexf, &edge_keys, &edge.attributes);
+            gexf.push_str("      </edge>\n");
+        }
+    }
+    gexf.push_str("    </edges>\n");
+    gexf.push_str("  </graph>\n</gexf>\n");
+    gexf
+}
+
+fn write_attribute_declarations(gexf: &mut String, class: &str, keys: &AttributeKeys) {
+    if keys.is_empty() {
+        return;
+    }
+    let _ = writeln!(gexf, "    <attributes class=\"{class}\">");
+    for (index, (name, kind)) in keys.iter().enumerate() {
+        let _ = writeln!(
+            gexf,
+            "      <attribute id=\"{index}\" title=\"{}\" type=\"{kind}\"/>",
+            escape_xml(name)
+        );
+    }
+    gexf.push_str("    </attributes>\n");
+}
+
+fn write_attribute_values(gexf: &mut String, keys: &AttributeKeys, attributes: &[Attribute]) {
+    if attributes.is_empty() {
+        return;
+    }
+    gexf.push_str("        <attvalues>\n");
+    for attribute in attributes {
+        if let Some(index) = keys.index(attribute) {
+            let _ = writeln!(
+                gexf,
+                "          <attvalue for=\"{index}\" value=\"{}\"/>",
+                escape_xml(&attribute.value.to_string())
+            );
+        }
+    }
+    gexf.push_str("        </attvalues>\n");
+}
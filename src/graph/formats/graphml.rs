@@ -0,0 +1,494 @@
+use std::{collections::HashMap, fmt::Write};
+
+use bevy::prelude::*;
+
+use crate::graph::{
+    components::{Attribute, AttributeValue},
+    document::{EdgeRecord, GraphDocument, VertexRecord},
+    formats::{AttributeKeys, escape_xml},
+    generators::circle_layout,
+};
+
+/// Keys for the label and position of nodes and the weight of edges,
+/// with their id, element kind, name and type.
+const BUILT_IN_KEYS: [(&str, &str, &str); 4] = [
+    ("label", "node", "string"),
+    ("x", "node", "double"),
+    ("y", "node", "double"),
+    ("weight", "edge", "double"),
+];
+
+/// GraphML with keys for the label, position and weight, and one per
+/// attribute name and type. Colours are written as `#rrggbbaa` strings,
+/// so they come back as string attributes.
+pub fn document_to_graphml(document: &GraphDocument) -> String {
+    let vertex_keys = AttributeKeys::new(document.vertices.iter().map(|vertex| &vertex.attributes));
+    let edge_keys = AttributeKeys::new(document.edges.iter().map(|edge| &edge.attributes));
+
+    let mut graphml = String::new();
+    graphml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    graphml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    for (name, domain, kind) in BUILT_IN_KEYS {
+        write_key(&mut graphml, name, domain, name, kind);
+    }
+    for (prefix, domain, keys) in [("v", "node", &vertex_keys), ("e", "edge", &edge_keys)] {
+        for (index, (name, kind)) in keys.iter().enumerate() {
+            write_key(
+                &mut graphml,
+                &format!("{prefix}{index}"),
+                domain,
+                name,
+                kind,
+            );
+        }
+    }
+
+    graphml.push_str("  <graph id=\"G\" edgedefault=\"directed\">\n");
+    for (index, vertex) in document.vertices.iter().enumerate() {
+        let _ = writeln!(graphml, "    <node id=\"n{index}\">");
+        write_data(&mut graphml, "label", &vertex.label);
+        write_data(&mut graphml, "x", &vertex.x.to_string());
+        write_data(&mut graphml, "y", &vertex.y.to_string());
+        write_attributes(&mut graphml, "v", &vertex_keys, &vertex.attributes);
+        graphml.push_str("    </node>\n");
+    }
+    for edge in &document.edges {
+        let _ = writeln!(
+            graphml,
+            "    <edge source=\"n{}\" target=\"n{}\">",
+            edge.from, edge.to
+        );
+        if let Some(weight) = edge.weight {
+            write_data(&mut graphml, "weight", &weight.to_string());
+        }
+        write_attributes(&mut graphml, "e", &edge_keys, &edge.attributes);
+        graphml.push_str("    </edge>\n");
+    }
+    graphml.push_str("  </graph>\n</graphml>\n");
+    graphml
+}
+
+/// A key of a GraphML file. `domain` is the element kind it is for.
+struct Key {
+    name: String,
+    kind: String,
+    domain: String,
+    default: Option<String>,
+    /// Whether it holds the label, x, y or weight instead of an attribute.
+    built_in: bool,
+}
+
+impl Key {
+    fn is_for(&self, domain: &str) -> bool {
+        self.domain == domain || self.domain == "all"
+    }
+}
+
+/// Reads the first graph of a GraphML file. The label, x, y and weight keys
+/// are found by their name, every other key becomes an attribute. If several
+/// keys share one of these names, e.g. for an attribute named `label`, the one
+/// whose id is the name wins, then the first one. Nodes without a label are
+/// named after their id, and if any node has no position, all of them are
+/// laid out on a circle.
+pub fn graphml_to_document(text: &str) -> Result<GraphDocument, String> {
+    let xml =
+        roxmltree::Document::parse(text).map_err(|error| format!("Malformed XML: {error}"))?;
+    let root = xml.root_element();
+    if root.tag_name().name() != "graphml" {
+        return Err(format!(
+            "Not a GraphML file, the root element is <{}>",
+            root.tag_name().name()
+        ));
+    }
+
+    let mut keys: HashMap<&str, Key> = HashMap::new();
+    let mut declared = Vec::new();
+    for key in root.children().filter(|node| node.has_tag_name("key")) {
+        let id = key.attribute("id").ok_or("A <key> has no id")?;
+        let default = key
+            .children()
+            .find(|node| node.has_tag_name("default"))
+            .and_then(|default| default.text())
+            .map(str::to_string);
+        keys.insert(
+            id,
+            Key {
+                name: key.attribute("attr.name").unwrap_or(id).to_string(),
+                kind: key.attribute("attr.type").unwrap_or("string").to_string(),
+                domain: key.attribute("for").unwrap_or("all").to_string(),
+                default,
+                built_in: false,
+            },
+        );
+        declared.push(id);
+    }
+    // Data of undeclared keys is kept as a string attribute named after the id.
+    for data in xml.descendants().filter(|node| node.has_tag_name("data")) {
+        let id = data.attribute("key").ok_or("A <data> has no key")?;
+        keys.entry(id).or_insert_with(|| {
+            declared.push(id);
+            Key {
+                name: id.to_string(),
+                kind: "string".to_string(),
+                domain: "all".to_string(),
+                default: None,
+                built_in: false,
+            }
+        });
+    }
+    for (name, domain, _) in BUILT_IN_KEYS {
+        let candidates = || {
+            declared
+                .iter()
+                .filter(|id| keys[**id].name == name && keys[**id].is_for(domain))
+        };
+        let built_in = candidates()
+            .find(|id| **id == name)
+            .or_else(|| candidates().next())
+            .copied();
+        if let Some(key) = built_in.and_then(|id| keys.get_mut(id)) {
+            key.built_in = true;
+        }
+    }
+    let graph = root
+        .children()
+        .find(|node| node.has_tag_name("graph"))
+        .ok_or("The GraphML file has no <graph>")?;
+
+    let mut document = GraphDocument::default();
+    let mut indices = HashMap::new();
+    let mut positioned = true;
+    for node in graph.children().filter(|node| node.has_tag_name("node")) {
+        let id = node.attribute("id").ok_or("A <node> has no id")?;
+        let mut vertex = VertexRecord {
+            label: id.to_string(),
+            ..default()
+        };
+        let (mut x, mut y) = (None, None);
+        for (key, value) in data(node, &keys) {
+            match key.built_in.then_some(key.name.as_str()) {
+                Some("label") => vertex.label = value.to_string(),
+                Some("x") => x = Some(parse_number(key, value)? as f32),
+                Some("y") => y = Some(parse_number(key, value)? as f32),
+                _ => vertex.attributes.push(parse_attribute(key, value)?),
+            }
+        }
+        match (x, y) {
+            (Some(x), Some(y)) => (vertex.x, vertex.y) = (x, y),
+            _ => positioned = false,
+        }
+        if indices.insert(id, document.vertices.len()).is_some() {
+            return Err(format!("The node id '{id}' is used twice"));
+        }
+        document.vertices.push(vertex);
+    }
+    if !positioned {
+        let layout = circle_layout(document.vertices.len());
+        for (vertex, position) in document.vertices.iter_mut().zip(layout) {
+            (vertex.x, vertex.y) = (position.x, position.y);
+        }
+    }
+
+    for node in graph.children().filter(|node| node.has_tag_name("edge")) {
+        let endpoint = |attribute: &str| {
+            let id = node
+                .attribute(attribute)
+                .ok_or(format!("An <edge> has no {attribute}"))?;
+            indices
+                .get(id)
+                .copied()
+                .ok_or(format!("An <edge> refers to the unknown node '{id}'"))
+        };
+        let mut edge = EdgeRecord {
+            from: endpoint("source")?,
+            to: endpoint("target")?,
+            weight: None,
+            attributes: Vec::new(),
+            style: None,
+        };
+        for (key, value) in data(node, &keys) {
+            match key.built_in.then_some(key.name.as_str()) {
+                Some("weight") => edge.weight = Some(parse_number(key, value)? as f32),
+                _ => edge.attributes.push(parse_attribute(key, value)?),
+            }
+        }
+        document.edges.push(edge);
+    }
+
+    Ok(document)
+}
+
+/// The data of an element, followed by the defaults of
+/// the keys for its kind it has no data for.
+fn data<'a>(
+    element: roxmltree::Node<'a, '_>,
+    keys: &'a HashMap<&str, Key>,
+) -> Vec<(&'a Key, &'a str)> {
+    let mut data: Vec<(&Key, &str)> = element
+        .children()
+        .filter(|node| node.has_tag_name("data"))
+        .filter_map(|node| {
+            let key = keys.get(node.attribute("key")?)?;
+            Some((key, node.text().unwrap_or_default()))
+        })
+        .collect();
+
+    let domain = element.tag_name().name();
+    for key in keys.values() {
+        if let Some(default) = &key.default
+            && key.is_for(domain)
+            && !data.iter().any(|(present, _)| std::ptr::eq(*present, key))
+        {
+            data.push((key, default));
+        }
+    }
+    data
+}
+
+fn parse_number(key: &Key, value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("'{value}' of the key '{}' is not a number", key.name))
+}
+
+fn parse_attribute(key: &Key, value: &str) -> Result<Attribute, String> {
+    let value = match key.kind.as_str() {
+        "boolean" => match value.trim() {
+            "true" | "1" => AttributeValue::Bool(true),
+            "false" | "0" => AttributeValue::Bool(false),
+            _ => {
+                return Err(format!(
+                    "'{value}' of the key '{}' is not a boolean",
+                    key.name
+                ));
+            }
+        },
+        "int" | "long" | "float" | "double" => AttributeValue::Number(parse_number(key, value)?),
+        _ => AttributeValue::String(value.to_string()),
+    };
+    Ok(Attribute {
+        key: key.name.clone(),
+        value,
+    })
+}
+
+fn write_key(graphml: &mut String, id: &str, domain: &str, name: &str, kind: &str) {
+    let _ = writeln!(
+        graphml,
+        "  <key id=\"{id}\" for=\"{domain}\" attr.name=\"{}\" attr.type=\"{kind}\"/>",
+        escape_xml(name),
+    );
+}
+
+fn write_data(graphml: &mut String, key: &str, value: &str) {
+    let _ = writeln!(
+        graphml,
+        "      <data key=\"{key}\">{}</data>",
+        escape_xml(value)
+    );
+}
+
+fn write_attributes(
+    graphml: &mut String,
+    prefix: &str,
+    keys: &AttributeKeys,
+    attributes: &[Attribute],
+) {
+    for attribute in attributes {
+        if let Some(index) = keys.index(attribute) {
+            write_data(
+                graphml,
+                &format!("{prefix}{index}"),
+                &attribute.value.to_string(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(key: &str, value: AttributeValue) -> Attribute {
+        Attribute {
+            key: key.to_string(),
+            value,
+        }
+    }
+
+    fn document() -> GraphDocument {
+        GraphDocument {
+            vertices: vec![
+                VertexRecord {
+                    id: None,
+                    label: "a & <b>".to_string(),
+                    x: 1.5,
+                    y: -2.0,
+                    attributes: vec![
+                        attribute("size", AttributeValue::Number(3.0)),
+                        attribute("done", AttributeValue::Bool(true)),
+                        // Named like the built-in keys.
+                        attribute("label", AttributeValue::String("other".to_string())),
+                        attribute("x", AttributeValue::Number(99.0)),
+                    ],
+                    style: None,
+                },
+                VertexRecord {
+                    id: None,
+                    label: String::new(),
+                    x: 0.0,
+                    y: 4.0,
+                    attributes: vec![attribute(
+                        "color",
+                        AttributeValue::Color([1.0, 0.0, 0.0, 1.0]),
+                    )],
+                    style: None,
+                },
+            ],
+            edges: vec![
+                EdgeRecord {
+                    from: 0,
+                    to: 1,
+                    weight: Some(2.5),
+                    attributes: vec![attribute(
+                        "weight",
+                        AttributeValue::String("heavy".to_string()),
+                    )],
+                    style: None,
+                },
+                EdgeRecord {
+                    from: 1,
+                    to: 1,
+                    weight: None,
+                    attributes: Vec::new(),
+                    style: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_built_in_keys_apart_from_attributes() {
+        let read = graphml_to_document(&document_to_graphml(&document())).unwrap();
+        let original = document();
+
+        assert_eq!(read.vertices.len(), 2);
+        for (read, original) in read.vertices.iter().zip(&original.vertices) {
+            assert_eq!(read.label, original.label);
+            assert_eq!((read.x, read.y), (original.x, original.y));
+        }
+        assert_eq!(read.vertices[0].attributes, original.vertices[0].attributes);
+        // Colours come back as strings.
+        assert_eq!(
+            read.vertices[1].attributes,
+            [attribute(
+                "color",
+                AttributeValue::String("#ff0000ff".to_string())
+            )]
+        );
+
+        let edges: Vec<_> = read
+            .edges
+            .iter()
+            .map(|edge| (edge.from, edge.to, edge.weight, edge.attributes.clone()))
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                (0, 1, Some(2.5), original.edges[0].attributes.clone()),
+                (1, 1, None, Vec::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_keys_of_other_tools() {
+        let text = r#"<?xml version="1.0"?>
+            <graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+              <key id="d0" for="node" attr.name="label" attr.type="string"/>
+              <key id="d1" for="node" attr.name="label" attr.type="string"/>
+              <key id="d2" for="node" attr.name="kind" attr.type="string">
+                <default>plain</default>
+              </key>
+              <key id="d3" for="edge" attr.name="weight" attr.type="int"/>
+              <graph edgedefault="directed">
+                <node id="first"><data key="d0">A</data><data key="d1">B</data></node>
+                <node id="second"><data key="d2">fancy</data><data key="note">hi</data></node>
+                <edge source="first" target="second"><data key="d3">4</data></edge>
+              </graph>
+            </graphml>"#;
+        let document = graphml_to_document(text).unwrap();
+
+        // The first key named label is the label, the second an attribute.
+        assert_eq!(document.vertices[0].label, "A");
+        assert_eq!(
+            document.vertices[0].attributes,
+            [
+                attribute("label", AttributeValue::String("B".to_string())),
+                attribute("kind", AttributeValue::String("plain".to_string())),
+            ]
+        );
+        // Without a label the id is used, undeclared keys become attributes.
+        assert_eq!(document.vertices[1].label, "second");
+        assert_eq!(
+            document.vertices[1].attributes,
+            [
+                attribute("kind", AttributeValue::String("fancy".to_string())),
+                attribute("note", AttributeValue::String("hi".to_string())),
+            ]
+        );
+        assert_eq!(document.edges[0].weight, Some(4.0));
+        // Without positions the vertices are laid out on a circle.
+        let positions: Vec<Vec2> = document
+            .vertices
+            .iter()
+            .map(|vertex| Vec2::new(vertex.x, vertex.y))
+            .collect();
+        assert_eq!(positions, circle_layout(2));
+    }
+
+    #[test]
+    fn malformed_files_are_rejected() {
+        let graph = |body: &str| {
+            format!(
+                r#"<graphml>
+                  <key id="x" for="node" attr.name="x" attr.type="double"/>
+                  <key id="b" for="node" attr.name="flag" attr.type="boolean"/>
+                  <graph>{body}</graph>
+                </graphml>"#
+            )
+        };
+        for (text, error) in [
+            ("<graphml>".to_string(), "Malformed XML"),
+            ("<svg/>".to_string(), "Not a GraphML file"),
+            ("<graphml/>".to_string(), "has no <graph>"),
+            (graph(r#"<node/>"#), "A <node> has no id"),
+            (graph(r#"<node id="a"/><node id="a"/>"#), "used twice"),
+            (
+                graph(r#"<node id="a"/><edge source="a" target="b"/>"#),
+                "unknown node 'b'",
+            ),
+            (
+                graph(r#"<node id="a"/><edge target="a"/>"#),
+                "has no source",
+            ),
+            (
+                graph(r#"<node id="a"><data key="x">far</data></node>"#),
+                "'far' of the key 'x' is not a number",
+            ),
+            (
+                graph(r#"<node id="a"><data key="b">maybe</data></node>"#),
+                "is not a boolean",
+            ),
+        ] {
+            let result = graphml_to_document(&text);
+            assert!(
+                result
+                    .as_ref()
+                    .is_err_and(|message| message.contains(error)),
+                "{text}: {result:?}"
+            );
+        }
+    }
+}
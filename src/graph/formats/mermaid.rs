@@ -0,0 +1,733 @@
+use std::{collections::HashMap, fmt::Write, iter::Peekable, str::CharIndices};
+
+use bevy::prelude::*;
+
+use crate::graph::{
+    components::{Attribute, AttributeValue, LineStyle, VertexShape},
+    constants::{MERMAID_LAYER_SPACING, MERMAID_VERTEX_SPACING},
+    document::{EdgeRecord, GraphDocument, VertexRecord},
+    formats::MermaidDirection,
+};
+
+/// A flowchart with a node per vertex in the shape of its style and an
+/// arrow per edge, weights as the edge text. Node ids come from the vertex
+/// ids, so they stay the same between exports.
+pub fn document_to_mermaid(document: &GraphDocument, direction: MermaidDirection) -> String {
+    let ids = node_ids(document);
+
+    let mut mermaid = format!("flowchart {}\n", direction.code());
+    for (vertex, id) in document.vertices.iter().zip(&ids) {
+        let shape = vertex
+            .style
+            .as_ref()
+            .map_or(VertexShape::default(), |style| style.shape);
+        let (open, close) = match shape {
+            VertexShape::Circle => ("((", "))"),
+            VertexShape::Square => ("[", "]"),
+            VertexShape::Diamond => ("{", "}"),
+            VertexShape::RoundedBox => ("(", ")"),
+        };
+        let _ = writeln!(
+            mermaid,
+            "    {id}{open}\"{}\"{close}",
+            escape_mermaid(&vertex.label)
+        );
+    }
+    for edge in &document.edges {
+        let dashed = edge
+            .style
+            .as_ref()
+            .is_some_and(|style| style.line != LineStyle::Solid);
+        let arrow = if dashed { "-.->" } else { "-->" };
+        let text = edge.weight.map_or(String::new(), |weight| {
+            format!("|\"{}\"|", escape_mermaid(&weight.to_string()))
+        });
+        let _ = writeln!(
+            mermaid,
+            "    {} {arrow}{text} {}",
+            ids[edge.from], ids[edge.to]
+        );
+    }
+    mermaid
+}
+
+/// Reads the basic flowchart subset: nodes with any of the usual shapes,
+/// chains of links with `&`, and link texts, which become the weight if they
+/// are a number and a `text` attribute otherwise. Styling, classes and
+/// subgraph boundaries are skipped. Markdown is read from its first mermaid
+/// code block. The vertices are laid out in layers along the direction.
+/// Node ids as exported become the vertex ids again.
+pub fn mermaid_to_document(text: &str) -> Result<GraphDocument, String> {
+    let text = match text.split_once("```mermaid") {
+        Some((_, block)) => block.split("```").next().unwrap_or_default(),
+        None => text,
+    };
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with("%%"));
+
+    let (_, header) = lines.next().ok_or("The flowchart is empty")?;
+    let mut words = header.split_whitespace();
+    if !matches!(words.next(), Some("flowchart" | "graph")) {
+        return Err(format!(
+            "Only flowcharts can be read, this starts with '{header}'"
+        ));
+    }
+    let direction = match words.next().map(|code| code.trim_end_matches(';')) {
+        None => MermaidDirection::default(),
+        Some(code) => {
+            MermaidDirection::parse(code).ok_or(format!("'{code}' is not a flowchart direction"))?
+        }
+    };
+
+    let mut parser = FlowchartParser::default();
+    for (number, line) in lines {
+        for statement in statements(line).into_iter().map(str::trim) {
+            let keyword = statement.split_whitespace().next().unwrap_or_default();
+            if statement.is_empty() || SKIPPED_KEYWORDS.contains(&keyword) {
+                continue;
+            }
+            parser
+                .statement(statement)
+                .map_err(|error| format!("Line {number}: {error}"))?;
+        }
+    }
+
+    let mut document = parser.document;
+    let layout = layered_layout(&document.edges, document.vertices.len(), direction);
+    for (vertex, position) in document.vertices.iter_mut().zip(layout) {
+        (vertex.x, vertex.y) = (position.x, position.y);
+    }
+    Ok(document)
+}
+
+/// Statements that only style or group nodes.
+const SKIPPED_KEYWORDS: [&str; 8] = [
+    "subgraph",
+    "end",
+    "direction",
+    "style",
+    "classDef",
+    "class",
+    "linkStyle",
+    "click",
+];
+
+/// Node shapes, the longer openings first so they aren't mistaken for shorter ones.
+const SHAPES: [(&str, &str); 12] = [
+    ("(((", ")))"),
+    ("((", "))"),
+    ("([", "])"),
+    ("[[", "]]"),
+    ("[(", ")]"),
+    ("{{", "}}"),
+    ("[/", "/]"),
+    ("[\\", "\\]"),
+    ("[", "]"),
+    ("(", ")"),
+    ("{", "}"),
+    (">", "]"),
+];
+
+#[derive(Default)]
+struct FlowchartParser {
+    document: GraphDocument,
+    /// The vertex of every node id.
+    ids: HashMap<String, usize>,
+}
+
+impl FlowchartParser {
+    /// A node group, followed by any number of links to further node groups.
+    fn statement(&mut self, statement: &str) -> Result<(), String> {
+        let mut characters = statement.char_indices().peekable();
+        let mut sources = self.node_group(statement, &mut characters)?;
+        loop {
+            skip_whitespace(&mut characters);
+            if characters.peek().is_none() {
+                return Ok(());
+            }
+            let text = link(statement, &mut characters)?;
+            let targets = self.node_group(statement, &mut characters)?;
+            for &from in &sources {
+                for &to in &targets {
+                    self.document.edges.push(edge(from, to, text.as_deref()));
+                }
+            }
+            sources = targets;
+        }
+    }
+
+    /// Nodes joined by `&`.
+    fn node_group(
+        &mut self,
+        statement: &str,
+        characters: &mut Peekable<CharIndices>,
+    ) -> Result<Vec<usize>, String> {
+        let mut nodes = vec![self.node(statement, characters)?];
+        loop {
+            skip_whitespace(characters);
+            if characters
+                .next_if(|(_, character)| *character == '&')
+                .is_none()
+            {
+                return Ok(nodes);
+            }
+            nodes.push(self.node(statement, characters)?);
+        }
+    }
+
+    /// A node id with an optional shape around its label.
+    fn node(
+        &mut self,
+        statement: &str,
+        characters: &mut Peekable<CharIndices>,
+    ) -> Result<usize, String> {
+        skip_whitespace(characters);
+        let start = characters
+            .peek()
+            .map_or(statement.len(), |(index, _)| *index);
+        while characters
+            .next_if(|(_, character)| character.is_alphanumeric() || *character == '_')
+            .is_some()
+        {}
+        let end = characters
+            .peek()
+            .map_or(statement.len(), |(index, _)| *index);
+        let id = &statement[start..end];
+        if id.is_empty() {
+            return Err(format!("Expected a node at '{}'", &statement[start..]));
+        }
+
+        let rest = &statement[end..];
+        let label = match SHAPES.iter().find(|(open, _)| rest.starts_with(open)) {
+            Some((open, close)) => {
+                let inner = &rest[open.len()..];
+                let (label, length) = match inner.strip_prefix('"') {
+                    Some(quoted) => {
+                        let quote = quoted
+                            .find('"')
+                            .ok_or(format!("The label of '{id}' is never closed"))?;
+                        if !quoted[quote + 1..].starts_with(close) {
+                            return Err(format!("Expected '{close}' after the label of '{id}'"));
+                        }
+                        (&quoted[..quote], quote + 2)
+                    }
+                    None => {
+                        let length = inner
+                            .find(close)
+                            .ok_or(format!("The label of '{id}' is never closed"))?;
+                        (inner[..length].trim(), length)
+                    }
+                };
+                let consumed = end + open.len() + length + close.len();
+                while characters.next_if(|(index, _)| *index < consumed).is_some() {}
+                Some(unescape_mermaid(label))
+            }
+            None => None,
+        };
+        // Classes like `A:::important` only style the node.
+        let after_shape = characters
+            .peek()
+            .map_or(statement.len(), |(index, _)| *index);
+        if statement[after_shape..].starts_with(":::") {
+            while characters
+                .next_if(|(_, character)| {
+                    *character == ':' || *character == '_' || character.is_alphanumeric()
+                })
+                .is_some()
+            {}
+        }
+
+        let index = *self.ids.entry(id.to_string()).or_insert_with(|| {
+            self.document.vertices.push(VertexRecord {
+                id: vertex_id(id),
+                label: id.to_string(),
+                ..default()
+            });
+            self.document.vertices.len() - 1
+        });
+        if let Some(label) = label {
+            self.document.vertices[index].label = label;
+        }
+        Ok(index)
+    }
+}
+
+/// An arrow like `-->`, `---`, `-.->`, `==>` or `--o`, with an optional
+/// text either as `|text|` behind it or inside of it as in `-- text -->`.
+fn link(statement: &str, characters: &mut Peekable<CharIndices>) -> Result<Option<String>, String> {
+    let start = characters
+        .peek()
+        .map_or(statement.len(), |(index, _)| *index);
+    let arrow = arrow(statement, characters);
+    if arrow.is_empty() {
+        return Err(format!("Expected a link at '{}'", &statement[start..]));
+    }
+
+    let mut text = None;
+    if matches!(arrow, "--" | "==" | "-.") {
+        let rest = &statement[start + arrow.len()..];
+        let closing = ["-->", "---", "==>", "===", ".->", ".-"]
+            .iter()
+            .filter_map(|closing| rest.find(closing))
+            .min()
+            .ok_or(format!("The link text after '{arrow}' is never closed"))?;
+        text = Some(rest[..closing].trim().to_string());
+        let closing_start = start + arrow.len() + closing;
+        while characters
+            .next_if(|(index, _)| *index < closing_start)
+            .is_some()
+        {}
+        arrow_tail(statement, characters);
+    }
+
+    skip_whitespace(characters);
+    if characters
+        .next_if(|(_, character)| *character == '|')
+        .is_some()
+    {
+        let start = characters
+            .peek()
+            .map_or(statement.len(), |(index, _)| *index);
+        let length = statement[start..]
+            .find('|')
+            .ok_or("A link text is never closed")?;
+        text = Some(statement[start..start + length].trim().to_string());
+        while characters
+            .next_if(|(index, _)| *index <= start + length)
+            .is_some()
+        {}
+    }
+
+    Ok(text.map(|text| unescape_mermaid(text.trim_matches('"'))))
+}
+
+/// The dashes, dots and arrowheads of a link.
+fn arrow<'a>(statement: &'a str, characters: &mut Peekable<CharIndices>) -> &'a str {
+    let start = characters
+        .peek()
+        .map_or(statement.len(), |(index, _)| *index);
+    while characters
+        .next_if(|(_, character)| matches!(character, '<' | '-' | '=' | '.' | '>'))
+        .is_some()
+    {}
+    arrow_tail(statement, characters);
+    let end = characters
+        .peek()
+        .map_or(statement.len(), |(index, _)| *index);
+    &statement[start..end]
+}
+
+/// The rest of a closing link, including an `o` or `x` head
+/// if it isn't the start of the next node id.
+fn arrow_tail(statement: &str, characters: &mut Peekable<CharIndices>) {
+    while characters
+        .next_if(|(_, character)| matches!(character, '-' | '=' | '.' | '>'))
+        .is_some()
+    {}
+    if let Some(&(index, 'o' | 'x')) = characters.peek()
+        && statement[index + 1..]
+            .chars()
+            .next()
+            .is_none_or(|next| next.is_whitespace())
+    {
+        characters.next();
+    }
+}
+
+/// Splits a line at the semicolons ending statements, not at those
+/// in quotes or ending entity codes like `#quot;`.
+fn statements(line: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut entity = false;
+    for (index, character) in line.char_indices() {
+        match character {
+            '"' => quoted = !quoted,
+            '#' => entity = true,
+            ';' if entity => entity = false,
+            ';' if !quoted => {
+                statements.push(&line[start..index]);
+                start = index + 1;
+            }
+            _ if !character.is_alphanumeric() => entity = false,
+            _ => {}
+        }
+    }
+    statements.push(&line[start..]);
+    statements
+}
+
+fn skip_whitespace(characters: &mut Peekable<CharIndices>) {
+    while characters
+        .next_if(|(_, character)| character.is_whitespace())
+        .is_some()
+    {}
+}
+
+fn edge(from: usize, to: usize, text: Option<&str>) -> EdgeRecord {
+    let mut edge = EdgeRecord {
+        from,
+        to,
+        weight: None,
+        attributes: Vec::new(),
+        style: None,
+    };
+    if let Some(text) = text.filter(|text| !text.is_empty()) {
+        match text.parse() {
+            Ok(weight) => edge.weight = Some(weight),
+            Err(_) => edge.attributes.push(Attribute {
+                key: "text".to_string(),
+                value: AttributeValue::String(text.to_string()),
+            }),
+        }
+    }
+    edge
+}
+
+/// Layers following the edges, each vertex one layer after the furthest of
+/// its predecessors. Edges closing a cycle are left out of that.
+fn layered_layout(edges: &[EdgeRecord], n: usize, direction: MermaidDirection) -> Vec<Vec2> {
+    let mut successors = vec![Vec::new(); n];
+    for edge in edges {
+        successors[edge.from].push(edge.to);
+    }
+
+    // Depth first search for a topological order that ignores back edges.
+    let mut order = Vec::with_capacity(n);
+    let mut visited = vec![false; n];
+    for root in 0..n {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        let mut stack = vec![(root, 0)];
+        while let Some(&(vertex, next)) = stack.last() {
+            match successors[vertex].get(next) {
+                Some(&successor) => {
+                    let top = stack.len() - 1;
+                    stack[top].1 += 1;
+                    if !visited[successor] {
+                        visited[successor] = true;
+                        stack.push((successor, 0));
+                    }
+                }
+                None => {
+                    order.push(vertex);
+                    stack.pop();
+                }
+            }
+        }
+    }
+    order.reverse();
+
+    let mut rank = vec![0; n];
+    for (position, &vertex) in order.iter().enumerate() {
+        rank[vertex] = position;
+    }
+    let mut layers = vec![0; n];
+    for &vertex in &order {
+        for &successor in &successors[vertex] {
+            if rank[successor] > rank[vertex] {
+                layers[successor] = layers[successor].max(layers[vertex] + 1);
+            }
+        }
+    }
+
+    let mut layer_sizes: HashMap<usize, usize> = HashMap::new();
+    for &layer in &layers {
+        *layer_sizes.entry(layer).or_default() += 1;
+    }
+    let mut placed: HashMap<usize, usize> = HashMap::new();
+    let axis = direction.axis();
+    layers
+        .iter()
+        .map(|&layer| {
+            let index = placed.entry(layer).or_default();
+            let offset = *index as f32 - (layer_sizes[&layer] as f32 - 1.0) / 2.0;
+            *index += 1;
+            axis * layer as f32 * MERMAID_LAYER_SPACING
+                + axis.perp() * offset * MERMAID_VERTEX_SPACING
+        })
+        .collect()
+}
+
+/// `v` and the vertex id, so a node keeps its id between exports whatever
+/// happens to its label. Vertices without an id, of documents that didn't
+/// come from the canvas, are numbered after the highest id.
+fn node_ids(document: &GraphDocument) -> Vec<String> {
+    let mut next = document
+        .vertices
+        .iter()
+        .filter_map(|vertex| vertex.id)
+        .max()
+        .map_or(0, |id| id.saturating_add(1));
+    document
+        .vertices
+        .iter()
+        .map(|vertex| {
+            let id = vertex.id.unwrap_or_else(|| {
+                next += 1;
+                next - 1
+            });
+            format!("v{id}")
+        })
+        .collect()
+}
+
+/// The vertex id of a node id written by [`node_ids`].
+fn vertex_id(node_id: &str) -> Option<u64> {
+    let id = node_id.strip_prefix('v')?.parse().ok()?;
+    (format!("v{id}") == node_id).then_some(id)
+}
+
+/// Quotes can't be escaped with a backslash in Mermaid, it has entity codes instead.
+fn escape_mermaid(text: &str) -> String {
+    text.replace('#', "#35;")
+        .replace('"', "#quot;")
+        .replace('\n', " ")
+}
+
+fn unescape_mermaid(text: &str) -> String {
+    text.replace("#quot;", "\"").replace("#35;", "#")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::components::{EdgeStyle, VertexStyle};
+
+    fn vertex(id: Option<u64>, label: &str) -> VertexRecord {
+        VertexRecord {
+            id,
+            label: label.to_string(),
+            ..default()
+        }
+    }
+
+    fn labels(document: &GraphDocument) -> Vec<&str> {
+        document
+            .vertices
+            .iter()
+            .map(|vertex| vertex.label.as_str())
+            .collect()
+    }
+
+    fn edges(document: &GraphDocument) -> Vec<(usize, usize, Option<f32>)> {
+        document
+            .edges
+            .iter()
+            .map(|edge| (edge.from, edge.to, edge.weight))
+            .collect()
+    }
+
+    fn text(edge: &EdgeRecord) -> Option<&str> {
+        edge.attributes
+            .iter()
+            .find_map(|attribute| match &attribute.value {
+                AttributeValue::String(text) if attribute.key == "text" => Some(text.as_str()),
+                _ => None,
+            })
+    }
+
+    fn parse(statement: &str) -> GraphDocument {
+        let mut parser = FlowchartParser::default();
+        parser.statement(statement).unwrap();
+        parser.document
+    }
+
+    #[test]
+    fn node_ids_come_from_vertex_ids() {
+        let mut document = GraphDocument {
+            vertices: vec![
+                vertex(Some(7), "end"),
+                vertex(Some(2), "end"),
+                vertex(None, "o"),
+            ],
+            edges: vec![edge(0, 1, None)],
+        };
+        let exported = document_to_mermaid(&document, MermaidDirection::LeftRight);
+        assert_eq!(node_ids(&document), ["v7", "v2", "v8"]);
+        assert!(exported.starts_with("flowchart LR\n"));
+        assert!(exported.contains("    v7((\"end\"))\n"));
+        assert!(exported.contains("    v7 --> v2\n"));
+
+        document.vertices[0].label = "renamed".to_string();
+        assert_eq!(node_ids(&document), ["v7", "v2", "v8"]);
+        assert_eq!(vertex_id("v7"), Some(7));
+        assert_eq!(vertex_id("v07"), None);
+        assert_eq!(vertex_id("A"), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut square = vertex(Some(3), "say \"hi\" #1");
+        square.style = Some(VertexStyle {
+            shape: VertexShape::Square,
+            ..default()
+        });
+        let mut dashed = edge(1, 0, None);
+        dashed.style = Some(EdgeStyle {
+            line: LineStyle::Dashed,
+            ..default()
+        });
+        let document = GraphDocument {
+            vertices: vec![vertex(Some(0), "a & b"), square, vertex(Some(10), "")],
+            edges: vec![edge(0, 1, Some("2.5")), dashed, edge(2, 2, Some("-1"))],
+        };
+
+        let text = document_to_mermaid(&document, MermaidDirection::TopDown);
+        let read = mermaid_to_document(&text).unwrap();
+        assert_eq!(labels(&read), labels(&document));
+        assert_eq!(edges(&read), edges(&document));
+        let ids: Vec<_> = read.vertices.iter().map(|vertex| vertex.id).collect();
+        assert_eq!(ids, [Some(0), Some(3), Some(10)]);
+        assert!(text.contains("    v3[\"say #quot;hi#quot; #35;1\"]\n"));
+        assert!(text.contains("    v3 -.-> v0\n"));
+        // Shapes and dashes are only written, so they are all that changes.
+        let plain = text.replace(
+            "v3[\"say #quot;hi#quot; #35;1\"]",
+            "v3((\"say #quot;hi#quot; #35;1\"))",
+        );
+        let plain = plain.replace("-.->", "-->");
+        assert_eq!(document_to_mermaid(&read, MermaidDirection::TopDown), plain);
+    }
+
+    #[test]
+    fn reads_a_readme_example() {
+        let markdown = "# Shopping\n\n```mermaid\ngraph TD\n    \
+            A[Christmas] -->|Get money| B(Go shopping)\n    \
+            B --> C{Let me think}\n    \
+            %% A comment\n    \
+            C -->|One| D[Laptop]\n    \
+            C -->|Two| E[iPhone]\n    \
+            C -->|Three| F[fa:fa-car Car]\n```\n\nMore text.\n```\nnot mermaid\n```\n";
+        let document = mermaid_to_document(markdown).unwrap();
+        assert_eq!(
+            labels(&document),
+            [
+                "Christmas",
+                "Go shopping",
+                "Let me think",
+                "Laptop",
+                "iPhone",
+                "fa:fa-car Car"
+            ]
+        );
+        assert_eq!(
+            edges(&document),
+            [
+                (0, 1, None),
+                (1, 2, None),
+                (2, 3, None),
+                (2, 4, None),
+                (2, 5, None)
+            ]
+        );
+        assert_eq!(text(&document.edges[0]), Some("Get money"));
+        assert_eq!(text(&document.edges[1]), None);
+        assert!(document.vertices.iter().all(|vertex| vertex.id.is_none()));
+        // Top down, so every layer is below the one before.
+        assert!(document.vertices[1].y < document.vertices[0].y);
+        assert_eq!(document.vertices[3].y, document.vertices[5].y);
+    }
+
+    #[test]
+    fn ampersands_join_nodes_and_links_chain() {
+        let document = parse("a & b --> c & d --- e");
+        assert_eq!(labels(&document), ["a", "b", "c", "d", "e"]);
+        assert_eq!(
+            edges(&document),
+            [
+                (0, 2, None),
+                (0, 3, None),
+                (1, 2, None),
+                (1, 3, None),
+                (2, 4, None),
+                (3, 4, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn link_texts() {
+        let document = parse("A -- 5 --> B == heavy ==> C -. maybe .-> D -->|\"7\"| E --- |x| F");
+        assert_eq!(
+            edges(&document),
+            [
+                (0, 1, Some(5.0)),
+                (1, 2, None),
+                (2, 3, None),
+                (3, 4, Some(7.0)),
+                (4, 5, None),
+            ]
+        );
+        let texts: Vec<_> = document.edges.iter().map(text).collect();
+        assert_eq!(texts, [None, Some("heavy"), Some("maybe"), None, Some("x")]);
+    }
+
+    #[test]
+    fn circle_and_cross_heads() {
+        let document = parse("A --o B --x C <--> oscar --o xenia");
+        assert_eq!(labels(&document), ["A", "B", "C", "oscar", "xenia"]);
+        assert_eq!(document.edges.len(), 4);
+
+        let mut characters = "--o B".char_indices().peekable();
+        assert_eq!(arrow("--o B", &mut characters), "--o");
+        let mut characters = "-->oscar".char_indices().peekable();
+        assert_eq!(arrow("-->oscar", &mut characters), "-->");
+        let mut characters = "--x".char_indices().peekable();
+        assert_eq!(arrow("--x", &mut characters), "--x");
+    }
+
+    #[test]
+    fn shapes_classes_and_entity_codes() {
+        let document = parse(
+            "A((circle)) --> B{{hexagon}}:::important --> C[\"say #quot;hi#quot; #35;1\"] --> D>flag]",
+        );
+        assert_eq!(
+            labels(&document),
+            ["circle", "hexagon", "say \"hi\" #1", "flag"]
+        );
+        assert_eq!(document.edges.len(), 3);
+    }
+
+    #[test]
+    fn statements_split_at_semicolons_outside_of_quotes_and_entities() {
+        assert_eq!(
+            statements("A[\"a;b\"] --> B; C[\"#quot;\"] --> D;"),
+            ["A[\"a;b\"] --> B", " C[\"#quot;\"] --> D", ""]
+        );
+        let document = mermaid_to_document(
+            "flowchart LR;\nsubgraph one\nA --> B; B --> C\nend\nstyle A fill:#f9f\nclassDef x fill:#fff;",
+        )
+        .unwrap();
+        assert_eq!(labels(&document), ["A", "B", "C"]);
+        assert_eq!(edges(&document), [(0, 1, None), (1, 2, None)]);
+    }
+
+    #[test]
+    fn malformed_flowcharts_are_rejected() {
+        for text in [
+            "",
+            "%% only a comment",
+            "sequenceDiagram\nA->>B: hi",
+            "flowchart XY",
+            "flowchart\nA[never closed --> B",
+            "flowchart\nA[\"never closed] --> B",
+            "flowchart\nA[\"label\" --> B",
+            "flowchart\nA -- text B",
+            "flowchart\nA -->| text B",
+            "flowchart\nA -->",
+            "flowchart\nA B",
+            "flowchart\n--> B",
+        ] {
+            assert!(mermaid_to_document(text).is_err(), "{text:?} was read");
+        }
+        let error = mermaid_to_document("graph\nA --> B\nA -->").unwrap_err();
+        assert!(error.starts_with("Line 3: "), "{error}");
+    }
+}
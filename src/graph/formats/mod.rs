@@ -0,0 +1,311 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::graph::{
+    components::{Attribute, AttributeValue},
+    constants::{
+        ADJACENCY_MATRIX_EXTENSION, EDGE_LIST_EXTENSION, TIKZ_EDGE_STYLE, TIKZ_VERTEX_STYLE,
+        TIKZ_WEIGHT_STYLE,
+    },
+    document::GraphDocument,
+    settings::Settings,
+};
+
+mod csv;
+mod gexf;
+mod graphml;
+mod mermaid;
+mod svg;
+mod tikz;
+
+/// Options of the exporters, edited in the File menu.
+#[derive(Resource, Debug, Clone)]
+pub struct ExportOptions {
+    /// Whether the SVG gets the background colour of the theme.
+    pub svg_background: bool,
+    /// Style names used by the TikZ nodes and edges, so the picture can
+    /// take on the styles of the document it is put into.
+    pub tikz_vertex_style: String,
+    pub tikz_edge_style: String,
+    pub tikz_weight_style: String,
+    /// Whether the picture defines the styles itself.
+    pub tikz_define_styles: bool,
+    pub mermaid_direction: MermaidDirection,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            svg_background: true,
+            tikz_vertex_style: TIKZ_VERTEX_STYLE.to_string(),
+            tikz_edge_style: TIKZ_EDGE_STYLE.to_string(),
+            tikz_weight_style: TIKZ_WEIGHT_STYLE.to_string(),
+            tikz_define_styles: true,
+            mermaid_direction: MermaidDirection::default(),
+        }
+    }
+}
+
+impl ExportOptions {
+    /// The TikZ style names of vertices, edges and weights,
+    /// empty ones fall back to the defaults.
+    pub fn tikz_styles(&self) -> [&str; 3] {
+        [
+            (&self.tikz_vertex_style, TIKZ_VERTEX_STYLE),
+            (&self.tikz_edge_style, TIKZ_EDGE_STYLE),
+            (&self.tikz_weight_style, TIKZ_WEIGHT_STYLE),
+        ]
+        .map(|(name, default)| match name.trim() {
+            "" => default,
+            name => name,
+        })
+    }
+
+    /// Why one of the TikZ style names can't be used as a key, if it can't.
+    pub fn tikz_styles_error(&self) -> Option<String> {
+        self.tikz_styles().into_iter().find_map(|name| {
+            name.contains(|character| r",=[]{}/\%#".contains(character))
+                .then(|| format!(r"TikZ style `{name}` can't contain , = [ ] {{ }} / \ % or #"))
+        })
+    }
+}
+
+/// Which way a flowchart flows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MermaidDirection {
+    #[default]
+    TopDown,
+    LeftRight,
+    BottomUp,
+    RightLeft,
+}
+
+impl MermaidDirection {
+    pub const ALL: [MermaidDirection; 4] = [
+        MermaidDirection::TopDown,
+        MermaidDirection::LeftRight,
+        MermaidDirection::BottomUp,
+        MermaidDirection::RightLeft,
+    ];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            MermaidDirection::TopDown => "TD",
+            MermaidDirection::LeftRight => "LR",
+            MermaidDirection::BottomUp => "BT",
+            MermaidDirection::RightLeft => "RL",
+        }
+    }
+
+    fn parse(code: &str) -> Option<Self> {
+        match code {
+            "TD" | "TB" => Some(MermaidDirection::TopDown),
+            "LR" => Some(MermaidDirection::LeftRight),
+            "BT" => Some(MermaidDirection::BottomUp),
+            "RL" => Some(MermaidDirection::RightLeft),
+            _ => None,
+        }
+    }
+
+    /// The direction the layers of a layout follow on the canvas.
+    fn axis(&self) -> Vec2 {
+        match self {
+            MermaidDirection::TopDown => Vec2::NEG_Y,
+            MermaidDirection::LeftRight => Vec2::X,
+            MermaidDirection::BottomUp => Vec2::Y,
+            MermaidDirection::RightLeft => Vec2::NEG_X,
+        }
+    }
+}
+
+/// Formats the graph can be exported to, each written by its own module.
+/// They all work on a `GraphDocument`, so they don't need a window or a GPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExportFormat {
+    Svg,
+    Tikz,
+    GraphMl,
+    Gexf,
+    EdgeList,
+    AdjacencyMatrix,
+    Mermaid,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 7] = [
+        ExportFormat::Svg,
+        ExportFormat::Tikz,
+        ExportFormat::GraphMl,
+        ExportFormat::Gexf,
+        ExportFormat::EdgeList,
+        ExportFormat::AdjacencyMatrix,
+        ExportFormat::Mermaid,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Svg => "SVG",
+            ExportFormat::Tikz => "TikZ",
+            ExportFormat::GraphMl => "GraphML",
+            ExportFormat::Gexf => "GEXF",
+            ExportFormat::EdgeList => "Edge list CSV",
+            ExportFormat::AdjacencyMatrix => "Adjacency matrix CSV",
+            ExportFormat::Mermaid => "Mermaid",
+        }
+    }
+
+    /// Replaces the extension of the document path when exporting.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Svg => "svg",
+            ExportFormat::Tikz => "tex",
+            ExportFormat::GraphMl => "graphml",
+            ExportFormat::Gexf => "gexf",
+            ExportFormat::EdgeList => EDGE_LIST_EXTENSION,
+            ExportFormat::AdjacencyMatrix => ADJACENCY_MATRIX_EXTENSION,
+            ExportFormat::Mermaid => "mmd",
+        }
+    }
+
+    /// Fails for graphs the format can't tell apart, e.g. CSV with duplicate labels.
+    pub fn write(
+        &self,
+        document: &GraphDocument,
+        settings: &Settings,
+        options: &ExportOptions,
+    ) -> Result<String, String> {
+        match self {
+            ExportFormat::Svg => Ok(svg::document_to_svg(
+                document,
+                settings,
+                options.svg_background,
+            )),
+            ExportFormat::Tikz => match options.tikz_styles_error() {
+                Some(error) => Err(error),
+                None => Ok(tikz::document_to_tikz(document, options)),
+            },
+            ExportFormat::GraphMl => Ok(graphml::document_to_graphml(document)),
+            ExportFormat::Gexf => Ok(gexf::document_to_gexf(document)),
+            ExportFormat::EdgeList => csv::document_to_edge_list(document),
+            ExportFormat::AdjacencyMatrix => csv::document_to_adjacency_matrix(document),
+            ExportFormat::Mermaid => Ok(mermaid::document_to_mermaid(
+                document,
+                options.mermaid_direction,
+            )),
+        }
+    }
+}
+
+/// Formats a graph can be read from. Importing replaces the graph
+/// like opening a document does, but keeps the document path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ImportFormat {
+    GraphMl,
+    EdgeList,
+    AdjacencyMatrix,
+    Mermaid,
+}
+
+impl ImportFormat {
+    pub const ALL: [ImportFormat; 4] = [
+        ImportFormat::GraphMl,
+        ImportFormat::EdgeList,
+        ImportFormat::AdjacencyMatrix,
+        ImportFormat::Mermaid,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImportFormat::GraphMl => "GraphML",
+            ImportFormat::EdgeList => "Edge list CSV",
+            ImportFormat::AdjacencyMatrix => "Adjacency matrix CSV",
+            ImportFormat::Mermaid => "Mermaid",
+        }
+    }
+
+    /// Replaces the extension of the document path when importing.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImportFormat::GraphMl => "graphml",
+            ImportFormat::EdgeList => EDGE_LIST_EXTENSION,
+            ImportFormat::AdjacencyMatrix => ADJACENCY_MATRIX_EXTENSION,
+            ImportFormat::Mermaid => "mmd",
+        }
+    }
+
+    pub fn read(&self, text: &str) -> Result<GraphDocument, String> {
+        match self {
+            ImportFormat::GraphMl => graphml::graphml_to_document(text),
+            ImportFormat::EdgeList => csv::edge_list_to_document(text),
+            ImportFormat::AdjacencyMatrix => csv::adjacency_matrix_to_document(text),
+            ImportFormat::Mermaid => mermaid::mermaid_to_document(text),
+        }
+    }
+}
+
+/// Reads CSV or tab separated text, e.g. cells copied from a spreadsheet.
+/// Text that does not look like cells is refused, so pasting it falls
+/// back to the internal clipboard.
+pub fn document_from_csv(text: &str) -> Result<GraphDocument, String> {
+    csv::pasted_csv_to_document(text)
+}
+
+/// The attribute names of all vertices or all edges, once for every
+/// XML type they have a value of. Colours are written as strings.
+struct AttributeKeys(Vec<(String, &'static str)>);
+
+impl AttributeKeys {
+    fn new<'a>(attributes: impl Iterator<Item = &'a Vec<Attribute>>) -> Self {
+        let mut keys = Vec::new();
+        for attribute in attributes.flatten() {
+            let key = (attribute.key.clone(), xml_type(&attribute.value));
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        AttributeKeys(keys)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&str, &'static str)> {
+        self.0.iter().map(|(name, kind)| (name.as_str(), *kind))
+    }
+
+    fn index(&self, attribute: &Attribute) -> Option<usize> {
+        let kind = xml_type(&attribute.value);
+        self.0
+            .iter()
+            .position(|(name, other)| *name == attribute.key && *other == kind)
+    }
+}
+
+/// The type GraphML and GEXF declare for a value.
+fn xml_type(value: &AttributeValue) -> &'static str {
+    match value {
+        AttributeValue::Number(_) => "double",
+        AttributeValue::Bool(_) => "boolean",
+        AttributeValue::String(_) | AttributeValue::Color(_) => "string",
+    }
+}
+
+/// Two decimals are plenty, trailing zeros are left out.
+fn number(value: f32) -> String {
+    let text = format!("{value:.2}");
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
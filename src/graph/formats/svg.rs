@@ -0,0 +1,414 @@
+use std::fmt::Write;
+
+use bevy::prelude::*;
+
+use crate::graph::{
+    components::{ArrowStyle, EdgeStyle, VertexShape, VertexStyle},
+    constants::{
+        ARROWHEAD_MIN_SIZE, ARROWHEAD_WIDTH_FACTOR, EDGE_WEIGHT_LABEL_OFFSET, EXPORT_PADDING,
+    },
+    document::GraphDocument,
+    formats::{escape_xml, number},
+    helpers::{bounding_rect, dash_pattern, label_width, rounded_box_length, vertex_label_offset},
+    settings::Settings,
+};
+
+/// Draws the document the way the canvas shows it: edges with their dashes,
+/// arrowheads and weights below the vertices, labels on top. Unstyled
+/// elements get the colours of the theme. Cropped to everything drawn
+/// plus `EXPORT_PADDING`, the background is optional.
+pub fn document_to_svg(document: &GraphDocument, settings: &Settings, background: bool) -> String {
+    let palette = settings.palette();
+    let vertex_styles: Vec<VertexStyle> = document
+        .vertices
+        .iter()
+        .map(|vertex| {
+            vertex
+                .style
+                .clone()
+                .unwrap_or_else(|| VertexStyle::themed(settings))
+        })
+        .collect();
+    let position = |index: usize| {
+        let vertex = &document.vertices[index];
+        Vec2::new(vertex.x, vertex.y)
+    };
+
+    let mut bounds = Vec::new();
+    let mut edges = String::new();
+    let mut labels = String::new();
+    for edge in &document.edges {
+        let style = edge
+            .style
+            .clone()
+            .unwrap_or_else(|| EdgeStyle::themed(settings));
+        let (from, to) = (position(edge.from), position(edge.to));
+        write_edge(&mut edges, from, to, &style);
+
+        let direction = (to - from).normalize_or_zero();
+        if style.arrow != ArrowStyle::None {
+            let size = (style.width * ARROWHEAD_WIDTH_FACTOR).max(ARROWHEAD_MIN_SIZE);
+            let center = to - direction * (vertex_styles[edge.to].radius + size / 2.0);
+            write_arrowhead(&mut edges, center, direction, size, &style);
+        }
+
+        if let Some(weight) = edge.weight {
+            let middle = (from + to) / 2.0 + direction.perp() * EDGE_WEIGHT_LABEL_OFFSET;
+            let text = weight.to_string();
+            let font_size = settings.edge_weight_font_size;
+            bounds.push(text_rect(middle, &text, font_size));
+            write_text(&mut labels, middle, &text, font_size, palette.text);
+        }
+    }
+
+    let mut vertices = String::new();
+    for (vertex, style) in document.vertices.iter().zip(&vertex_styles) {
+        let center = Vec2::new(vertex.x, vertex.y);
+        let font_size = settings.vertex_label_font_size;
+        let outset = style.stroke_width.max(0.0);
+        let half_length = match style.shape {
+            VertexShape::RoundedBox => {
+                rounded_box_length(style.radius, label_width(&vertex.label, font_size)) / 2.0
+            }
+            _ => 0.0,
+        };
+        bounds.push(Rect::from_center_half_size(
+            center,
+            Vec2::new(half_length, 0.0) + Vec2::splat(style.radius + outset),
+        ));
+
+        if outset > 0.0 {
+            write_shape(
+                &mut vertices,
+                style.shape,
+                center,
+                style.radius + outset,
+                half_length,
+                style.stroke_color(),
+            );
+        }
+        write_shape(
+            &mut vertices,
+            style.shape,
+            center,
+            style.radius,
+            half_length,
+            style.fill_color(),
+        );
+
+        if !vertex.label.is_empty() {
+            let label = center + vertex_label_offset(style.shape, style.radius, font_size);
+            bounds.push(text_rect(label, &vertex.label, font_size));
+            write_text(&mut labels, label, &vertex.label, font_size, palette.text);
+        }
+    }
+
+    let area = bounding_rect(bounds.iter().flat_map(|rect| [rect.min, rect.max]))
+        .unwrap_or_default()
+        .inflate(EXPORT_PADDING);
+    // The canvas points y up, SVG points it down.
+    let (x, y, width, height) = (area.min.x, -area.max.y, area.width(), area.height());
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+        number(width),
+        number(height),
+        number(x),
+        number(y),
+        number(width),
+        number(height),
+    );
+    if background {
+        let _ = writeln!(
+            svg,
+            r#"  <rect x="{}" y="{}" width="{}" height="{}"{}/>"#,
+            number(x),
+            number(y),
+            number(width),
+            number(height),
+            paint("fill", palette.background),
+        );
+    }
+    for (group, content) in [("edges", edges), ("vertices", vertices), ("labels", labels)] {
+        let _ = writeln!(svg, r#"  <g id="{group}">"#);
+        svg.push_str(&content);
+        let _ = writeln!(svg, "  </g>");
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// A straight line between the centres of the vertices. Dashes are spread
+/// over it like on the canvas, with a dash at both ends.
+fn write_edge(svg: &mut String, from: Vec2, to: Vec2, style: &EdgeStyle) {
+    let (from, to) = (flip(from), flip(to));
+    let _ = write!(
+        svg,
+        r#"    <line x1="{}" y1="{}" x2="{}" y2="{}"{} stroke-width="{}""#,
+        number(from.x),
+        number(from.y),
+        number(to.x),
+        number(to.y),
+        paint("stroke", style.color()),
+        number(style.width),
+    );
+
+    let length = from.distance(to);
+    if let Some((dashes, ratio)) = dash_pattern(style.line, length, style.width)
+        && dashes > 1
+    {
+        let period = length / (dashes as f32 - 1.0 + ratio);
+        let dash = period * ratio;
+        let _ = write!(
+            svg,
+            r#" stroke-dasharray="{} {}""#,
+            number(dash),
+            number(period - dash),
+        );
+    }
+    svg.push_str("/>\n");
+}
+
+/// The same unit shapes as `arrowhead_mesh`, scaled to `size`
+/// and turned towards `direction`.
+fn write_arrowhead(svg: &mut String, center: Vec2, direction: Vec2, size: f32, style: &EdgeStyle) {
+    let place = |point: Vec2| flip(center + direction.rotate(point * size));
+    let fill = paint("fill", style.color());
+    match style.arrow {
+        ArrowStyle::None => {}
+        ArrowStyle::Triangle => {
+            let points = [
+                Vec2::new(0.5, 0.0),
+                Vec2::new(-0.5, 0.5),
+                Vec2::new(-0.5, -0.5),
+            ];
+            write_polygon(svg, points.map(place), &fill);
+        }
+        ArrowStyle::Diamond => {
+            let points = [
+                Vec2::new(0.5, 0.0),
+                Vec2::new(0.0, 0.35),
+                Vec2::new(-0.5, 0.0),
+                Vec2::new(0.0, -0.35),
+            ];
+            write_polygon(svg, points.map(place), &fill);
+        }
+        ArrowStyle::Circle => {
+            let center = flip(center);
+            let _ = writeln!(
+                svg,
+                r#"    <circle cx="{}" cy="{}" r="{}"{fill}/>"#,
+                number(center.x),
+                number(center.y),
+                number(0.4 * size),
+            );
+        }
+    }
+}
+
+/// A vertex shape like `vertex_mesh` builds it. Rounded boxes are
+/// `half_length` longer than their radius on both sides.
+fn write_shape(
+    svg: &mut String,
+    shape: VertexShape,
+    center: Vec2,
+    radius: f32,
+    half_length: f32,
+    color: Color,
+) {
+    let fill = paint("fill", color);
+    let center = flip(center);
+    match shape {
+        VertexShape::Circle => {
+            let _ = writeln!(
+                svg,
+                r#"    <circle cx="{}" cy="{}" r="{}"{fill}/>"#,
+                number(center.x),
+                number(center.y),
+                number(radius),
+            );
+        }
+        VertexShape::Square | VertexShape::RoundedBox => {
+            let half_size = Vec2::new(half_length + radius, radius);
+            let corner = if shape == VertexShape::RoundedBox {
+                radius
+            } else {
+                0.0
+            };
+            let _ = writeln!(
+                svg,
+                r#"    <rect x="{}" y="{}" width="{}" height="{}" rx="{}"{fill}/>"#,
+                number(center.x - half_size.x),
+                number(center.y - half_size.y),
+                number(half_size.x * 2.0),
+                number(half_size.y * 2.0),
+                number(corner),
+            );
+        }
+        VertexShape::Diamond => {
+            let points = [
+                Vec2::new(radius, 0.0),
+                Vec2::new(0.0, radius),
+                Vec2::new(-radius, 0.0),
+                Vec2::new(0.0, -radius),
+            ];
+            write_polygon(svg, points.map(|point| center + point), &fill);
+        }
+    }
+}
+
+fn write_polygon<const N: usize>(svg: &mut String, points: [Vec2; N], fill: &str) {
+    let points: Vec<String> = points
+        .iter()
+        .map(|point| format!("{},{}", number(point.x), number(point.y)))
+        .collect();
+    let _ = writeln!(svg, r#"    <polygon points="{}"{fill}/>"#, points.join(" "));
+}
+
+/// Centred on `center`, like `Text2d` is.
+fn write_text(svg: &mut String, center: Vec2, text: &str, font_size: f32, color: Color) {
+    let center = flip(center);
+    let _ = writeln!(
+        svg,
+        r#"    <text x="{}" y="{}" font-size="{}" font-family="sans-serif" text-anchor="middle" dominant-baseline="central"{}>{}</text>"#,
+        number(center.x),
+        number(center.y),
+        number(font_size),
+        paint("fill", color),
+        escape_xml(text),
+    );
+}
+
+/// A rough guess of the area a text covers, there is no font to measure it with.
+fn text_rect(center: Vec2, text: &str, font_size: f32) -> Rect {
+    Rect::from_center_size(center, Vec2::new(label_width(text, font_size), font_size))
+}
+
+fn flip(point: Vec2) -> Vec2 {
+    Vec2::new(point.x, -point.y)
+}
+
+/// A colour attribute, with its opacity only if it isn't opaque.
+fn paint(attribute: &str, color: Color) -> String {
+    let [r, g, b, a] = color.to_srgba().to_u8_array();
+    let mut paint = format!(r##" {attribute}="#{r:02x}{g:02x}{b:02x}""##);
+    if a < u8::MAX {
+        let _ = write!(
+            paint,
+            r#" {attribute}-opacity="{}""#,
+            number(a as f32 / 255.0)
+        );
+    }
+    paint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{
+        document::{EdgeRecord, VertexRecord},
+        settings::Palette,
+    };
+
+    fn vertex(label: &str, x: f32, y: f32) -> VertexRecord {
+        VertexRecord {
+            label: label.to_string(),
+            x,
+            y,
+            style: Some(VertexStyle {
+                radius: 10.0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn edge(arrow: ArrowStyle) -> EdgeRecord {
+        EdgeRecord {
+            from: 0,
+            to: 1,
+            weight: None,
+            attributes: Vec::new(),
+            style: Some(EdgeStyle {
+                width: 2.0,
+                arrow,
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn document(arrow: ArrowStyle, y: f32) -> GraphDocument {
+        GraphDocument {
+            vertices: vec![vertex("", 0.0, 0.0), vertex("", 100.0, y)],
+            edges: vec![edge(arrow)],
+        }
+    }
+
+    #[test]
+    fn crops_to_the_drawing_and_flips_y() {
+        let svg = document_to_svg(
+            &document(ArrowStyle::None, 50.0),
+            &Settings::default(),
+            false,
+        );
+        // The vertices cover (-10, -10) to (110, 60), padded by 20 on every side.
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="160" height="110" viewBox="-30 -80 160 110">"#
+        ));
+        assert_eq!(EXPORT_PADDING, 20.0);
+        assert!(svg.contains(r#"<line x1="0" y1="0" x2="100" y2="-50""#));
+        assert!(svg.contains(r#"<circle cx="100" cy="-50" r="10""#));
+    }
+
+    #[test]
+    fn background_is_optional() {
+        let document = document(ArrowStyle::None, 50.0);
+        let with = document_to_svg(&document, &Settings::default(), true);
+        let fill = paint("fill", Palette::DARK.background);
+        assert!(with.contains(&format!(
+            r#"  <rect x="-30" y="-80" width="160" height="110"{fill}/>"#
+        )));
+
+        let without = document_to_svg(&document, &Settings::default(), false);
+        assert!(!without.contains("<rect"));
+        assert_eq!(with.lines().count(), without.lines().count() + 1);
+    }
+
+    #[test]
+    fn arrowheads_touch_the_target() {
+        // A 12 wide arrowhead ends at the radius of the target, at x = 90.
+        let triangle = document_to_svg(
+            &document(ArrowStyle::Triangle, 0.0),
+            &Settings::default(),
+            false,
+        );
+        assert!(triangle.contains(r#"<polygon points="90,0 78,-6 78,6""#));
+
+        let circle = document_to_svg(
+            &document(ArrowStyle::Circle, 0.0),
+            &Settings::default(),
+            false,
+        );
+        assert!(circle.contains(r#"<circle cx="84" cy="0" r="4.8""#));
+
+        let none = document_to_svg(
+            &document(ArrowStyle::None, 0.0),
+            &Settings::default(),
+            false,
+        );
+        assert!(!none.contains("<polygon") && !none.contains(r#"cx="84""#));
+    }
+
+    #[test]
+    fn escapes_labels() {
+        let document = GraphDocument {
+            vertices: vec![vertex(r#"<a & "b">"#, 0.0, 0.0)],
+            edges: Vec::new(),
+        };
+        let svg = document_to_svg(&document, &Settings::default(), false);
+        assert!(svg.contains(r#">&lt;a &amp; &quot;b&quot;&gt;</text>"#));
+        assert!(!svg.contains("<a "));
+    }
+}
@@ -0,0 +1,160 @@
+use std::fmt::Write;
+
+use crate::graph::{
+    constants::TIKZ_UNITS_PER_CM,
+    document::GraphDocument,
+    formats::{ExportOptions, number},
+};
+
+/// A `tikzpicture` with a node per vertex and a `\draw[->]` per edge, named
+/// after the style names of the options. Positions are in cm and keep the
+/// y axis of the canvas, so the picture looks the same way around.
+pub fn document_to_tikz(document: &GraphDocument, options: &ExportOptions) -> String {
+    let [vertex_style, edge_style, weight_style] = options.tikz_styles();
+
+    let mut tikz = String::new();
+    if options.tikz_define_styles {
+        let _ = writeln!(tikz, r"\begin{{tikzpicture}}[");
+        let _ = writeln!(
+            tikz,
+            r"  {vertex_style}/.style={{circle, draw, minimum size=4mm}},"
+        );
+        let _ = writeln!(tikz, r"  {edge_style}/.style={{thick}},");
+        let _ = writeln!(tikz, r"  {weight_style}/.style={{auto, font=\small}}");
+        let _ = writeln!(tikz, "]");
+    } else {
+        let _ = writeln!(tikz, r"\begin{{tikzpicture}}");
+    }
+
+    for (index, vertex) in document.vertices.iter().enumerate() {
+        let _ = writeln!(
+            tikz,
+            r"  \node[{vertex_style}] (v{index}) at ({}, {}) {{{}}};",
+            number(vertex.x / TIKZ_UNITS_PER_CM),
+            number(vertex.y / TIKZ_UNITS_PER_CM),
+            escape_latex(&vertex.label),
+        );
+    }
+
+    for edge in &document.edges {
+        let weight = edge.weight.map_or(String::new(), |weight| {
+            format!(
+                " node[{weight_style}] {{{}}}",
+                escape_latex(&weight.to_string())
+            )
+        });
+        let path = if edge.from == edge.to {
+            "to[loop above]"
+        } else {
+            "--"
+        };
+        let _ = writeln!(
+            tikz,
+            r"  \draw[->, {edge_style}] (v{}) {path}{weight} (v{});",
+            edge.from, edge.to,
+        );
+    }
+
+    tikz.push_str("\\end{tikzpicture}\n");
+    tikz
+}
+
+/// Makes text safe to put into a node, the special characters
+/// of LaTeX are printed as they are.
+fn escape_latex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '\\' => escaped.push_str(r"\textbackslash{}"),
+            '~' => escaped.push_str(r"\textasciitilde{}"),
+            '^' => escaped.push_str(r"\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{
+        document::{EdgeRecord, VertexRecord},
+        formats::ExportFormat,
+        settings::Settings,
+    };
+
+    fn document() -> GraphDocument {
+        GraphDocument {
+            vertices: vec![
+                VertexRecord {
+                    label: "a".to_string(),
+                    ..Default::default()
+                },
+                VertexRecord {
+                    label: "b_1".to_string(),
+                    x: 50.0,
+                    ..Default::default()
+                },
+            ],
+            edges: vec![EdgeRecord {
+                from: 0,
+                to: 1,
+                weight: Some(2.5),
+                attributes: Vec::new(),
+                style: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn writes_nodes_and_edges() {
+        let tikz = document_to_tikz(&document(), &ExportOptions::default());
+        assert!(tikz.contains(r"  vertex/.style={circle, draw, minimum size=4mm},"));
+        assert!(tikz.contains(r"  \node[vertex] (v1) at (1, 0) {b\_1};"));
+        assert!(tikz.contains(r"  \draw[->, edge] (v0) -- node[weight] {2.5} (v1);"));
+    }
+
+    #[test]
+    fn empty_style_names_fall_back_to_defaults() {
+        let options = ExportOptions {
+            tikz_vertex_style: String::new(),
+            tikz_edge_style: "  ".to_string(),
+            tikz_weight_style: " label ".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(options.tikz_styles_error(), None);
+        let tikz = document_to_tikz(&document(), &options);
+        assert_eq!(
+            tikz,
+            document_to_tikz(
+                &document(),
+                &ExportOptions {
+                    tikz_weight_style: "label".to_string(),
+                    ..Default::default()
+                }
+            )
+        );
+        assert!(!tikz.contains("[]") && !tikz.contains("  /.style"));
+    }
+
+    #[test]
+    fn invalid_style_names_are_rejected() {
+        for name in ["thick, red", "a=b", "x]", r"\foo", "a/b"] {
+            let options = ExportOptions {
+                tikz_edge_style: name.to_string(),
+                ..Default::default()
+            };
+            assert!(options.tikz_styles_error().is_some(), "{name}");
+            assert!(
+                ExportFormat::Tikz
+                    .write(&document(), &Settings::default(), &options)
+                    .is_err()
+            );
+        }
+    }
+}
@@ -0,0 +1,518 @@
+use std::{collections::HashSet, f32::consts::TAU};
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::graph::constants::{GENERATOR_MAX_EDGES, GENERATOR_MAX_VERTICES, GENERATOR_SPACING};
+
+/// The shapes the generator dialog can produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneratorKind {
+    Complete,
+    Cycle,
+    Path,
+    Star,
+    Wheel,
+    Grid,
+    Hypercube,
+    CompleteBipartite,
+    Petersen,
+    RandomTree,
+    ErdosRenyi,
+    BarabasiAlbert,
+    WattsStrogatz,
+}
+
+impl GeneratorKind {
+    pub const ALL: [GeneratorKind; 13] = [
+        GeneratorKind::Complete,
+        GeneratorKind::Cycle,
+        GeneratorKind::Path,
+        GeneratorKind::Star,
+        GeneratorKind::Wheel,
+        GeneratorKind::Grid,
+        GeneratorKind::Hypercube,
+        GeneratorKind::CompleteBipartite,
+        GeneratorKind::Petersen,
+        GeneratorKind::RandomTree,
+        GeneratorKind::ErdosRenyi,
+        GeneratorKind::BarabasiAlbert,
+        GeneratorKind::WattsStrogatz,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GeneratorKind::Complete => "Complete",
+            GeneratorKind::Cycle => "Cycle",
+            GeneratorKind::Path => "Path",
+            GeneratorKind::Star => "Star",
+            GeneratorKind::Wheel => "Wheel",
+            GeneratorKind::Grid => "Grid",
+            GeneratorKind::Hypercube => "Hypercube",
+            GeneratorKind::CompleteBipartite => "Complete bipartite",
+            GeneratorKind::Petersen => "Petersen",
+            GeneratorKind::RandomTree => "Random tree",
+            GeneratorKind::ErdosRenyi => "Erdős–Rényi G(n, p)",
+            GeneratorKind::BarabasiAlbert => "Barabási–Albert",
+            GeneratorKind::WattsStrogatz => "Watts–Strogatz",
+        }
+    }
+
+    /// Whether the seed has any effect on the result.
+    pub fn is_random(&self) -> bool {
+        matches!(
+            self,
+            GeneratorKind::RandomTree
+                | GeneratorKind::ErdosRenyi
+                | GeneratorKind::BarabasiAlbert
+                | GeneratorKind::WattsStrogatz
+        )
+    }
+
+    /// The largest vertex count that stays within `GENERATOR_MAX_EDGES`,
+    /// for the kinds whose edge count grows with the square of it.
+    pub fn max_vertices(&self, parameters: &GeneratorParameters) -> usize {
+        let density = match self {
+            GeneratorKind::Complete => 1.0,
+            GeneratorKind::ErdosRenyi => parameters.p.clamp(0.0, 1.0),
+            _ => return GENERATOR_MAX_VERTICES,
+        };
+        if density == 0.0 {
+            return GENERATOR_MAX_VERTICES;
+        }
+        // Largest n with density * n * (n - 1) / 2 <= GENERATOR_MAX_EDGES.
+        let pairs = GENERATOR_MAX_EDGES as f64 / density;
+        let n = ((1.0 + (1.0 + 8.0 * pairs).sqrt()) / 2.0).floor() as usize;
+        n.clamp(1, GENERATOR_MAX_VERTICES)
+    }
+}
+
+/// Every parameter any of the generators might use.
+/// Each generator only reads the ones relevant to it.
+#[derive(Debug, Clone)]
+pub struct GeneratorParameters {
+    /// Vertex count for most generators.
+    pub n: usize,
+    /// Second part size for bipartite graphs, and the
+    /// number of edges per new vertex for Barabási–Albert.
+    pub m: usize,
+    /// Edge probability for Erdős–Rényi.
+    pub p: f64,
+    /// Ring lattice degree for Watts–Strogatz.
+    pub k: usize,
+    /// Rewiring probability for Watts–Strogatz.
+    pub beta: f64,
+    pub rows: usize,
+    pub columns: usize,
+    pub dimension: usize,
+    pub seed: u64,
+}
+
+impl Default for GeneratorParameters {
+    fn default() -> Self {
+        GeneratorParameters {
+            n: 8,
+            m: 2,
+            p: 0.2,
+            k: 4,
+            beta: 0.2,
+            rows: 4,
+            columns: 5,
+            dimension: 3,
+            seed: 0,
+        }
+    }
+}
+
+/// A generated graph, positions are relative to its center
+/// and edges are index pairs into `positions`.
+#[derive(Debug, Default)]
+pub struct GeneratedGraph {
+    pub positions: Vec<Vec2>,
+    pub edges: Vec<(usize, usize)>,
+}
+
+pub fn generate(kind: GeneratorKind, parameters: &GeneratorParameters) -> GeneratedGraph {
+    let mut rng = StdRng::seed_from_u64(parameters.seed);
+    let n = parameters.n.min(kind.max_vertices(parameters));
+
+    match kind {
+        GeneratorKind::Complete => GeneratedGraph {
+            positions: circle_layout(n),
+            edges: (0..n)
+                .flat_map(|from| (from + 1..n).map(move |to| (from, to)))
+                .collect(),
+        },
+        GeneratorKind::Cycle => GeneratedGraph {
+            positions: circle_layout(n),
+            edges: cycle_edges(0, n),
+        },
+        GeneratorKind::Path => GeneratedGraph {
+            positions: (0..n)
+                .map(|index| {
+                    Vec2::new(
+                        (index as f32 - (n as f32 - 1.0) / 2.0) * GENERATOR_SPACING,
+                        0.0,
+                    )
+                })
+                .collect(),
+            edges: (1..n).map(|index| (index - 1, index)).collect(),
+        },
+        GeneratorKind::Star => {
+            let mut positions = vec![Vec2::ZERO];
+            positions.extend(circle_layout(n.saturating_sub(1)));
+            GeneratedGraph {
+                positions,
+                edges: (1..n).map(|leaf| (0, leaf)).collect(),
+            }
+        }
+        GeneratorKind::Wheel => {
+            let rim = n.saturating_sub(1);
+            let mut positions = vec![Vec2::ZERO];
+            positions.extend(circle_layout(rim));
+            let mut edges: Vec<(usize, usize)> = (1..n).map(|spoke| (0, spoke)).collect();
+            edges.extend(cycle_edges(1, rim));
+            GeneratedGraph { positions, edges }
+        }
+        GeneratorKind::Grid => grid(parameters.rows, parameters.columns),
+        GeneratorKind::Hypercube => hypercube(parameters.dimension),
+        GeneratorKind::CompleteBipartite => complete_bipartite(n, parameters.m),
+        GeneratorKind::Petersen => petersen(),
+        GeneratorKind::RandomTree => random_tree(n, &mut rng),
+        GeneratorKind::ErdosRenyi => {
+            let mut edges = Vec::new();
+            for from in 0..n {
+                for to in from + 1..n {
+                    if rng.random_bool(parameters.p.clamp(0.0, 1.0)) {
+                        edges.push((from, to));
+                    }
+                }
+            }
+            GeneratedGraph {
+                positions: circle_layout(n),
+                edges,
+            }
+        }
+        GeneratorKind::BarabasiAlbert => barabasi_albert(n, parameters.m, &mut rng),
+        GeneratorKind::WattsStrogatz => watts_strogatz(n, parameters.k, parameters.beta, &mut rng),
+    }
+}
+
+/// A large, roughly square grid with extra short chords between nearby
+/// rows until it has `edge_count` edges. Used by the benchmark scene.
+pub fn benchmark_graph(vertex_count: usize, edge_count: usize, seed: u64) -> GeneratedGraph {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let columns = (vertex_count as f32).sqrt().ceil().max(1.0) as usize;
+    let rows = vertex_count.div_ceil(columns);
+
+    let mut graph = grid(rows, columns);
+    graph.positions.truncate(vertex_count);
+    graph
+        .edges
+        .retain(|(from, to)| *from < vertex_count && *to < vertex_count);
+    graph.edges.truncate(edge_count);
+
+    let mut existing: HashSet<(usize, usize)> = graph.edges.iter().copied().collect();
+    let reach = 2 * columns;
+    while graph.edges.len() < edge_count && vertex_count > reach {
+        let from = rng.random_range(0..vertex_count - reach);
+        let to = from + rng.random_range(2..=reach);
+        if existing.insert((from, to)) {
+            graph.edges.push((from, to));
+        }
+    }
+
+    graph
+}
+
+/// Vertices evenly spread on a circle whose size grows with
+/// the vertex count, so neighbours keep a constant distance.
+pub fn circle_layout(n: usize) -> Vec<Vec2> {
+    if n == 1 {
+        return vec![Vec2::ZERO];
+    }
+
+    let radius = (n as f32 * GENERATOR_SPACING / TAU).max(GENERATOR_SPACING);
+    (0..n)
+        .map(|index| {
+            let angle = TAU * index as f32 / n as f32;
+            Vec2::from_angle(angle) * radius
+        })
+        .collect()
+}
+
+/// Edges around `count` consecutive vertices starting at `first`.
+fn cycle_edges(first: usize, count: usize) -> Vec<(usize, usize)> {
+    if count < 3 {
+        return (1..count)
+            .map(|index| (first + index - 1, first + index))
+            .collect();
+    }
+    (0..count)
+        .map(|index| (first + index, first + (index + 1) % count))
+        .collect()
+}
+
+fn grid(rows: usize, columns: usize) -> GeneratedGraph {
+    let offset = Vec2::new(columns as f32 - 1.0, rows as f32 - 1.0) * GENERATOR_SPACING / 2.0;
+    let mut positions = Vec::with_capacity(rows * columns);
+    let mut edges = Vec::new();
+
+    for row in 0..rows {
+        for column in 0..columns {
+            let index = row * columns + column;
+            positions.push(
+                Vec2::new(column as f32, -(row as f32)) * GENERATOR_SPACING
+                    + Vec2::new(-offset.x, offset.y),
+            );
+            if column + 1 < columns {
+                edges.push((index, index + 1));
+            }
+            if row + 1 < rows {
+                edges.push((index, index + columns));
+            }
+        }
+    }
+
+    GeneratedGraph { positions, edges }
+}
+
+/// Each bit of the vertex index moves it along its own direction,
+/// which gives the usual projection of a hypercube.
+fn hypercube(dimension: usize) -> GeneratedGraph {
+    let n = 1usize << dimension;
+    let directions: Vec<Vec2> = (0..dimension)
+        .map(|bit| {
+            Vec2::from_angle(TAU / 2.0 * bit as f32 / dimension as f32) * GENERATOR_SPACING * 1.5
+        })
+        .collect();
+    let center = directions.iter().sum::<Vec2>() / 2.0;
+
+    let positions = (0..n)
+        .map(|index| {
+            directions
+                .iter()
+                .enumerate()
+                .filter(|(bit, _)| index & (1 << bit) != 0)
+                .map(|(_, direction)| *direction)
+                .sum::<Vec2>()
+                - center
+        })
+        .collect();
+    let edges = (0..n)
+        .flat_map(|index| {
+            (0..dimension)
+                .map(move |bit| (index, index ^ (1 << bit)))
+                .filter(|(from, to)| from < to)
+        })
+        .collect();
+
+    GeneratedGraph { positions, edges }
+}
+
+/// The two parts are placed in two columns.
+fn complete_bipartite(left: usize, right: usize) -> GeneratedGraph {
+    let column = |count: usize, x: f32| {
+        (0..count).map(move |index| {
+            Vec2::new(
+                x,
+                ((count as f32 - 1.0) / 2.0 - index as f32) * GENERATOR_SPACING,
+            )
+        })
+    };
+    let width = GENERATOR_SPACING * 2.0;
+    let positions = column(left, -width).chain(column(right, width)).collect();
+    let edges = (0..left)
+        .flat_map(|from| (0..right).map(move |to| (from, left + to)))
+        .collect();
+
+    GeneratedGraph { positions, edges }
+}
+
+/// Outer pentagon, inner pentagram and the spokes between them.
+fn petersen() -> GeneratedGraph {
+    let point =
+        |index: usize, radius: f32| Vec2::from_angle(TAU / 4.0 + TAU * index as f32 / 5.0) * radius;
+    let positions = (0..5)
+        .map(|index| point(index, GENERATOR_SPACING * 2.5))
+        .chain((0..5).map(|index| point(index, GENERATOR_SPACING * 1.2)))
+        .collect();
+
+    let mut edges = Vec::new();
+    for index in 0..5 {
+        edges.push((index, (index + 1) % 5));
+        edges.push((index, index + 5));
+        edges.push((index + 5, (index + 2) % 5 + 5));
+    }
+
+    GeneratedGraph { positions, edges }
+}
+
+/// Every new vertex attaches to a uniformly chosen earlier one,
+/// then the tree is laid out level by level from the root.
+fn random_tree(n: usize, rng: &mut StdRng) -> GeneratedGraph {
+    let parents: Vec<usize> = (1..n).map(|index| rng.random_range(0..index)).collect();
+    let edges: Vec<(usize, usize)> = parents
+        .iter()
+        .enumerate()
+        .map(|(index, parent)| (*parent, index + 1))
+        .collect();
+
+    let mut depths = vec![0usize; n];
+    for (index, parent) in parents.iter().enumerate() {
+        depths[index + 1] = depths[*parent] + 1;
+    }
+    let max_depth = depths.iter().copied().max().unwrap_or(0);
+    let mut levels = vec![Vec::new(); max_depth + 1];
+    for (index, depth) in depths.iter().enumerate() {
+        levels[*depth].push(index);
+    }
+
+    let mut positions = vec![Vec2::ZERO; n];
+    for (depth, level) in levels.iter().enumerate() {
+        for (slot, index) in level.iter().enumerate() {
+            positions[*index] = Vec2::new(
+                (slot as f32 - (level.len() as f32 - 1.0) / 2.0) * GENERATOR_SPACING,
+                (max_depth as f32 / 2.0 - depth as f32) * GENERATOR_SPACING,
+            );
+        }
+    }
+
+    GeneratedGraph { positions, edges }
+}
+
+/// Starts from a star of `m + 1` vertices, then every new vertex
+/// connects to `m` distinct vertices chosen proportionally to their degree.
+fn barabasi_albert(n: usize, m: usize, rng: &mut StdRng) -> GeneratedGraph {
+    let m = m.max(1);
+    let initial = (m + 1).min(n);
+    let mut edges: Vec<(usize, usize)> = (1..initial).map(|leaf| (0, leaf)).collect();
+    // Every vertex appears once per incident edge end.
+    let mut endpoints: Vec<usize> = edges.iter().flat_map(|(from, to)| [*from, *to]).collect();
+
+    for vertex in initial..n {
+        // A vector keeps the order of the draws, so a seed always gives the same graph.
+        let mut targets = Vec::new();
+        while targets.len() < m.min(vertex) {
+            let target = endpoints[rng.random_range(0..endpoints.len())];
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        for target in targets {
+            edges.push((vertex, target));
+            endpoints.extend([vertex, target]);
+        }
+    }
+
+    GeneratedGraph {
+        positions: circle_layout(n),
+        edges,
+    }
+}
+
+/// Ring lattice where each vertex reaches `k / 2` neighbours on both
+/// sides, then each edge is rewired with probability `beta`.
+fn watts_strogatz(n: usize, k: usize, beta: f64, rng: &mut StdRng) -> GeneratedGraph {
+    let half = (k / 2).min(n.saturating_sub(1) / 2);
+    let mut existing = HashSet::new();
+    let mut degrees = vec![0usize; n];
+    let mut edges = Vec::new();
+    for offset in 1..=half {
+        for from in 0..n {
+            let to = (from + offset) % n;
+            existing.insert((from.min(to), from.max(to)));
+            degrees[from] += 1;
+            degrees[to] += 1;
+            edges.push((from, to));
+        }
+    }
+
+    for edge in edges.iter_mut() {
+        if !rng.random_bool(beta.clamp(0.0, 1.0)) {
+            continue;
+        }
+        let (from, to) = *edge;
+        // A fully connected vertex has nowhere to be rewired to.
+        if degrees[from] >= n - 1 {
+            continue;
+        }
+        loop {
+            let new_to = rng.random_range(0..n);
+            let key = (from.min(new_to), from.max(new_to));
+            if new_to != from && !existing.contains(&key) {
+                existing.remove(&(from.min(to), from.max(to)));
+                existing.insert(key);
+                degrees[to] -= 1;
+                degrees[new_to] += 1;
+                *edge = (from, new_to);
+                break;
+            }
+        }
+    }
+
+    GeneratedGraph {
+        positions: circle_layout(n),
+        edges,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_kinds_are_reproducible() {
+        let parameters = GeneratorParameters {
+            n: 200,
+            m: 3,
+            seed: 7,
+            ..default()
+        };
+        for kind in GeneratorKind::ALL
+            .into_iter()
+            .filter(GeneratorKind::is_random)
+        {
+            let first = generate(kind, &parameters);
+            let second = generate(kind, &parameters);
+            assert_eq!(first.edges, second.edges, "{}", kind.name());
+            assert_eq!(first.positions, second.positions, "{}", kind.name());
+        }
+    }
+
+    #[test]
+    fn barabasi_albert_targets_are_distinct() {
+        let parameters = GeneratorParameters {
+            n: 300,
+            m: 4,
+            ..default()
+        };
+        let graph = generate(GeneratorKind::BarabasiAlbert, &parameters);
+        let distinct: HashSet<(usize, usize)> = graph.edges.iter().copied().collect();
+        assert_eq!(distinct.len(), graph.edges.len());
+        assert!(graph.edges.iter().all(|(from, to)| from != to));
+    }
+
+    #[test]
+    fn dense_kinds_are_capped() {
+        let parameters = GeneratorParameters {
+            n: GENERATOR_MAX_VERTICES,
+            p: 1.0,
+            ..default()
+        };
+        for kind in [GeneratorKind::Complete, GeneratorKind::ErdosRenyi] {
+            let graph = generate(kind, &parameters);
+            assert!(graph.edges.len() <= GENERATOR_MAX_EDGES, "{}", kind.name());
+            assert_eq!(graph.positions.len(), kind.max_vertices(&parameters));
+        }
+
+        let sparse = GeneratorParameters {
+            p: 0.0,
+            ..parameters
+        };
+        assert_eq!(
+            GeneratorKind::ErdosRenyi.max_vertices(&sparse),
+            GENERATOR_MAX_VERTICES
+        );
+    }
+}
@@ -1,6 +1,23 @@
-use bevy::prelude::*;
+use std::{f32::consts::FRAC_PI_2, path::Path};
 
-use crate::graph::components::Position;
+use bevy::{
+    asset::RenderAssetUsages,
+    mesh::{Indices, PrimitiveTopology},
+    prelude::*,
+};
+
+use crate::graph::{
+    components::{
+        ArrowStyle, DirectedEdge, EdgeStyle, HeatMapStyle, LineStyle, Position, Vertex,
+        VertexShape, VertexStyle,
+    },
+    constants::{
+        CHARACTER_WIDTH_FACTOR, DASH_LENGTH_FACTOR, EDGE_HANDLE_GAP, FRAME_MIN_SCALE,
+        FRAME_PADDING, METRICS_EXTENSION, ROUNDED_BOX_PADDING, STYLE_HOVER_LIGHTENING,
+        VERTEX_LABEL_GAP,
+    },
+    settings::Palette,
+};
 
 /// Despawning an entity requires it to be an entity command
 /// first, which I just decided to put here.
@@ -21,3 +38,261 @@ pub fn update_entity_position(
         position.0 = new_position;
     };
 }
+
+/// Where the handles of the `from` and `to` ends of an edge are, on the
+/// edge just outside of its vertices. `None` if a vertex is missing.
+/// Unstyled vertices have the radius of the settings.
+pub fn edge_handles(
+    edge: &DirectedEdge,
+    vertices: &Query<(&Position, Option<&VertexStyle>), With<Vertex>>,
+    vertex_radius: f32,
+) -> Option<[Vec2; 2]> {
+    let (from, from_style) = vertices.get(edge.from).ok()?;
+    let (to, to_style) = vertices.get(edge.to).ok()?;
+    let direction = (to.0 - from.0).normalize_or_zero();
+    let offset = |style: Option<&VertexStyle>| {
+        style.map_or(vertex_radius, |style| style.radius) + EDGE_HANDLE_GAP
+    };
+    Some([
+        from.0 + direction * offset(from_style),
+        to.0 - direction * offset(to_style),
+    ])
+}
+
+/// Unnamed vertices still need something to show in lists and tables.
+pub fn vertex_display_name(label: &str, entity: Entity) -> String {
+    if label.is_empty() {
+        format!("<{entity}>")
+    } else {
+        label.to_string()
+    }
+}
+
+/// The metrics CSV lives next to the document it was computed from.
+pub fn metrics_path(document_path: &str) -> String {
+    Path::new(document_path)
+        .with_extension(METRICS_EXTENSION)
+        .display()
+        .to_string()
+}
+
+/// Quotes a CSV field if it would break the row otherwise.
+pub fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// The world position under the cursor, if the cursor is over the window.
+pub fn cursor_world_position(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Option<Vec2> {
+    let cursor = window.cursor_position()?;
+    camera.viewport_to_world_2d(camera_transform, cursor).ok()
+}
+
+/// The smallest rectangle around all `points`, `None` if there are none.
+pub fn bounding_rect(points: impl IntoIterator<Item = Vec2>) -> Option<Rect> {
+    let mut points = points.into_iter();
+    let first = Rect::from_center_size(points.next()?, Vec2::ZERO);
+    Some(points.fold(first, |rect, point| rect.union_point(point)))
+}
+
+/// The part of the world a 2D camera shows in a viewport of `viewport_size`.
+pub fn visible_rect(transform: &Transform, projection: &Projection, viewport_size: Vec2) -> Rect {
+    let scale = match projection {
+        Projection::Orthographic(orthographic) => orthographic.scale,
+        _ => 1.0,
+    };
+    Rect::from_center_size(transform.translation.truncate(), viewport_size * scale)
+}
+
+/// Centres the camera on `rect` and zooms so all of it, plus `FRAME_PADDING`
+/// around it, fits a viewport of `viewport_size`. Small or single point
+/// rectangles are not zoomed into further than `FRAME_MIN_SCALE`.
+pub fn frame_rect(
+    rect: Rect,
+    viewport_size: Vec2,
+    transform: &mut Transform,
+    projection: &mut Projection,
+) {
+    let rect = rect.inflate(FRAME_PADDING);
+    transform.translation.x = rect.center().x;
+    transform.translation.y = rect.center().y;
+    if let Projection::Orthographic(orthographic) = projection {
+        let scale = rect.size() / viewport_size.max(Vec2::ONE);
+        orthographic.scale = scale.max_element().max(FRAME_MIN_SCALE);
+    }
+}
+
+/// The mesh of a vertex shape. Rounded boxes are as long as their label
+/// of `label_width`, `outset` grows the shape around it, e.g. for the stroke.
+pub fn vertex_mesh(shape: VertexShape, radius: f32, label_width: f32, outset: f32) -> Mesh {
+    let size = (radius + outset) * 2.0;
+    match shape {
+        VertexShape::Circle => Circle::new(radius + outset).into(),
+        VertexShape::Square => Rectangle::new(size, size).into(),
+        VertexShape::Diamond => Rhombus::new(size, size).into(),
+        VertexShape::RoundedBox => {
+            let length = rounded_box_length(radius, label_width);
+            Mesh::from(Capsule2d::new(radius + outset, length))
+                .rotated_by(Quat::from_rotation_z(FRAC_PI_2))
+        }
+    }
+}
+
+/// Roughly how wide a label is at `font_size`, used to size rounded boxes to it.
+pub fn label_width(label: &str, font_size: f32) -> f32 {
+    label.chars().count() as f32 * font_size * CHARACTER_WIDTH_FACTOR
+}
+
+/// Length of the straight part of a rounded box around a label.
+pub fn rounded_box_length(radius: f32, label_width: f32) -> f32 {
+    (label_width + 2.0 * ROUNDED_BOX_PADDING - 2.0 * radius).max(0.0)
+}
+
+/// Where the label of a vertex goes relative to it, labels below
+/// the vertex move further down the larger their font is.
+pub fn vertex_label_offset(shape: VertexShape, radius: f32, font_size: f32) -> Vec2 {
+    match shape {
+        VertexShape::RoundedBox => Vec2::ZERO,
+        _ => Vec2::new(0.0, -radius - VERTEX_LABEL_GAP - font_size / 2.0),
+    }
+}
+
+/// The colour of a vertex that is not hovered. Heat-map colours take
+/// precedence, unstyled vertices follow the palette of the theme.
+pub fn vertex_fill(
+    style: Option<&VertexStyle>,
+    heat_map: Option<&HeatMapStyle>,
+    palette: Palette,
+) -> Color {
+    heat_map
+        .and_then(|heat_map| heat_map.color)
+        .or(style.map(VertexStyle::fill_color))
+        .unwrap_or(palette.vertex)
+}
+
+pub fn hovered_vertex_fill(style: Option<&VertexStyle>, palette: Palette) -> Color {
+    style.map_or(palette.hovered_vertex, |style| {
+        style.fill_color().lighter(STYLE_HOVER_LIGHTENING)
+    })
+}
+
+pub fn edge_color(style: Option<&EdgeStyle>, palette: Palette) -> Color {
+    style.map_or(palette.edge, EdgeStyle::color)
+}
+
+pub fn hovered_edge_color(style: Option<&EdgeStyle>, palette: Palette) -> Color {
+    style.map_or(palette.hovered_edge, |style| {
+        style.color().lighter(STYLE_HOVER_LIGHTENING)
+    })
+}
+
+/// How many dashes fit on an edge, and which part of a dash period is
+/// the dash itself. `None` for solid lines.
+pub fn dash_pattern(line: LineStyle, length: f32, width: f32) -> Option<(usize, f32)> {
+    let (dash, gap) = match line {
+        LineStyle::Solid => return None,
+        LineStyle::Dashed => (width * DASH_LENGTH_FACTOR, width * DASH_LENGTH_FACTOR / 2.0),
+        LineStyle::Dotted => (width, width),
+    };
+    let dashes = ((length + gap) / (dash + gap)).floor().max(1.0) as usize;
+    Some((dashes, dash / (dash + gap)))
+}
+
+/// The corners of an edge, the unit square stretched by its transform.
+pub fn edge_quad(transform: &Transform) -> [[f32; 3]; 4] {
+    [[-0.5, -0.5], [0.5, -0.5], [0.5, 0.5], [-0.5, 0.5]].map(|[x, y]| {
+        let corner = transform.transform_point(Vec3::new(x, y, 0.0));
+        [corner.x, corner.y, 0.0]
+    })
+}
+
+/// A mesh with a quad for each edge of the `EdgeBatch`. The positions
+/// are rewritten in place when edges move, so it stays in the main world.
+pub fn edge_batch_mesh(quads: &[[[f32; 3]; 4]]) -> Mesh {
+    // A degenerate quad draws nothing, but keeps the buffers from being empty.
+    let quads = if quads.is_empty() {
+        &[[[0.0; 3]; 4]]
+    } else {
+        quads
+    };
+    let positions: Vec<[f32; 3]> = quads.iter().flatten().copied().collect();
+    let uvs: Vec<[f32; 2]> = quads
+        .iter()
+        .flat_map(|_| [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]])
+        .collect();
+    let indices: Vec<u32> = (0..quads.len() as u32)
+        .flat_map(|quad| {
+            let first = quad * 4;
+            [first, first + 1, first + 2, first, first + 2, first + 3]
+        })
+        .collect();
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        vec![[0.0, 0.0, 1.0]; positions.len()],
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Evenly spread dashes in the unit square of an edge, which the edge
+/// transform stretches to its length and width. Both ends are dashes.
+pub fn dashed_edge_mesh(dashes: usize, ratio: f32) -> Mesh {
+    let period = 1.0 / (dashes as f32 - 1.0 + ratio);
+    let mut positions = Vec::with_capacity(dashes * 4);
+    let mut uvs = Vec::with_capacity(dashes * 4);
+    let mut indices = Vec::with_capacity(dashes * 6);
+
+    for dash in 0..dashes {
+        let start = -0.5 + dash as f32 * period;
+        let end = start + ratio * period;
+        let first = positions.len() as u32;
+        positions.extend([
+            [start, -0.5, 0.0],
+            [end, -0.5, 0.0],
+            [end, 0.5, 0.0],
+            [start, 0.5, 0.0],
+        ]);
+        uvs.extend([[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]]);
+        indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        vec![[0.0, 0.0, 1.0]; positions.len()],
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Unit sized arrowhead pointing along the x axis.
+pub fn arrowhead_mesh(style: ArrowStyle) -> Mesh {
+    match style {
+        ArrowStyle::None => Rectangle::new(0.0, 0.0).into(),
+        ArrowStyle::Triangle => Triangle2d::new(
+            Vec2::new(0.5, 0.0),
+            Vec2::new(-0.5, 0.5),
+            Vec2::new(-0.5, -0.5),
+        )
+        .into(),
+        ArrowStyle::Diamond => Rhombus::new(1.0, 0.7).into(),
+        ArrowStyle::Circle => Circle::new(0.4).into(),
+    }
+}
@@ -1,47 +1,136 @@
-use bevy::prelude::*;
+use bevy::{
+    picking::{PickingSystems, mesh_picking::MeshPickingSettings},
+    prelude::*,
+    time::common_conditions::on_timer,
+};
 use bevy_egui::{EguiPlugin, EguiPrimaryContextPass};
 
+mod actions;
+mod analysis;
+mod benchmark;
 mod bundles;
 mod components;
 mod constants;
 mod custom_observers;
+mod document;
 mod events;
+mod formats;
+mod generators;
 mod helpers;
+mod panels;
 mod picking_observers;
+mod recovery;
 mod resources;
+mod search;
+mod settings;
+mod spatial;
 mod startups;
+mod text_editor;
 mod undo_redo;
 mod undo_redo_observers;
 mod updates;
 
 use custom_observers::{
-    canvas_clicked, click_vertex, edge_clicked, on_vertex_renamed, update_cursor_icon,
-    vertex_drag_dropped, vertex_dragging,
+    canvas_clicked, click_vertex, edge_clicked, edge_end_dropped, on_apply_graph_text,
+    on_attributes_edited, on_copy_selection, on_cut_selection, on_duplicate_selection,
+    on_edge_weight_edited, on_delete_elements, on_export_document, on_export_metrics,
+    on_generate_graph, on_merge_vertices, on_open_document, on_paste, on_recolor, on_run_action,
+    on_save_document, on_set_attribute, on_style_edited, on_toggle_edge, on_vertex_renamed,
+    update_cursor_icon, vertex_drag_dropped, vertex_dragging,
+};
+use actions::ActionRegistry;
+use analysis::GraphSnapshot;
+use constants::{
+    BENCHMARK_ARGUMENT, HANDLE_CACHE_EVICTION_INTERVAL, KEYBINDINGS_PATH, SPATIAL_CELL_SIZE,
+};
+use formats::ExportOptions;
+use panels::{
+    apply_egui_theme, show_adjacency_matrix, show_command_palette, show_generator_dialog,
+    show_inspector, show_merge_dialog, show_menu_bar, show_metrics_panel, show_minimap,
+    show_recovery_dialog, show_search_panel, show_settings_window, show_statistics_panel,
+    show_tables, show_text_editor,
+};
+use resources::{
+    AdjacencyMatrixState, AreaSelection, CommandPaletteState, DocumentState, EdgeEndDrag,
+    GeneratorDialogState, GraphClipboard, GraphMetrics, HandleCache, HoveredEntity, InspectorState,
+    MergeDialogState, MetricsPanelState, MinimapState, RenamingState, SearchState,
+    SettingsWindowState, StatisticsPanelState, TablesState, TextEditorState, UndoRedoStack,
+    VertexDrag, VertexIds,
+};
+use picking_observers::{
+    on_edge_clicked, on_edge_drag_start, on_edge_dragging, on_edge_drop, on_edge_hovered,
+    on_edge_out, on_vertex_clicked, on_vertex_dragged, on_vertex_dragging, on_vertex_drop,
+    on_vertex_hovered, on_vertex_out,
 };
-use resources::{HoveredEntity, RenamingState, UndoRedoStack};
-use startups::{spawn_canvas, spawn_temporary_edge};
+use recovery::RecoveryState;
+use settings::Settings;
+use spatial::SpatialIndex;
+use startups::{spawn_canvas, spawn_edge_batch, spawn_temporary_edge};
 use undo_redo_observers::{
     on_redo_edge_draw, on_redo_vertex_deletion, on_redo_vertex_move, on_redo_vertex_rename,
     on_redo_vertex_spawn, on_undo_edge_draw, on_undo_vertex_deletion, on_undo_vertex_move,
     on_undo_vertex_rename, on_undo_vertex_spawn, on_undo_edge_deletion, on_redo_edge_deletion,
-    on_redo_vertex_insertion, on_undo_vertex_insertion
+    on_redo_vertex_insertion, on_undo_vertex_insertion, on_redo_graph_insertion,
+    on_undo_graph_insertion, on_redo_graph_deletion, on_undo_graph_deletion, on_redo_graph_replace,
+    on_undo_graph_replace, on_redo_attributes_edit, on_undo_attributes_edit,
+    on_redo_edge_weight_edit, on_undo_edge_weight_edit, on_redo_style_edit, on_undo_style_edit,
+    on_redo_batch_edit, on_undo_batch_edit, on_redo_edge_reconnect, on_undo_edge_reconnect,
+    on_redo_vertex_merge, on_undo_vertex_merge,
 };
 use updates::{
-    cursor_icon_manager, project_positions, show_rename_input,
-    undo_redo_system, update_edge_transforms, update_temp_edge_transform,
+    apply_edge_styles, apply_heat_map, apply_settings, apply_vertex_styles, autosave,
+    autosave_on_exit, cursor_icon_manager, draw_edge_handles, draw_matrix_highlight,
+    draw_search_matches, draw_selection, evict_unused_handles, fit_canvas_to_camera,
+    pick_from_spatial_index, project_positions, shortcut_system, show_rename_input,
+    update_edge_arrowheads, update_edge_batch, update_edge_transforms, update_edge_weight_labels,
+    update_graph_metrics, update_graph_snapshot, update_graph_statistics, update_search_matches,
+    update_spatial_index, update_temp_edge_transform, update_window_title,
 };
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((EguiPlugin::default(), MeshPickingPlugin))
+        // Vertices and edges are picked through the spatial index,
+        // only the canvas is left to mesh picking.
+        .insert_resource(MeshPickingSettings {
+            require_markers: true,
+            ..default()
+        })
+        .insert_resource(SpatialIndex::new(SPATIAL_CELL_SIZE))
+        .insert_resource(AreaSelection::default())
         .insert_resource(HoveredEntity(None))
         .insert_resource(RenamingState::default())
         .insert_resource(UndoRedoStack::default())
+        .insert_resource(GraphSnapshot::default())
+        .insert_resource(GraphMetrics::default())
+        .insert_resource(MetricsPanelState::default())
+        .insert_resource(StatisticsPanelState::default())
+        .insert_resource(GeneratorDialogState::default())
+        .insert_resource(GraphClipboard::default())
+        .insert_resource(DocumentState::default())
+        .insert_resource(InspectorState::default())
+        .insert_resource(HandleCache::default())
+        .insert_resource(VertexIds::default())
+        .insert_resource(MinimapState::default())
+        .insert_resource(SearchState::default())
+        .insert_resource(CommandPaletteState::default())
+        .insert_resource(ActionRegistry::load(KEYBINDINGS_PATH))
+        .insert_resource(VertexDrag::default())
+        .insert_resource(EdgeEndDrag::default())
+        .insert_resource(Settings::load())
+        .insert_resource(SettingsWindowState::default())
+        .insert_resource(RecoveryState::start_session())
+        .insert_resource(ExportOptions::default())
+        .insert_resource(TextEditorState::default())
+        .insert_resource(AdjacencyMatrixState::default())
+        .insert_resource(TablesState::default())
+        .insert_resource(MergeDialogState::default())
         .add_observer(on_vertex_renamed)
         .add_observer(canvas_clicked)
         .add_observer(click_vertex)
         .add_observer(vertex_drag_dropped)
         .add_observer(vertex_dragging)
         .add_observer(edge_clicked)
+        .add_observer(edge_end_dropped)
         .add_observer(update_cursor_icon)
         .add_observer(on_redo_vertex_rename)
         .add_observer(on_undo_vertex_rename)
@@ -57,16 +146,114 @@ pub(super) fn plugin(app: &mut App) {
         .add_observer(on_redo_edge_deletion)
         .add_observer(on_undo_vertex_insertion)
         .add_observer(on_redo_vertex_insertion)
-        .add_systems(Startup, (spawn_canvas, spawn_temporary_edge))
-        .add_systems(EguiPrimaryContextPass, show_rename_input)
+        .add_observer(on_undo_graph_insertion)
+        .add_observer(on_redo_graph_insertion)
+        .add_observer(on_undo_graph_deletion)
+        .add_observer(on_redo_graph_deletion)
+        .add_observer(on_undo_graph_replace)
+        .add_observer(on_redo_graph_replace)
+        .add_observer(on_undo_attributes_edit)
+        .add_observer(on_redo_attributes_edit)
+        .add_observer(on_undo_edge_weight_edit)
+        .add_observer(on_redo_edge_weight_edit)
+        .add_observer(on_undo_style_edit)
+        .add_observer(on_redo_style_edit)
+        .add_observer(on_undo_batch_edit)
+        .add_observer(on_redo_batch_edit)
+        .add_observer(on_undo_edge_reconnect)
+        .add_observer(on_redo_edge_reconnect)
+        .add_observer(on_undo_vertex_merge)
+        .add_observer(on_redo_vertex_merge)
+        .add_observer(on_generate_graph)
+        .add_observer(on_copy_selection)
+        .add_observer(on_cut_selection)
+        .add_observer(on_paste)
+        .add_observer(on_duplicate_selection)
+        .add_observer(on_attributes_edited)
+        .add_observer(on_edge_weight_edited)
+        .add_observer(on_style_edited)
+        .add_observer(on_save_document)
+        .add_observer(on_open_document)
+        .add_observer(on_export_document)
+        .add_observer(on_export_metrics)
+        .add_observer(on_apply_graph_text)
+        .add_observer(on_toggle_edge)
+        .add_observer(on_delete_elements)
+        .add_observer(on_set_attribute)
+        .add_observer(on_recolor)
+        .add_observer(on_merge_vertices)
+        .add_observer(on_run_action)
+        // Picking observers are global instead of per entity, since
+        // thousands of observer entities slow down large graphs.
+        .add_observer(on_vertex_clicked)
+        .add_observer(on_vertex_hovered)
+        .add_observer(on_vertex_out)
+        .add_observer(on_vertex_dragging)
+        .add_observer(on_vertex_drop)
+        .add_observer(on_vertex_dragged)
+        .add_observer(on_edge_clicked)
+        .add_observer(on_edge_drag_start)
+        .add_observer(on_edge_dragging)
+        .add_observer(on_edge_drop)
+        .add_observer(on_edge_hovered)
+        .add_observer(on_edge_out)
+        .add_systems(Startup, (spawn_canvas, spawn_temporary_edge, spawn_edge_batch))
+        .add_systems(
+            PreUpdate,
+            pick_from_spatial_index.in_set(PickingSystems::Backend),
+        )
+        .add_systems(Last, autosave_on_exit)
+        .add_systems(
+            EguiPrimaryContextPass,
+            (
+                apply_egui_theme,
+                show_menu_bar,
+                show_statistics_panel,
+                show_rename_input,
+                show_metrics_panel,
+                show_generator_dialog,
+                show_merge_dialog,
+                show_text_editor,
+                show_adjacency_matrix,
+                show_tables,
+                show_inspector,
+                show_minimap,
+                show_search_panel,
+                show_command_palette,
+                show_settings_window,
+                show_recovery_dialog,
+            )
+                .chain(),
+        )
         .add_systems(
             Update,
             (
                 project_positions,
-                update_edge_transforms,
+                (update_edge_transforms, apply_edge_styles).chain(),
+                update_spatial_index.after(update_edge_transforms),
+                update_edge_batch.after(update_edge_transforms),
+                apply_vertex_styles,
+                update_edge_arrowheads,
+                update_edge_weight_labels,
                 update_temp_edge_transform,
+                apply_settings.run_if(resource_changed::<Settings>),
                 cursor_icon_manager,
-                undo_redo_system,
+                (update_window_title, autosave),
+                (shortcut_system, fit_canvas_to_camera).chain(),
+                draw_selection,
+                draw_edge_handles,
+                draw_matrix_highlight,
+                (update_search_matches, draw_search_matches).chain(),
+                (
+                    update_graph_snapshot,
+                    (update_graph_statistics, (update_graph_metrics, apply_heat_map).chain()),
+                )
+                    .chain(),
+                evict_unused_handles.run_if(on_timer(HANDLE_CACHE_EVICTION_INTERVAL)),
             ),
         );
+
+    if std::env::args().any(|argument| argument == BENCHMARK_ARGUMENT) {
+        app.add_plugins(benchmark::plugin);
+    }
 }
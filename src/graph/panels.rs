@@ -0,0 +1,2067 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+
+use crate::graph::{
+    actions::{ActionRegistry, EditorAction},
+    analysis::{GraphSnapshot, MatrixOrdering, Metric, reverse_cuthill_mckee},
+    components::{
+        ArrowStyle, Attribute, AttributeValue, Attributes, DirectedEdge, EdgeStyle, EdgeWeight,
+        ElementStyle, LineStyle, Position, Selected, Vertex, VertexShape, VertexStyle,
+    },
+    constants::{
+        COMMAND_PALETTE_HEIGHT, COMMAND_PALETTE_WIDTH, DEGREE_HISTOGRAM_HEIGHT, FRAME_PADDING,
+        MATRIX_CELL_SIZE, MATRIX_LABEL_SIZE, MINIMAP_MAX_EDGES, MINIMAP_SIZE, MINIMAP_VERTEX_SIZE,
+        SEARCH_RESULTS_HEIGHT, TABLE_COLUMN_WIDTH, TABLE_HEIGHT, TABLE_ROW_HEIGHT, TIKZ_EDGE_STYLE,
+        TIKZ_VERTEX_STYLE, TIKZ_WEIGHT_STYLE,
+    },
+    events::{
+        ApplyGraphTextEvent, AttributesEditedEvent, DeleteElementsEvent, DocumentSource,
+        EdgeWeightEditedEvent, ExportDocumentEvent, ExportMetricsEvent, GenerateGraphEvent,
+        MergeVerticesEvent, OpenDocumentEvent, RecolorEvent, RunActionEvent, SaveDocumentEvent,
+        SetAttributeEvent, StyleEditedEvent, ToggleEdgeEvent, VertexRenamedEvent,
+    },
+    formats::{ExportFormat, ExportOptions, ImportFormat, MermaidDirection},
+    generators::GeneratorKind,
+    helpers::{bounding_rect, metrics_path, vertex_display_name, visible_rect},
+    recovery::{RecoveryReason, RecoveryState, discard_recovery, recovery_path},
+    resources::{
+        AdjacencyMatrixState, CellEdit, CommandPaletteState, DocumentState, GeneratorDialogState,
+        GraphMetrics, HoveredEntity, InspectorState, MergeDialogState, MergeLabel,
+        MetricsPanelState, MinimapState, SearchState, SettingsWindowState, StatisticsPanelState,
+        TableColumn, TableKind, TablesState, TextEditorState, UndoRedoStack,
+    },
+    search::{MatchedField, fuzzy_score},
+    settings::{Settings, Theme, settings_path},
+    text_editor::graph_to_text,
+    undo_redo::{UndoAction, VertexMoveAction},
+};
+
+/// The top menu bar, the entry point for every panel.
+pub fn show_menu_bar(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut metrics_panel: ResMut<MetricsPanelState>,
+    mut statistics_panel: ResMut<StatisticsPanelState>,
+    mut generator_dialog: ResMut<GeneratorDialogState>,
+    mut inspector: ResMut<InspectorState>,
+    mut document_state: ResMut<DocumentState>,
+    mut minimap: ResMut<MinimapState>,
+    mut search: ResMut<SearchState>,
+    mut settings_window: ResMut<SettingsWindowState>,
+    mut export_options: ResMut<ExportOptions>,
+    mut text_editor: ResMut<TextEditorState>,
+    mut adjacency_matrix: ResMut<AdjacencyMatrixState>,
+    mut tables: ResMut<TablesState>,
+    mut merge_dialog: ResMut<MergeDialogState>,
+) {
+    let Ok(context) = contexts.ctx_mut() else {
+        return;
+    };
+
+    egui::TopBottomPanel::top("menu_bar").show(context, |ui| {
+        egui::MenuBar::new().ui(ui, |ui| {
+            ui.menu_button("File", |ui| {
+                ui.text_edit_singleline(&mut document_state.path);
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        commands.trigger(SaveDocumentEvent {
+                            path: document_state.path.clone(),
+                        });
+                    }
+                    if ui.button("Open").clicked() {
+                        commands.trigger(OpenDocumentEvent {
+                            path: document_state.path.clone(),
+                            source: DocumentSource::Document,
+                        });
+                    }
+                });
+                ui.menu_button("Import", |ui| {
+                    for format in ImportFormat::ALL {
+                        if ui.button(format.name()).clicked() {
+                            let path = std::path::Path::new(&document_state.path)
+                                .with_extension(format.extension());
+                            commands.trigger(OpenDocumentEvent {
+                                path: path.to_string_lossy().into_owned(),
+                                source: DocumentSource::Import(format),
+                            });
+                        }
+                    }
+                });
+                ui.menu_button("Export", |ui| {
+                    ui.checkbox(&mut export_options.svg_background, "SVG background");
+                    ui.label("TikZ styles");
+                    egui::Grid::new("tikz_styles").show(ui, |ui| {
+                        let options = &mut *export_options;
+                        for (label, name, default) in [
+                            (
+                                "Vertices",
+                                &mut options.tikz_vertex_style,
+                                TIKZ_VERTEX_STYLE,
+                            ),
+                            ("Edges", &mut options.tikz_edge_style, TIKZ_EDGE_STYLE),
+                            ("Weights", &mut options.tikz_weight_style, TIKZ_WEIGHT_STYLE),
+                        ] {
+                            ui.label(label);
+                            ui.add(egui::TextEdit::singleline(name).hint_text(default));
+                            ui.end_row();
+                        }
+                    });
+                    ui.checkbox(&mut export_options.tikz_define_styles, "Define TikZ styles");
+                    let tikz_error = export_options.tikz_styles_error();
+                    if let Some(error) = &tikz_error {
+                        ui.colored_label(ui.visuals().error_fg_color, error);
+                    }
+                    egui::ComboBox::from_label("Mermaid direction")
+                        .selected_text(export_options.mermaid_direction.code())
+                        .show_ui(ui, |ui| {
+                            for direction in MermaidDirection::ALL {
+                                ui.selectable_value(
+                                    &mut export_options.mermaid_direction,
+                                    direction,
+                                    direction.code(),
+                                );
+                            }
+                        });
+                    ui.separator();
+                    for format in ExportFormat::ALL {
+                        let enabled = format != ExportFormat::Tikz || tikz_error.is_none();
+                        if ui
+                            .add_enabled(enabled, egui::Button::new(format.name()))
+                            .clicked()
+                        {
+                            let path = std::path::Path::new(&document_state.path)
+                                .with_extension(format.extension());
+                            commands.trigger(ExportDocumentEvent {
+                                path: path.to_string_lossy().into_owned(),
+                                format,
+                            });
+                        }
+                    }
+                });
+                if let Some(status) = &document_state.status {
+                    ui.label(status);
+                }
+            });
+            ui.menu_button("Graph", |ui| {
+                if ui.button("Search…").clicked() {
+                    search.open = true;
+                    search.focus = true;
+                }
+                if ui.button("Generate…").clicked() {
+                    generator_dialog.open = true;
+                }
+                if ui.button("Merge vertices…").clicked() {
+                    merge_dialog.open = true;
+                }
+            });
+            ui.menu_button("View", |ui| {
+                ui.checkbox(&mut statistics_panel.open, "Statistics");
+                ui.checkbox(&mut metrics_panel.open, "Metrics");
+                ui.checkbox(&mut inspector.open, "Inspector");
+                ui.checkbox(&mut minimap.open, "Minimap");
+                ui.checkbox(&mut text_editor.open, "Text editor");
+                ui.checkbox(&mut adjacency_matrix.open, "Adjacency matrix");
+                ui.checkbox(&mut tables.open, "Tables");
+                ui.separator();
+                if ui.button("Settings…").clicked() {
+                    settings_window.open = true;
+                }
+            });
+        });
+    });
+}
+
+/// Summary of the whole graph on the right side. The distance based
+/// rows show their progress while they are still being accumulated.
+pub fn show_statistics_panel(
+    mut contexts: EguiContexts,
+    panel: Res<StatisticsPanelState>,
+    snapshot: Res<GraphSnapshot>,
+) {
+    let Ok(context) = contexts.ctx_mut() else {
+        return;
+    };
+
+    if !panel.open {
+        return;
+    }
+
+    let statistics = &panel.statistics;
+    let distances = &panel.distances;
+    let yes_no = |value: bool| if value { "yes" } else { "no" };
+
+    egui::SidePanel::right("statistics_panel")
+        .resizable(true)
+        .default_width(220.0)
+        .show(context, |ui| {
+            ui.heading("Statistics");
+            egui::Grid::new("statistics_grid")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    let mut row = |name: &str, value: String| {
+                        ui.label(name);
+                        ui.label(value);
+                        ui.end_row();
+                    };
+                    row("Vertices", statistics.vertex_count.to_string());
+                    row("Edges", statistics.edge_count.to_string());
+                    row("Density", format!("{:.4}", statistics.density));
+                    row("Weak components", statistics.weak_components.to_string());
+                    row(
+                        "Strong components",
+                        statistics.strong_components.to_string(),
+                    );
+                    row("DAG", yes_no(statistics.is_dag).to_string());
+                    row("Tree", yes_no(statistics.is_tree).to_string());
+                    row("Forest", yes_no(statistics.is_forest).to_string());
+
+                    if distances.is_complete(&snapshot) {
+                        row("Diameter", distances.diameter.to_string());
+                        row(
+                            "Radius",
+                            distances
+                                .radius
+                                .map_or("-".to_string(), |radius| radius.to_string()),
+                        );
+                        row(
+                            "Avg. path length",
+                            distances
+                                .average_path_length()
+                                .map_or("-".to_string(), |length| format!("{length:.3}")),
+                        );
+                    } else {
+                        let progress = distances.next_source * 100 / snapshot.vertex_count().max(1);
+                        row("Distances", format!("computing… {progress}%"));
+                    }
+                });
+
+            ui.separator();
+            ui.label("Degree distribution");
+            degree_histogram(ui, &statistics.degree_histogram);
+        });
+}
+
+/// Bar chart of the degree histogram drawn with the painter,
+/// the tooltip shows the exact count of the hovered bar.
+fn degree_histogram(ui: &mut egui::Ui, histogram: &[usize]) {
+    let max_count = histogram.iter().copied().max().unwrap_or(0);
+    if max_count == 0 {
+        ui.label("-");
+        return;
+    }
+
+    let size = egui::vec2(ui.available_width(), DEGREE_HISTOGRAM_HEIGHT);
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let bar_width = rect.width() / histogram.len() as f32;
+    let color = ui.visuals().selection.bg_fill;
+
+    for (degree, count) in histogram.iter().enumerate() {
+        let height = rect.height() * *count as f32 / max_count as f32;
+        let left = rect.left() + degree as f32 * bar_width;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(left, rect.bottom() - height),
+            egui::pos2(left + (bar_width - 1.0).max(1.0), rect.bottom()),
+        );
+        painter.rect_filled(bar, 0.0, color);
+    }
+
+    if let Some(pointer) = response.hover_pos() {
+        let degree = ((pointer.x - rect.left()) / bar_width) as usize;
+        if let Some(count) = histogram.get(degree) {
+            response.on_hover_text(format!("degree {degree}: {count} vertices"));
+        }
+    }
+}
+
+/// Picks a generator and its parameters. Only the
+/// parameters used by the chosen generator are shown.
+pub fn show_generator_dialog(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut dialog: ResMut<GeneratorDialogState>,
+) {
+    let Ok(context) = contexts.ctx_mut() else {
+        return;
+    };
+
+    if !dialog.open {
+        return;
+    }
+
+    let mut open = dialog.open;
+    egui::Window::new("Generate graph")
+        .open(&mut open)
+        .resizable(false)
+        .show(context, |ui| {
+            let dialog = dialog.as_mut();
+            egui::ComboBox::from_label("Kind")
+                .selected_text(dialog.kind.name())
+                .show_ui(ui, |ui| {
+                    for kind in GeneratorKind::ALL {
+                        ui.selectable_value(&mut dialog.kind, kind, kind.name());
+                    }
+                });
+
+            let parameters = &mut dialog.parameters;
+            egui::Grid::new("generator_parameters")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    let vertex_range = 1..=dialog.kind.max_vertices(parameters);
+                    match dialog.kind {
+                        GeneratorKind::Petersen => {}
+                        GeneratorKind::Grid => {
+                            ui.label("Rows");
+                            ui.add(egui::DragValue::new(&mut parameters.rows).range(1..=200));
+                            ui.end_row();
+                            ui.label("Columns");
+                            ui.add(egui::DragValue::new(&mut parameters.columns).range(1..=200));
+                            ui.end_row();
+                        }
+                        GeneratorKind::Hypercube => {
+                            ui.label("Dimension");
+                            ui.add(egui::DragValue::new(&mut parameters.dimension).range(0..=12));
+                            ui.end_row();
+                        }
+                        GeneratorKind::CompleteBipartite => {
+                            ui.label("Left part");
+                            ui.add(egui::DragValue::new(&mut parameters.n).range(1..=200));
+                            ui.end_row();
+                            ui.label("Right part");
+                            ui.add(egui::DragValue::new(&mut parameters.m).range(1..=200));
+                            ui.end_row();
+                        }
+                        kind => {
+                            ui.label("Vertices");
+                            ui.add(egui::DragValue::new(&mut parameters.n).range(vertex_range));
+                            ui.end_row();
+                            match kind {
+                                GeneratorKind::ErdosRenyi => {
+                                    ui.label("Edge probability");
+                                    ui.add(egui::Slider::new(&mut parameters.p, 0.0..=1.0));
+                                    ui.end_row();
+                                }
+                                GeneratorKind::BarabasiAlbert => {
+                                    ui.label("Edges per vertex");
+                                    ui.add(egui::DragValue::new(&mut parameters.m).range(1..=20));
+                                    ui.end_row();
+                                }
+                                GeneratorKind::WattsStrogatz => {
+                                    ui.label("Neighbours (k)");
+                                    ui.add(egui::DragValue::new(&mut parameters.k).range(2..=20));
+                                    ui.end_row();
+                                    ui.label("Rewiring (β)");
+                                    ui.add(egui::Slider::new(&mut parameters.beta, 0.0..=1.0));
+                                    ui.end_row();
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+
+                    if dialog.kind.is_random() {
+                        ui.label("Seed");
+                        ui.add(egui::DragValue::new(&mut parameters.seed));
+                        ui.end_row();
+                    }
+                });
+
+            if ui.button("Generate").clicked() {
+                commands.trigger(GenerateGraphEvent {
+                    kind: dialog.kind,
+                    parameters: dialog.parameters.clone(),
+                });
+            }
+        });
+    dialog.open = open;
+}
+
+/// Merges the selected vertices into one, named after one of them or
+/// with their labels joined. The survivor keeps its entity, so it is the
+/// chosen vertex, or the oldest one when the labels are joined.
+pub fn show_merge_dialog(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut dialog: ResMut<MergeDialogState>,
+    selected: Query<(Entity, &Vertex), With<Selected>>,
+) {
+    let Ok(context) = contexts.ctx_mut() else {
+        return;
+    };
+
+    if !dialog.open {
+        return;
+    }
+
+    let mut selected: Vec<(Entity, &str)> = selected
+        .iter()
+        .map(|(entity, vertex)| (entity, vertex.label.as_str()))
+        .collect();
+    selected.sort_by_key(|(entity, _)| entity.index());
+    if let MergeLabel::Vertex(chosen) = dialog.label
+        && !selected.iter().any(|(entity, _)| *entity == chosen)
+    {
+        dialog.label = MergeLabel::Joined;
+    }
+
+    let mut open = dialog.open;
+    let mut merged = false;
+    egui::Window::new("Merge vertices")
+        .open(&mut open)
+        .resizable(false)
+        .show(context, |ui| {
+            let dialog = dialog.as_mut();
+            ui.label(format!("{} selected vertices", selected.len()));
+            egui::ComboBox::from_label("Label")
+                .selected_text(match dialog.label {
+                    MergeLabel::Joined => "Joined".to_string(),
+                    MergeLabel::Vertex(chosen) => {
+                        let label = selected
+                            .iter()
+                            .find(|(entity, _)| *entity == chosen)
+                            .map_or("", |(_, label)| *label);
+                        vertex_display_name(label, chosen)
+                    }
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut dialog.label, MergeLabel::Joined, "Joined");
+                    for (entity, label) in &selected {
+                        ui.selectable_value(
+                            &mut dialog.label,
+                            MergeLabel::Vertex(*entity),
+                            vertex_display_name(label, *entity),
+                        );
+                    }
+                });
+            if dialog.label == MergeLabel::Joined {
+                ui.horizontal(|ui| {
+                    ui.label("Separator");
+                    ui.add(egui::TextEdit::singleline(&mut dialog.separator).desired_width(40.0));
+                });
+            }
+            ui.checkbox(&mut dialog.drop_loops, "Drop self-loops");
+            ui.checkbox(&mut dialog.drop_duplicates, "Drop duplicate edges");
+
+            let mut vertices: Vec<Entity> = selected.iter().map(|(entity, _)| *entity).collect();
+            let label = match dialog.label {
+                MergeLabel::Joined => dialog.joined_label(selected.iter().map(|(_, label)| *label)),
+                MergeLabel::Vertex(chosen) => {
+                    vertices.sort_by_key(|entity| *entity != chosen);
+                    selected
+                        .iter()
+                        .find(|(entity, _)| *entity == chosen)
+                        .map_or("", |(_, label)| *label)
+                        .to_string()
+                }
+            };
+            ui.label(format!("Merged label: {label}"));
+            if ui
+                .add_enabled(vertices.len() >= 2, egui::Button::new("Merge"))
+                .clicked()
+            {
+                commands.trigger(MergeVerticesEvent {
+                    vertices,
+                    label,
+                    drop_loops: dialog.drop_loops,
+                    drop_duplicates: dialog.drop_duplicates,
+                });
+                merged = true;
+            }
+        });
+    dialog.open = open && !merged;
+}
+
+/// Sortable table of the vertex metrics, with the heat-map
+/// settings and the CSV export below it.
+pub fn show_metrics_panel(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut panel: ResMut<MetricsPanelState>,
+    metrics: Res<GraphMetrics>,
+    snapshot: Res<GraphSnapshot>,
+    document_state: Res<DocumentState>,
+    vertices: Query<&Vertex>,
+) {
+    let Ok(context) = contexts.ctx_mut() else {
+        return;
+    };
+
+    if !panel.open {
+        return;
+    }
+
+    let rows: Vec<(String, usize)> = if metrics.revision == snapshot.revision {
+        snapshot
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(index, entity)| {
+                let label = vertices
+                    .get(*entity)
+                    .map(|vertex| vertex.label.as_str())
+                    .unwrap_or_default();
+                (vertex_display_name(label, *entity), index)
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut open = panel.open;
+    egui::Window::new("Metrics")
+        .open(&mut open)
+        .default_width(560.0)
+        .show(context, |ui| {
+            let mut sorted = rows.clone();
+            match panel.sort_by {
+                None => sorted.sort_by(|a, b| a.0.cmp(&b.0)),
+                Some(metric) => sorted.sort_by(|a, b| {
+                    metric
+                        .value(&metrics.values[a.1])
+                        .total_cmp(&metric.value(&metrics.values[b.1]))
+                }),
+            }
+            if panel.descending {
+                sorted.reverse();
+            }
+
+            egui::ScrollArea::vertical()
+                .max_height(320.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("metrics_grid")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            sort_header(ui, &mut panel, None, "Label");
+                            for metric in Metric::ALL {
+                                sort_header(ui, &mut panel, Some(metric), metric.name());
+                            }
+                            ui.end_row();
+
+                            for (label, index) in &sorted {
+                                ui.label(label);
+                                for metric in Metric::ALL {
+                                    ui.label(metric.format(&metrics.values[*index]));
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Map onto canvas:");
+                egui::ComboBox::from_id_salt("heat_map_metric")
+                    .selected_text(panel.heat_map.map_or("Nothing", |metric| metric.name()))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut panel.heat_map, None, "Nothing");
+                        for metric in Metric::ALL {
+                            ui.selectable_value(&mut panel.heat_map, Some(metric), metric.name());
+                        }
+                    });
+                ui.checkbox(&mut panel.heat_map_color, "Colour");
+                ui.checkbox(&mut panel.heat_map_radius, "Radius");
+            });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Export CSV").clicked() {
+                    commands.trigger(ExportMetricsEvent);
+                }
+                ui.label(metrics_path(&document_state.path));
+            });
+            if let Some(status) = &panel.export_status {
+                ui.label(status);
+            }
+        });
+    panel.open = open;
+}
+
+/// Clicking a header sorts by that column,
+/// clicking it again flips the direction.
+fn sort_header(
+    ui: &mut egui::Ui,
+    panel: &mut MetricsPanelState,
+    column: Option<Metric>,
+    name: &str,
+) {
+    let text = if panel.sort_by == column {
+        format!("{name} {}", if panel.descending { "⏷" } else { "⏶" })
+    } else {
+        name.to_string()
+    };
+
+    if ui.button(text).clicked() {
+        if panel.sort_by == column {
+            panel.descending = !panel.descending;
+        } else {
+            panel.sort_by = column;
+            panel.descending = column.is_some();
+        }
+    }
+}
+
+/// What the inspector shows of a vertex or an edge.
+struct InspectedData {
+    label: String,
+    weight: Option<f32>,
+    attributes: Vec<Attribute>,
+    style: ElementStyle,
+}
+
+/// Shows the single selected vertex, otherwise the last hovered vertex or edge.
+/// Changes are applied once nothing in the UI is being edited anymore, so
+/// typing a word or dragging a value is a single undo step.
+pub fn show_inspector(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut inspector: ResMut<InspectorState>,
+    hovered_entity: Res<HoveredEntity>,
+    selected: Query<Entity, With<Selected>>,
+    vertices: Query<(&Vertex, Option<&Attributes>, Option<&VertexStyle>)>,
+    edges: Query<(
+        &DirectedEdge,
+        Option<&EdgeWeight>,
+        Option<&Attributes>,
+        Option<&EdgeStyle>,
+    )>,
+    settings: Res<Settings>,
+) {
+    let Ok(context) = contexts.ctx_mut() else {
+        return;
+    };
+
+    if !inspector.open {
+        return;
+    }
+
+    let single_selected = match (selected.iter().next(), selected.iter().nth(1)) {
+        (Some(entity), None) => Some(entity),
+        _ => None,
+    };
+    if !inspector.editing
+        && let Some(candidate) = single_selected.or(hovered_entity.0)
+    {
+        inspector.target = Some(candidate);
+    }
+
+    let vertex_name = |entity: Entity| {
+        let label = vertices
+            .get(entity)
+            .map(|(vertex, ..)| vertex.label.as_str())
+            .unwrap_or_default();
+        vertex_display_name(label, entity)
+    };
+    let attributes_of = |attributes: Option<&Attributes>| {
+        attributes
+            .map(|attributes| attributes.0.clone())
+            .unwrap_or_default()
+    };
+
+    let mut edge_ends = None;
+    let current = inspector.target.and_then(|target| {
+        if let Ok((vertex, attributes, style)) = vertices.get(target) {
+            Some(InspectedData {
+                label: vertex.label.clone(),
+                weight: None,
+                attributes: attributes_of(attributes),
+                style: ElementStyle::Vertex(
+                    style
+                        .cloned()
+                        .unwrap_or_else(|| VertexStyle::themed(&settings)),
+                ),
+            })
+        } else if let Ok((edge, weight, attributes, style)) = edges.get(target) {
+            edge_ends = Some((vertex_name(edge.from), vertex_name(edge.to)));
+            Some(InspectedData {
+                label: String::new(),
+                weight: weight.map(|weight| weight.0),
+                attributes: attributes_of(attributes),
+                style: ElementStyle::Edge(
+                    style
+                        .cloned()
+                        .unwrap_or_else(|| EdgeStyle::themed(&settings)),
+                ),
+            })
+        } else {
+            None
+        }
+    });
+
+    let inspector = inspector.as_mut();
+    if !inspector.editing
+        && let Some(current) = &current
+    {
+        inspector.label = current.label.clone();
+        inspector.weight = current.weight;
+        inspector.attributes = current.attributes.clone();
+        inspector.style = current.style.clone();
+    }
+
+    let mut open = inspector.open;
+    egui::Window::new("Inspector")
+        .open(&mut open)
+        .default_pos(egui::pos2(12.0, 48.0))
+        .default_width(280.0)
+        .show(context, |ui| {
+            let (Some(target), Some(_)) = (inspector.target, &current) else {
+                ui.label("Hover or select a vertex or an edge.");
+                return;
+            };
+
+            match &edge_ends {
+                None => {
+                    ui.label(format!("Vertex {target}"));
+                    ui.horizontal(|ui| {
+                        ui.label("Label");
+                        ui.text_edit_singleline(&mut inspector.label);
+                    });
+                }
+                Some((from, to)) => {
+                    ui.label(format!("Edge {from} → {to}"));
+                    ui.horizontal(|ui| {
+                        let mut weighted = inspector.weight.is_some();
+                        if ui.checkbox(&mut weighted, "Weight").changed() {
+                            inspector.weight = weighted.then_some(1.0);
+                        }
+                        if let Some(weight) = &mut inspector.weight {
+                            ui.add(egui::DragValue::new(weight).speed(0.1));
+                        }
+                    });
+                }
+            }
+
+            ui.separator();
+            egui::CollapsingHeader::new("Style")
+                .default_open(true)
+                .show(ui, |ui| match &mut inspector.style {
+                    ElementStyle::Vertex(style) => vertex_style_editor(ui, style),
+                    ElementStyle::Edge(style) => edge_style_editor(ui, style),
+                });
+
+            ui.separator();
+            ui.label("Attributes");
+            let mut removed = None;
+            egui::Grid::new("inspector_attributes")
+                .num_columns(4)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (index, attribute) in inspector.attributes.iter_mut().enumerate() {
+                        ui.add(egui::TextEdit::singleline(&mut attribute.key).desired_width(80.0));
+                        egui::ComboBox::from_id_salt(("attribute_type", index))
+                            .selected_text(attribute.value.type_name())
+                            .show_ui(ui, |ui| {
+                                for default in AttributeValue::defaults() {
+                                    let same_type = std::mem::discriminant(&default)
+                                        == std::mem::discriminant(&attribute.value);
+                                    if ui
+                                        .selectable_label(same_type, default.type_name())
+                                        .clicked()
+                                        && !same_type
+                                    {
+                                        attribute.value = default;
+                                    }
+                                }
+                            });
+                        attribute_value_editor(ui, &mut attribute.value);
+                        if ui.button("🗑").on_hover_text("Remove").clicked() {
+                            removed = Some(index);
+                        }
+                        ui.end_row();
+                    }
+                });
+            if let Some(index) = removed {
+                inspector.attributes.remove(index);
+            }
+            if ui.button("Add attribute").clicked() {
+                inspector.attributes.push(Attribute {
+                    key: format!("key{}", inspector.attributes.len() + 1),
+                    value: AttributeValue::String(String::new()),
+                });
+            }
+        });
+    inspector.open = open;
+
+    inspector.editing =
+        context.memory(|memory| memory.focused().is_some()) || context.is_using_pointer();
+    if inspector.editing {
+        return;
+    }
+    let (Some(target), Some(current)) = (inspector.target, current) else {
+        return;
+    };
+
+    if edge_ends.is_none() && inspector.label != current.label {
+        commands.trigger(VertexRenamedEvent {
+            entity: target,
+            new_label: inspector.label.clone(),
+            manual: true,
+        });
+    }
+    if inspector.weight != current.weight {
+        commands.trigger(EdgeWeightEditedEvent {
+            entity: target,
+            weight: inspector.weight,
+            manual: true,
+        });
+    }
+    if inspector.attributes != current.attributes {
+        commands.trigger(AttributesEditedEvent {
+            entity: target,
+            attributes: inspector.attributes.clone(),
+            manual: true,
+        });
+    }
+    if inspector.style != current.style {
+        commands.trigger(StyleEditedEvent {
+            entity: target,
+            style: inspector.style.clone(),
+            manual: true,
+        });
+    }
+}
+
+/// The whole graph in the bottom right corner with the visible area
+/// outlined. Pressing or dragging on it moves the camera there.
+pub fn show_minimap(
+    mut contexts: EguiContexts,
+    minimap: Res<MinimapState>,
+    window: Single<&Window>,
+    camera: Single<(&mut Transform, &Projection), With<Camera2d>>,
+    vertices: Query<&Position, With<Vertex>>,
+    edges: Query<&DirectedEdge>,
+    settings: Res<Settings>,
+) {
+    let Ok(context) = contexts.ctx_mut() else {
+        return;
+    };
+
+    if !minimap.open {
+        return;
+    }
+
+    let Some(bounds) = bounding_rect(vertices.iter().map(|position| position.0)) else {
+        return;
+    };
+    let bounds = bounds.inflate(FRAME_PADDING);
+    let scale = (MINIMAP_SIZE / bounds.size()).min_element();
+    let (mut camera_transform, projection) = camera.into_inner();
+    let visible = visible_rect(&camera_transform, projection, window.size());
+    let palette = settings.palette();
+
+    egui::Area::new(egui::Id::new("minimap"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -12.0))
+        .show(context, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                let size = egui::Vec2::splat(MINIMAP_SIZE);
+                let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+                let painter = ui.painter_at(rect);
+                let to_minimap = |point: Vec2| {
+                    let offset = (point - bounds.center()) * scale;
+                    rect.center() + egui::vec2(offset.x, -offset.y)
+                };
+
+                painter.rect_filled(rect, 0.0, egui_color(palette.background));
+                if edges.iter().count() <= MINIMAP_MAX_EDGES {
+                    let stroke = egui::Stroke::new(1.0, egui_color(palette.edge));
+                    for edge in &edges {
+                        if let Ok(from) = vertices.get(edge.from)
+                            && let Ok(to) = vertices.get(edge.to)
+                        {
+                            painter.line_segment([to_minimap(from.0), to_minimap(to.0)], stroke);
+                        }
+                    }
+                }
+                let vertex_size = egui::Vec2::splat(MINIMAP_VERTEX_SIZE);
+                for position in &vertices {
+                    let vertex = egui::Rect::from_center_size(to_minimap(position.0), vertex_size);
+                    painter.rect_filled(vertex, 0.0, egui_color(palette.vertex));
+                }
+                painter.rect_stroke(
+                    egui::Rect::from_two_pos(to_minimap(visible.min), to_minimap(visible.max)),
+                    0.0,
+                    egui::Stroke::new(1.0, egui_color(palette.minimap_viewport)),
+                    egui::StrokeKind::Middle,
+                );
+
+                if response.is_pointer_button_down_on()
+                    && let Some(pointer) = response.interact_pointer_pos()
+                {
+                    let offset = (pointer - rect.center()) / scale;
+                    let target = bounds.center() + Vec2::new(offset.x, -offset.y);
+                    camera_transform.translation.x = target.x;
+                    camera_transform.translation.y = target.y;
+                }
+            });
+        });
+}
+
+/// The graph as DOT-like text on the left. The text follows the graph
+/// until it is edited, Apply replaces the graph with what it describes.
+pub fn show_text_editor(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut editor: ResMut<TextEditorState>,
+    undo_redo: Res<UndoRedoStack>,
+    vertices: Query<(Entity, &Vertex)>,
+    edges: Query<(&DirectedEdge, Option<&EdgeWeight>)>,
+) {
+    let Ok(context) = contexts.ctx_mut() else {
+        return;
+    };
+
+    if !editor.open {
+        return;
+    }
+
+    let editor = editor.as_mut();
+    let state = undo_redo.state();
+    let outdated = editor.synced != Some(state);
+    if outdated && !editor.is_edited() {
+        // Edges of deleted vertices stay around hidden, they are left out.
+        editor.generated = graph_to_text(
+            vertices
+                .iter()
+                .map(|(entity, vertex)| (entity, vertex.label.as_str())),
+            edges
+                .iter()
+                .filter(|(edge, _)| vertices.contains(edge.from) && vertices.contains(edge.to))
+                .map(|(edge, weight)| (edge.from, edge.to, weight.map(|weight| weight.0))),
+        );
+        editor.text = editor.generated.clone();
+        editor.synced = Some(state);
+    }
+
+    egui::SidePanel::left("text_editor")
+        .resizable(true)
+        .default_width(280.0)
+        .show(context, |ui| {
+            ui.heading("Text");
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(editor.is_edited(), egui::Button::new("Apply"))
+                    .clicked()
+                {
+                    commands.trigger(ApplyGraphTextEvent {
+                        text: editor.text.clone(),
+                    });
+                }
+                if ui
+                    .add_enabled(editor.is_edited(), egui::Button::new("Revert"))
+                    .clicked()
+                {
+                    editor.reload();
+                    editor.error = None;
+                }
+            });
+            if let Some(error) = &editor.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            } else if outdated && editor.is_edited() {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    "The graph changed since editing started, applying overwrites it",
+                );
+            }
+            ui.separator();
+            egui::ScrollArea::both().show(ui, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut editor.text)
+                        .code_editor()
+                        .desired_width(f32::INFINITY),
+                );
+            });
+        });
+}
+
+/// The adjacency matrix at the bottom, a row per source and a column per
+/// target vertex. Clicking a cell toggles its edge, hovering it highlights
+/// the edge on the canvas. Only the visible cells are drawn.
+pub fn show_adjacency_matrix(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut matrix: ResMut<AdjacencyMatrixState>,
+    snapshot: Res<GraphSnapshot>,
+    vertices: Query<&Vertex>,
+    edges: Query<(&DirectedEdge, Option<&EdgeWeight>)>,
+) {
+    let Ok(context) = contexts.ctx_mut() else {
+        return;
+    };
+
+    if !matrix.open {
+        if matrix.hovered.is_some() {
+            matrix.hovered = None;
+        }
+        return;
+    }
+
+    let matrix = matrix.as_mut();
+    let n = snapshot.vertex_count();
+    let names: Vec<String> = snapshot
+        .vertices
+        .iter()
+        .map(|entity| {
+            let label = vertices
+                .get(*entity)
+                .map_or("", |vertex| vertex.label.as_str());
+            vertex_display_name(label, *entity)
+        })
+        .collect();
+    let order: Vec<usize> = match matrix.ordering {
+        MatrixOrdering::Label => {
+            let mut order: Vec<usize> = (0..n).collect();
+            order.sort_by(|a, b| names[*a].cmp(&names[*b]));
+            order
+        }
+        MatrixOrdering::Degree => {
+            let mut order: Vec<usize> = (0..n).collect();
+            order.sort_by_key(|vertex| {
+                std::cmp::Reverse(
+                    snapshot.out_adjacency[*vertex].len() + snapshot.in_adjacency[*vertex].len(),
+                )
+            });
+            order
+        }
+        MatrixOrdering::ReverseCuthillMcKee => reverse_cuthill_mckee(&snapshot),
+    };
+    // Of parallel edges only the first shows its weight.
+    let mut cells: HashMap<(usize, usize), Option<f32>> = HashMap::new();
+    for (edge, weight) in &edges {
+        if let (Some(&from), Some(&to)) =
+            (snapshot.index.get(&edge.from), snapshot.index.get(&edge.to))
+        {
+            cells
+                .entry((from, to))
+                .or_insert(weight.map(|weight| weight.0));
+        }
+    }
+
+    let mut hovered = None;
+    let mut clicked = None;
+    egui::TopBottomPanel::bottom("adjacency_matrix")
+        .resizable(true)
+        .default_height(260.0)
+        .show(context, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading("Adjacency matrix");
+                egui::ComboBox::from_id_salt("matrix_ordering")
+                    .selected_text(matrix.ordering.name())
+                    .show_ui(ui, |ui| {
+                        for ordering in MatrixOrdering::ALL {
+                            ui.selectable_value(&mut matrix.ordering, ordering, ordering.name());
+                        }
+                    });
+            });
+            if n == 0 {
+                ui.label("The graph is empty");
+                return;
+            }
+
+            egui::ScrollArea::both().auto_shrink(false).show(ui, |ui| {
+                let size = egui::Vec2::splat(MATRIX_LABEL_SIZE + n as f32 * MATRIX_CELL_SIZE);
+                let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+                let painter = ui.painter_at(rect);
+                let origin = rect.min + egui::Vec2::splat(MATRIX_LABEL_SIZE);
+                let visible = ui.clip_rect();
+                let visible_range = |min: f32, max: f32, start: f32| {
+                    let first = ((min - start) / MATRIX_CELL_SIZE).floor().max(0.0) as usize;
+                    let last = ((max - start) / MATRIX_CELL_SIZE).ceil().max(0.0) as usize;
+                    first.min(n)..last.min(n)
+                };
+                let rows = visible_range(visible.top(), visible.bottom(), origin.y);
+                let columns = visible_range(visible.left(), visible.right(), origin.x);
+                let cell_rect = |row: usize, column: usize| {
+                    egui::Rect::from_min_size(
+                        origin + egui::vec2(column as f32, row as f32) * MATRIX_CELL_SIZE,
+                        egui::Vec2::splat(MATRIX_CELL_SIZE),
+                    )
+                };
+                let pointer_cell = response.hover_pos().and_then(|pointer| {
+                    let offset = (pointer - origin) / MATRIX_CELL_SIZE;
+                    let cell = (offset.y.floor() as isize, offset.x.floor() as isize);
+                    (cell.0 >= 0 && cell.1 >= 0 && (cell.0 as usize) < n && (cell.1 as usize) < n)
+                        .then_some((cell.0 as usize, cell.1 as usize))
+                });
+
+                let visuals = ui.visuals();
+                let font = egui::FontId::proportional(12.0);
+                let grid = visuals.widgets.noninteractive.bg_stroke;
+                if let Some((row, column)) = pointer_cell {
+                    let row_rect = cell_rect(row, 0).union(cell_rect(row, n - 1));
+                    let column_rect = cell_rect(0, column).union(cell_rect(n - 1, column));
+                    painter.rect_filled(row_rect, 0.0, visuals.faint_bg_color);
+                    painter.rect_filled(column_rect, 0.0, visuals.faint_bg_color);
+                }
+
+                let row_labels = painter.with_clip_rect(egui::Rect::from_x_y_ranges(
+                    rect.left()..=origin.x,
+                    origin.y..=rect.bottom(),
+                ));
+                for row in rows.clone() {
+                    row_labels.text(
+                        egui::pos2(origin.x - 4.0, cell_rect(row, 0).center().y),
+                        egui::Align2::RIGHT_CENTER,
+                        &names[order[row]],
+                        font.clone(),
+                        visuals.text_color(),
+                    );
+                }
+                let column_labels = painter.with_clip_rect(egui::Rect::from_x_y_ranges(
+                    origin.x..=rect.right(),
+                    rect.top()..=origin.y,
+                ));
+                for column in columns.clone() {
+                    let galley = column_labels.layout_no_wrap(
+                        names[order[column]].clone(),
+                        font.clone(),
+                        visuals.text_color(),
+                    );
+                    // Rotated a quarter turn, so the text runs upwards from the matrix.
+                    let position = egui::pos2(
+                        cell_rect(0, column).center().x - galley.size().y / 2.0,
+                        origin.y - 4.0,
+                    );
+                    column_labels.add(
+                        egui::epaint::TextShape::new(position, galley, visuals.text_color())
+                            .with_angle(-std::f32::consts::FRAC_PI_2),
+                    );
+                }
+
+                for row in rows {
+                    for column in columns.clone() {
+                        let cell = cell_rect(row, column);
+                        painter.rect_stroke(cell, 0.0, grid, egui::StrokeKind::Inside);
+                        let Some(weight) = cells.get(&(order[row], order[column])) else {
+                            continue;
+                        };
+                        painter.rect_filled(cell.shrink(2.0), 2.0, visuals.selection.bg_fill);
+                        if let Some(weight) = weight {
+                            painter.text(
+                                cell.center(),
+                                egui::Align2::CENTER_CENTER,
+                                weight.to_string(),
+                                egui::FontId::proportional(9.0),
+                                visuals.selection.stroke.color,
+                            );
+                        }
+                    }
+                }
+
+                if let Some((row, column)) = pointer_cell {
+                    let (from, to) = (order[row], order[column]);
+                    hovered = Some((snapshot.vertices[from], snapshot.vertices[to]));
+                    if response.clicked() {
+                        clicked = hovered;
+                    }
+                    let edge = match cells.get(&(from, to)) {
+                        None => "no edge, click to add one".to_string(),
+                        Some(None) => "click to delete".to_string(),
+                        Some(Some(weight)) => format!("weight {weight}, click to delete"),
+                    };
+                    response.on_hover_text(format!("{} → {}: {edge}", names[from], names[to]));
+                }
+            });
+        });
+
+    if matrix.hovered != hovered {
+        matrix.hovered = hovered;
+    }
+    if let Some((from, to)) = clicked {
+        commands.trigger(ToggleEdgeEvent { from, to });
+    }
+}
+
+/// A row of the vertex or edge table, with the text of each column
+/// and the value numeric columns sort by.
+struct TableRow {
+    entity: Entity,
+    selected: bool,
+    cells: Vec<String>,
+    numbers: Vec<Option<f32>>,
+}
+
+/// Sortable, filterable tables of the vertices and the edges. Double
+/// clicking a label, position or weight edits it, and the selected rows
+/// can be deleted, recoloured or given an attribute at once.
+pub fn show_tables(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut tables: ResMut<TablesState>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    vertices: Query<(
+        Entity,
+        &Vertex,
+        &Position,
+        Option<&Attributes>,
+        Has<Selected>,
+    )>,
+    edges: Query<(
+        Entity,
+        &DirectedEdge,
+        Option<&EdgeWeight>,
+        Option<&Attributes>,
+    )>,
+) {
+    let Ok(context) = contexts.ctx_mut() else {
+        return;
+    };
+
+    if !tables.open {
+        return;
+    }
+
+    let tables = tables.as_mut();
+    // Edges of deleted vertices stay around hidden, they are left out.
+    let live_edges: Vec<_> = edges
+        .iter()
+        .filter(|(_, edge, ..)| vertices.contains(edge.from) && vertices.contains(edge.to))
+        .collect();
+    tables
+        .selected_edges
+        .retain(|entity| live_edges.iter().any(|(edge, ..)| edge == entity));
+
+    let columns = TableColumn::columns(tables.kind);
+    let mut rows: Vec<TableRow> = match tables.kind {
+        TableKind::Vertices => {
+            let mut degrees: HashMap<Entity, usize> = HashMap::new();
+            for (_, edge, ..) in &live_edges {
+                *degrees.entry(edge.from).or_default() += 1;
+                *degrees.entry(edge.to).or_default() += 1;
+            }
+            vertices
+                .iter()
+                .map(|(entity, vertex, position, attributes, selected)| {
+                    let degree = degrees.get(&entity).copied().unwrap_or_default();
+                    let values = [
+                        (vertex_display_name(&vertex.label, entity), None),
+                        (format!("{:.1}", position.0.x), Some(position.0.x)),
+                        (format!("{:.1}", position.0.y), Some(position.0.y)),
+                        (degree.to_string(), Some(degree as f32)),
+                        (attributes_summary(attributes), None),
+                    ];
+                    TableRow {
+                        entity,
+                        selected,
+                        cells: values.iter().map(|(cell, _)| cell.clone()).collect(),
+                        numbers: values.iter().map(|(_, number)| *number).collect(),
+                    }
+                })
+                .collect()
+        }
+        TableKind::Edges => live_edges
+            .iter()
+            .map(|(entity, edge, weight, attributes)| {
+                let name = |vertex: Entity| {
+                    let label = vertices
+                        .get(vertex)
+                        .map_or("", |(_, vertex, ..)| vertex.label.as_str());
+                    vertex_display_name(label, vertex)
+                };
+                let weight = weight.map(|weight| weight.0);
+                let values = [
+                    (name(edge.from), None),
+                    (name(edge.to), None),
+                    (
+                        weight.map(|weight| weight.to_string()).unwrap_or_default(),
+                        weight,
+                    ),
+                    (attributes_summary(*attributes), None),
+                ];
+                TableRow {
+                    entity: *entity,
+                    selected: tables.selected_edges.contains(entity),
+                    cells: values.iter().map(|(cell, _)| cell.clone()).collect(),
+                    numbers: values.iter().map(|(_, number)| *number).collect(),
+                }
+            })
+            .collect(),
+    };
+
+    let filter = tables.filter.to_lowercase();
+    if !filter.is_empty() {
+        rows.retain(|row| {
+            row.cells
+                .iter()
+                .any(|cell| cell.to_lowercase().contains(&filter))
+        });
+    }
+    let sort_index = columns
+        .iter()
+        .position(|column| *column == tables.sort_by)
+        .unwrap_or_default();
+    rows.sort_by(
+        |a, b| match (a.numbers[sort_index], b.numbers[sort_index]) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            _ => a.cells[sort_index].cmp(&b.cells[sort_index]),
+        },
+    );
+    if tables.descending {
+        rows.reverse();
+    }
+    let selection: Vec<Entity> = match tables.kind {
+        TableKind::Vertices => vertices
+            .iter()
+            .filter(|(.., selected)| *selected)
+            .map(|(entity, ..)| entity)
+            .collect(),
+        TableKind::Edges => tables.selected_edges.iter().copied().collect(),
+    };
+
+    let mut clicked = None;
+    let mut committed = None;
+    let mut open = tables.open;
+    egui::Window::new("Tables")
+        .open(&mut open)
+        .default_width(520.0)
+        .show(context, |ui| {
+            ui.horizontal(|ui| {
+                for (kind, name) in [
+                    (TableKind::Vertices, "Vertices"),
+                    (TableKind::Edges, "Edges"),
+                ] {
+                    if ui.selectable_value(&mut tables.kind, kind, name).changed() {
+                        tables.sort_by = TableColumn::columns(kind)[0];
+                        tables.descending = false;
+                        tables.anchor = None;
+                        tables.editing = None;
+                    }
+                }
+                ui.separator();
+                ui.label("Filter");
+                ui.text_edit_singleline(&mut tables.filter);
+            });
+
+            let grid = |id: &str| {
+                egui::Grid::new(id)
+                    .num_columns(columns.len())
+                    .min_col_width(TABLE_COLUMN_WIDTH)
+                    .max_col_width(TABLE_COLUMN_WIDTH)
+                    .min_row_height(TABLE_ROW_HEIGHT)
+            };
+            grid("table_header").show(ui, |ui| {
+                for column in columns {
+                    table_sort_header(ui, tables, *column);
+                }
+            });
+
+            egui::ScrollArea::vertical()
+                .max_height(TABLE_HEIGHT)
+                .show_rows(ui, TABLE_ROW_HEIGHT, rows.len(), |ui, visible| {
+                    ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Truncate);
+                    grid("table_grid")
+                        .striped(true)
+                        .start_row(visible.start)
+                        .show(ui, |ui| {
+                            for (index, row) in visible.clone().zip(&rows[visible]) {
+                                for (cell, column) in row.cells.iter().zip(columns) {
+                                    let edit = tables.editing.as_mut().filter(|edit| {
+                                        edit.entity == row.entity && edit.column == *column
+                                    });
+                                    if let Some(edit) = edit {
+                                        let response = ui.add(
+                                            egui::TextEdit::singleline(&mut edit.text)
+                                                .desired_width(TABLE_COLUMN_WIDTH),
+                                        );
+                                        if response.lost_focus() {
+                                            if !ui
+                                                .input(|input| input.key_pressed(egui::Key::Escape))
+                                            {
+                                                committed = tables.editing.take();
+                                            } else {
+                                                tables.editing = None;
+                                            }
+                                        } else if !response.has_focus() {
+                                            response.request_focus();
+                                        }
+                                        continue;
+                                    }
+
+                                    let response = ui.selectable_label(row.selected, cell);
+                                    if response.double_clicked() && column.is_editable() {
+                                        let text = match column {
+                                            TableColumn::Label => vertices
+                                                .get(row.entity)
+                                                .map(|(_, vertex, ..)| vertex.label.clone())
+                                                .unwrap_or_default(),
+                                            _ => cell.clone(),
+                                        };
+                                        tables.editing = Some(CellEdit {
+                                            entity: row.entity,
+                                            column: *column,
+                                            text,
+                                        });
+                                        tables.error = None;
+                                    } else if response.clicked() {
+                                        clicked = Some(index);
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            if let Some(error) = &tables.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label(format!("{} of {} selected", selection.len(), rows.len()));
+                let any = !selection.is_empty();
+                if ui.add_enabled(any, egui::Button::new("Delete")).clicked() {
+                    commands.trigger(DeleteElementsEvent {
+                        entities: selection.clone(),
+                    });
+                }
+                ui.color_edit_button_rgba_unmultiplied(&mut tables.color);
+                if ui.add_enabled(any, egui::Button::new("Recolour")).clicked() {
+                    commands.trigger(RecolorEvent {
+                        entities: selection.clone(),
+                        color: tables.color,
+                    });
+                }
+            });
+            ui.horizontal(|ui| {
+                let attribute = &mut tables.attribute;
+                ui.add(
+                    egui::TextEdit::singleline(&mut attribute.key)
+                        .hint_text("key")
+                        .desired_width(80.0),
+                );
+                egui::ComboBox::from_id_salt("table_attribute_type")
+                    .selected_text(attribute.value.type_name())
+                    .show_ui(ui, |ui| {
+                        for default in AttributeValue::defaults() {
+                            let same_type = std::mem::discriminant(&default)
+                                == std::mem::discriminant(&attribute.value);
+                            if ui
+                                .selectable_label(same_type, default.type_name())
+                                .clicked()
+                                && !same_type
+                            {
+                                attribute.value = default;
+                            }
+                        }
+                    });
+                attribute_value_editor(ui, &mut attribute.value);
+                let enabled = !selection.is_empty() && !attribute.key.is_empty();
+                if ui
+                    .add_enabled(enabled, egui::Button::new("Set attribute"))
+                    .clicked()
+                {
+                    commands.trigger(SetAttributeEvent {
+                        entities: selection.clone(),
+                        attribute: attribute.clone(),
+                    });
+                }
+            });
+        });
+    tables.open = open;
+
+    if let Some(index) = clicked {
+        let modifiers = context.input(|input| input.modifiers);
+        let entity = rows[index].entity;
+        let mut selected: HashSet<Entity> = if modifiers.command || modifiers.shift {
+            selection.iter().copied().collect()
+        } else {
+            HashSet::new()
+        };
+        let anchor = tables
+            .anchor
+            .and_then(|anchor| rows.iter().position(|row| row.entity == anchor));
+        match anchor {
+            Some(anchor) if modifiers.shift => {
+                let range = anchor.min(index)..=anchor.max(index);
+                selected.extend(rows[range].iter().map(|row| row.entity));
+            }
+            _ => {
+                if !selected.remove(&entity) {
+                    selected.insert(entity);
+                }
+                tables.anchor = Some(entity);
+            }
+        }
+
+        match tables.kind {
+            TableKind::Vertices => {
+                for (vertex, .., was_selected) in &vertices {
+                    match (was_selected, selected.contains(&vertex)) {
+                        (false, true) => {
+                            commands.entity(vertex).insert(Selected);
+                        }
+                        (true, false) => {
+                            commands.entity(vertex).remove::<Selected>();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            TableKind::Edges => tables.selected_edges = selected,
+        }
+    }
+
+    if let Some(edit) = committed {
+        tables.error =
+            apply_cell_edit(&mut commands, &mut undo_redo, &vertices, &edges, edit).err();
+    }
+}
+
+/// `key=value` for every attribute, separated by commas.
+fn attributes_summary(attributes: Option<&Attributes>) -> String {
+    attributes
+        .map(|attributes| {
+            attributes
+                .0
+                .iter()
+                .map(|attribute| format!("{}={}", attribute.key, attribute.value))
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .unwrap_or_default()
+}
+
+/// Like `sort_header` of the metrics panel, for the columns of the tables.
+fn table_sort_header(ui: &mut egui::Ui, tables: &mut TablesState, column: TableColumn) {
+    let text = if tables.sort_by == column {
+        format!(
+            "{} {}",
+            column.name(),
+            if tables.descending { "⏷" } else { "⏶" }
+        )
+    } else {
+        column.name().to_string()
+    };
+
+    if ui.button(text).clicked() {
+        if tables.sort_by == column {
+            tables.descending = !tables.descending;
+        } else {
+            tables.sort_by = column;
+            tables.descending = false;
+        }
+    }
+}
+
+/// Renames, moves or reweights the element of the edited cell,
+/// each as an undo step of its own.
+fn apply_cell_edit(
+    commands: &mut Commands,
+    undo_redo: &mut UndoRedoStack,
+    vertices: &Query<(
+        Entity,
+        &Vertex,
+        &Position,
+        Option<&Attributes>,
+        Has<Selected>,
+    )>,
+    edges: &Query<(
+        Entity,
+        &DirectedEdge,
+        Option<&EdgeWeight>,
+        Option<&Attributes>,
+    )>,
+    edit: CellEdit,
+) -> Result<(), String> {
+    let text = edit.text.trim();
+    let number = || {
+        text.parse::<f32>()
+            .map_err(|_| format!("'{text}' is not a number"))
+    };
+    match edit.column {
+        TableColumn::Label => {
+            if let Ok((_, vertex, ..)) = vertices.get(edit.entity)
+                && vertex.label != edit.text
+            {
+                commands.trigger(VertexRenamedEvent {
+                    entity: edit.entity,
+                    new_label: edit.text.clone(),
+                    manual: true,
+                });
+            }
+        }
+        TableColumn::X | TableColumn::Y => {
+            let Ok((_, _, position, ..)) = vertices.get(edit.entity) else {
+                return Ok(());
+            };
+            let mut moved = position.0;
+            if edit.column == TableColumn::X {
+                moved.x = number()?;
+            } else {
+                moved.y = number()?;
+            }
+            if moved != position.0 {
+                commands.entity(edit.entity).insert(Position(moved));
+                undo_redo.push_undo(
+                    UndoAction::UndoVertexMoveAction(VertexMoveAction {
+                        entity: edit.entity,
+                        position: position.0,
+                    }),
+                    commands,
+                );
+            }
+        }
+        TableColumn::Weight => {
+            let Ok((_, _, weight, _)) = edges.get(edit.entity) else {
+                return Ok(());
+            };
+            let weight_value = if text.is_empty() {
+                None
+            } else {
+                Some(number()?)
+            };
+            if weight.map(|weight| weight.0) != weight_value {
+                commands.trigger(EdgeWeightEditedEvent {
+                    entity: edit.entity,
+                    weight: weight_value,
+                    manual: true,
+                });
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Ctrl + F search over labels and attribute values. Clicking a result or
+/// pressing Enter centres the camera on it, Enter again moves to the next one.
+pub fn show_search_panel(
+    mut contexts: EguiContexts,
+    mut search: ResMut<SearchState>,
+    mut camera: Single<&mut Transform, With<Camera2d>>,
+    positions: Query<&Position>,
+) {
+    let Ok(context) = contexts.ctx_mut() else {
+        return;
+    };
+
+    if !search.open {
+        return;
+    }
+
+    let search = search.as_mut();
+    let mut open = search.open;
+    let mut chosen = None;
+    egui::Window::new("Search")
+        .open(&mut open)
+        .default_width(260.0)
+        .show(context, |ui| {
+            ui.horizontal(|ui| {
+                let response = ui.text_edit_singleline(&mut search.query);
+                if std::mem::take(&mut search.focus) {
+                    response.request_focus();
+                }
+                if response.lost_focus() {
+                    if ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                        if !search.matches.is_empty() {
+                            let next = search.current.map_or(0, |current| current + 1);
+                            chosen = Some(next % search.matches.len());
+                        }
+                        response.request_focus();
+                    } else if ui.input(|input| input.key_pressed(egui::Key::Escape)) {
+                        search.open = false;
+                    }
+                }
+                ui.checkbox(&mut search.regex, "Regex");
+            });
+
+            if let Some(error) = &search.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            } else if !search.query.is_empty() {
+                ui.label(format!("{} matches", search.matches.len()));
+            }
+
+            let row_height = ui.text_style_height(&egui::TextStyle::Body);
+            egui::ScrollArea::vertical()
+                .max_height(SEARCH_RESULTS_HEIGHT)
+                .show_rows(ui, row_height, search.matches.len(), |ui, rows| {
+                    for index in rows {
+                        let found = &search.matches[index];
+                        let text = match &found.field {
+                            MatchedField::Label => found.name.clone(),
+                            MatchedField::Attribute(key) => format!("{} ({key})", found.name),
+                        };
+                        if ui
+                            .selectable_label(search.current == Some(index), text)
+                            .clicked()
+                        {
+                            chosen = Some(index);
+                        }
+                    }
+                });
+        });
+    search.open &= open;
+
+    if let Some(index) = chosen {
+        search.current = Some(index);
+        if let Ok(position) = positions.get(search.matches[index].entity) {
+            camera.translation.x = position.0.x;
+            camera.translation.y = position.0.y;
+        }
+    }
+}
+
+/// Ctrl + Shift + P lists every action with its binding, fuzzy filtered
+/// by the query. Enter or a click runs the highlighted action. Gestures
+/// can't be run from here, they are listed so they can be discovered.
+pub fn show_command_palette(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut palette: ResMut<CommandPaletteState>,
+    registry: Res<ActionRegistry>,
+) {
+    let Ok(context) = contexts.ctx_mut() else {
+        return;
+    };
+
+    if !palette.open {
+        return;
+    }
+
+    let palette = palette.as_mut();
+    let mut listed: Vec<(EditorAction, i32)> = EditorAction::ALL
+        .into_iter()
+        .filter_map(|action| fuzzy_score(&palette.query, action.name()).map(|s| (action, s)))
+        .collect();
+    listed.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    let last = listed.len().saturating_sub(1);
+
+    let mut chosen = None;
+    egui::Window::new("Command palette")
+        .title_bar(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+        .default_width(COMMAND_PALETTE_WIDTH)
+        .show(context, |ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut palette.query)
+                    .hint_text("Type a command")
+                    .desired_width(f32::INFINITY),
+            );
+            if std::mem::take(&mut palette.focus) {
+                response.request_focus();
+            }
+            if response.changed() {
+                palette.highlighted = 0;
+            }
+
+            let (up, down, enter, escape) = ui.input(|input| {
+                (
+                    input.key_pressed(egui::Key::ArrowUp),
+                    input.key_pressed(egui::Key::ArrowDown),
+                    input.key_pressed(egui::Key::Enter),
+                    input.key_pressed(egui::Key::Escape),
+                )
+            });
+            if down {
+                palette.highlighted = (palette.highlighted + 1).min(last);
+            } else if up {
+                palette.highlighted = palette.highlighted.saturating_sub(1);
+            }
+            if response.lost_focus() {
+                if enter {
+                    chosen = listed.get(palette.highlighted).map(|(action, _)| *action);
+                    response.request_focus();
+                } else if escape {
+                    palette.open = false;
+                }
+            }
+
+            egui::ScrollArea::vertical()
+                .max_height(COMMAND_PALETTE_HEIGHT)
+                .show(ui, |ui| {
+                    for (index, (action, _)) in listed.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            let button = egui::Button::selectable(
+                                index == palette.highlighted,
+                                action.name(),
+                            );
+                            if ui.add_enabled(!action.is_gesture(), button).clicked() {
+                                chosen = Some(*action);
+                            }
+                            let bindings: Vec<String> = registry
+                                .bindings(*action)
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect();
+                            ui.with_layout(
+                                egui::Layout::right_to_left(egui::Align::Center),
+                                |ui| ui.weak(bindings.join(", ")),
+                            );
+                        });
+                    }
+                });
+        });
+
+    if let Some(action) = chosen
+        && !action.is_gesture()
+    {
+        palette.open = false;
+        commands.trigger(RunActionEvent { action });
+    }
+}
+
+/// Offers to restore what the last session autosaved, if it
+/// crashed or was closed with unsaved changes.
+pub fn show_recovery_dialog(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut recovery: ResMut<RecoveryState>,
+) {
+    let Ok(context) = contexts.ctx_mut() else {
+        return;
+    };
+
+    let Some(reason) = recovery.offer else {
+        return;
+    };
+
+    egui::Window::new("Restore unsaved work?")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(context, |ui| {
+            ui.label(match reason {
+                RecoveryReason::UncleanShutdown => "The last session did not shut down cleanly.",
+                RecoveryReason::UnsavedChanges => {
+                    "The last session was closed with unsaved changes."
+                }
+            });
+            ui.label(
+                "Restoring replaces the current graph with the autosaved one, as one undo step.",
+            );
+            ui.horizontal(|ui| {
+                if ui.button("Restore").clicked() {
+                    if let Some(path) = recovery_path() {
+                        commands.trigger(OpenDocumentEvent {
+                            path: path.display().to_string(),
+                            source: DocumentSource::Recovery,
+                        });
+                    }
+                    recovery.offer = None;
+                }
+                if ui.button("Discard").clicked() {
+                    discard_recovery();
+                    recovery.offer = None;
+                }
+            });
+        });
+}
+
+/// Edits the `Settings` live, everything restyles as soon as they change.
+/// They are only written to the config directory when saved.
+pub fn show_settings_window(
+    mut contexts: EguiContexts,
+    mut window: ResMut<SettingsWindowState>,
+    mut settings: ResMut<Settings>,
+) {
+    let Ok(context) = contexts.ctx_mut() else {
+        return;
+    };
+
+    if !window.open {
+        return;
+    }
+
+    let mut open = window.open;
+    let mut edited = settings.clone();
+    egui::Window::new("Settings")
+        .open(&mut open)
+        .resizable(false)
+        .show(context, |ui| {
+            egui::Grid::new("settings_grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Theme");
+                    egui::ComboBox::from_id_salt("settings_theme")
+                        .selected_text(edited.theme.name())
+                        .show_ui(ui, |ui| {
+                            for theme in Theme::ALL {
+                                ui.selectable_value(&mut edited.theme, theme, theme.name());
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("Label font size");
+                    ui.add(
+                        egui::DragValue::new(&mut edited.vertex_label_font_size)
+                            .range(6.0..=48.0)
+                            .speed(0.2),
+                    );
+                    ui.end_row();
+                    ui.label("Weight font size");
+                    ui.add(
+                        egui::DragValue::new(&mut edited.edge_weight_font_size)
+                            .range(6.0..=48.0)
+                            .speed(0.2),
+                    );
+                    ui.end_row();
+                    ui.label("Vertex radius");
+                    ui.add(
+                        egui::DragValue::new(&mut edited.vertex_radius)
+                            .range(2.0..=50.0)
+                            .speed(0.2),
+                    );
+                    ui.end_row();
+                    ui.label("Edge width");
+                    ui.add(
+                        egui::DragValue::new(&mut edited.edge_width)
+                            .range(0.5..=30.0)
+                            .speed(0.1),
+                    );
+                    ui.end_row();
+                    ui.label("Double click time");
+                    ui.add(
+                        egui::DragValue::new(&mut edited.consecutive_click_time)
+                            .range(0.1..=1.0)
+                            .speed(0.01)
+                            .suffix(" s"),
+                    );
+                    ui.end_row();
+                });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    window.status = Some(match edited.save() {
+                        Ok(path) => format!("Saved to {}", path.display()),
+                        Err(error) => format!("Saving failed: {error}"),
+                    });
+                }
+                if ui.button("Reset to defaults").clicked() {
+                    edited = Settings::default();
+                }
+            });
+            match &window.status {
+                Some(status) => ui.label(status),
+                None => ui.weak(
+                    settings_path().map_or("No config directory found".to_string(), |path| {
+                        path.display().to_string()
+                    }),
+                ),
+            };
+        });
+
+    window.open = open;
+    // Only touched when edited, so `apply_settings` doesn't restyle every frame.
+    if edited != *settings {
+        *settings = edited;
+    }
+}
+
+/// egui follows the theme too, it is only told when the theme changes.
+pub fn apply_egui_theme(
+    mut contexts: EguiContexts,
+    mut applied: Local<Option<Theme>>,
+    settings: Res<Settings>,
+) {
+    if *applied == Some(settings.theme) {
+        return;
+    }
+    let Ok(context) = contexts.ctx_mut() else {
+        return;
+    };
+
+    context.set_visuals(match settings.theme {
+        Theme::Dark => egui::Visuals::dark(),
+        Theme::Light => egui::Visuals::light(),
+        Theme::HighContrast => {
+            let mut visuals = egui::Visuals::dark();
+            visuals.override_text_color = Some(egui::Color32::WHITE);
+            visuals.panel_fill = egui::Color32::BLACK;
+            visuals.window_fill = egui::Color32::BLACK;
+            visuals.window_stroke = egui::Stroke::new(1.0, egui::Color32::WHITE);
+            visuals.widgets.noninteractive.bg_stroke = egui::Stroke::new(1.0, egui::Color32::WHITE);
+            visuals
+        }
+    });
+    *applied = Some(settings.theme);
+}
+
+fn egui_color(color: Color) -> egui::Color32 {
+    let [r, g, b, a] = color.to_srgba().to_u8_array();
+    egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+}
+
+fn vertex_style_editor(ui: &mut egui::Ui, style: &mut VertexStyle) {
+    egui::Grid::new("inspector_vertex_style")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Shape");
+            egui::ComboBox::from_id_salt("vertex_shape")
+                .selected_text(style.shape.name())
+                .show_ui(ui, |ui| {
+                    for shape in VertexShape::ALL {
+                        ui.selectable_value(&mut style.shape, shape, shape.name());
+                    }
+                });
+            ui.end_row();
+            ui.label("Radius");
+            ui.add(egui::DragValue::new(&mut style.radius).range(2.0..=100.0));
+            ui.end_row();
+            ui.label("Fill");
+            ui.color_edit_button_rgba_unmultiplied(&mut style.fill);
+            ui.end_row();
+            ui.label("Stroke");
+            ui.horizontal(|ui| {
+                ui.color_edit_button_rgba_unmultiplied(&mut style.stroke);
+                ui.add(
+                    egui::DragValue::new(&mut style.stroke_width)
+                        .range(0.0..=20.0)
+                        .speed(0.1),
+                );
+            });
+            ui.end_row();
+        });
+}
+
+fn edge_style_editor(ui: &mut egui::Ui, style: &mut EdgeStyle) {
+    egui::Grid::new("inspector_edge_style")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Colour");
+            ui.color_edit_button_rgba_unmultiplied(&mut style.color);
+            ui.end_row();
+            ui.label("Width");
+            ui.add(
+                egui::DragValue::new(&mut style.width)
+                    .range(1.0..=40.0)
+                    .speed(0.1),
+            );
+            ui.end_row();
+            ui.label("Line");
+            egui::ComboBox::from_id_salt("edge_line")
+                .selected_text(style.line.name())
+                .show_ui(ui, |ui| {
+                    for line in LineStyle::ALL {
+                        ui.selectable_value(&mut style.line, line, line.name());
+                    }
+                });
+            ui.end_row();
+            ui.label("Arrow");
+            egui::ComboBox::from_id_salt("edge_arrow")
+                .selected_text(style.arrow.name())
+                .show_ui(ui, |ui| {
+                    for arrow in ArrowStyle::ALL {
+                        ui.selectable_value(&mut style.arrow, arrow, arrow.name());
+                    }
+                });
+            ui.end_row();
+        });
+}
+
+fn attribute_value_editor(ui: &mut egui::Ui, value: &mut AttributeValue) {
+    match value {
+        AttributeValue::String(text) => {
+            ui.add(egui::TextEdit::singleline(text).desired_width(100.0));
+        }
+        AttributeValue::Number(number) => {
+            ui.add(egui::DragValue::new(number).speed(0.1));
+        }
+        AttributeValue::Bool(flag) => {
+            ui.checkbox(flag, "");
+        }
+        AttributeValue::Color(color) => {
+            ui.color_edit_button_rgba_unmultiplied(color);
+        }
+    }
+}
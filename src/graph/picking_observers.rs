@@ -1,23 +1,38 @@
 use bevy::prelude::*;
 
 use crate::graph::{
+    actions::{ActionRegistry, EditorAction},
     bundles::VertexBundle,
-    components::{TemporaryDirectedEdge, Vertex},
-    constants::{EDGE_COLOR, HOVERED_EDGE_COLOR, HOVERED_VERTEX_COLOR, VERTEX_COLOR},
+    components::{
+        DirectedEdge, EdgeEnd, EdgeStyle, HeatMapStyle, Position, Selected, TemporaryDirectedEdge,
+        Vertex, VertexStyle,
+    },
+    constants::{EDGE_HANDLE_GRAB_RADIUS, LASSO_POINT_SPACING, VERTEX_SNAP_RADIUS},
     events::{
-        CanvasClickedEvent, EdgeClickedEvent, VertexClickedEvent, VertexDragDroppedEvent,
-        VertexDraggingEvent,
+        CanvasClickedEvent, EdgeClickedEvent, EdgeEndDroppedEvent, VertexClickedEvent,
+        VertexDragDroppedEvent, VertexDraggingEvent,
+    },
+    helpers::{edge_color, edge_handles, hovered_edge_color, hovered_vertex_fill, vertex_fill},
+    resources::{
+        AreaSelection, AreaSelectionMode, EdgeEndDrag, GraphAssets, HoveredEntity, UndoRedoStack,
+        VertexDrag,
     },
-    resources::{HoveredEntity, UndoRedoStack},
+    settings::Settings,
+    spatial::SpatialIndex,
     undo_redo::{UndoAction, VertexDeletionAction, VertexMoveAction},
 };
 
-/// Clicking the canvas results in a new Vertex.
+/// Clicking the canvas results in a new Vertex,
+/// unless the click ends an area selection.
 pub fn on_canvas_clicked(
     click: On<Pointer<Click>>,
     camera: Single<(&Camera, &GlobalTransform)>,
+    area_selection: Res<AreaSelection>,
     mut commands: Commands,
 ) {
+    if area_selection.mode.is_some() {
+        return;
+    }
     let (camera, camera_transform) = camera.into_inner();
     let Ok(world_pos) =
         camera.viewport_to_world_2d(camera_transform, click.pointer_location.position)
@@ -31,17 +46,103 @@ pub fn on_canvas_clicked(
     });
 }
 
+/// Shift + left dragging the canvas starts a rectangle selection,
+/// Alt + left dragging starts a lasso, see `ActionRegistry`.
+pub fn on_canvas_drag_start(
+    drag: On<Pointer<DragStart>>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    registry: Res<ActionRegistry>,
+    mut area_selection: ResMut<AreaSelection>,
+) {
+    let mode = if registry.gesture_held(EditorAction::RectangleSelection, drag.button, &keyboard) {
+        AreaSelectionMode::Rectangle
+    } else if registry.gesture_held(EditorAction::LassoSelection, drag.button, &keyboard) {
+        AreaSelectionMode::Lasso
+    } else {
+        return;
+    };
+
+    let (camera, camera_transform) = camera.into_inner();
+    if let Ok(world_pos) =
+        camera.viewport_to_world_2d(camera_transform, drag.pointer_location.position)
+    {
+        area_selection.mode = Some(mode);
+        area_selection.points = vec![world_pos, world_pos];
+    }
+}
+
+/// Moves the free corner of the rectangle, or extends the lasso.
+pub fn on_canvas_dragging(
+    drag: On<Pointer<Drag>>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    mut area_selection: ResMut<AreaSelection>,
+) {
+    let Some(mode) = area_selection.mode else {
+        return;
+    };
+    let (camera, camera_transform) = camera.into_inner();
+    let Ok(world_pos) =
+        camera.viewport_to_world_2d(camera_transform, drag.pointer_location.position)
+    else {
+        return;
+    };
+
+    match mode {
+        AreaSelectionMode::Rectangle => area_selection.points[1] = world_pos,
+        AreaSelectionMode::Lasso => {
+            if area_selection
+                .points
+                .last()
+                .is_none_or(|last| last.distance(world_pos) >= LASSO_POINT_SPACING)
+            {
+                area_selection.points.push(world_pos);
+            }
+        }
+    }
+}
+
+/// Adds the vertices inside of the rectangle or lasso to the selection.
+pub fn on_canvas_drag_end(
+    _drag: On<Pointer<DragEnd>>,
+    index: Res<SpatialIndex>,
+    mut area_selection: ResMut<AreaSelection>,
+    mut commands: Commands,
+) {
+    let Some(mode) = area_selection.mode.take() else {
+        return;
+    };
+    let points = std::mem::take(&mut area_selection.points);
+    let vertices = match mode {
+        AreaSelectionMode::Rectangle => {
+            index.vertices_in_rect(Rect::from_corners(points[0], points[1]))
+        }
+        AreaSelectionMode::Lasso => index.vertices_in_polygon(&points),
+    };
+    for vertex in vertices {
+        commands.entity(vertex).insert(Selected);
+    }
+}
+
 /// If a vertex is hovered we save it into the
 /// `HoveredEntity` resource. For more information
 /// see the docs at the resource declaration.
 pub fn on_vertex_hovered(
     over: On<Pointer<Over>>,
+    vertices: Query<(), With<Vertex>>,
     mut hovered_entity: ResMut<HoveredEntity>,
     mut materials_query: Query<&mut MeshMaterial2d<ColorMaterial>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut assets: GraphAssets,
+    styles: Query<&VertexStyle>,
 ) {
+    if !vertices.contains(over.entity) {
+        return;
+    }
     hovered_entity.0 = Some(over.entity);
-    let new_material = materials.add(HOVERED_VERTEX_COLOR);
+    let new_material = assets.material(hovered_vertex_fill(
+        styles.get(over.entity).ok(),
+        assets.palette(),
+    ));
     if let Ok(mut material) = materials_query.get_mut(over.entity) {
         material.0 = new_material;
     };
@@ -52,12 +153,22 @@ pub fn on_vertex_hovered(
 ///  For more information see the docs at the resource declaration.
 pub fn on_vertex_out(
     out: On<Pointer<Out>>,
+    vertices: Query<(), With<Vertex>>,
     mut hovered_entity: ResMut<HoveredEntity>,
     mut materials_query: Query<&mut MeshMaterial2d<ColorMaterial>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut assets: GraphAssets,
+    heat_map_styles: Query<&HeatMapStyle>,
+    styles: Query<&VertexStyle>,
 ) {
+    if !vertices.contains(out.entity) {
+        return;
+    }
     hovered_entity.0 = None;
-    let new_material = materials.add(VERTEX_COLOR);
+    let new_material = assets.material(vertex_fill(
+        styles.get(out.entity).ok(),
+        heat_map_styles.get(out.entity).ok(),
+        assets.palette(),
+    ));
     if let Ok(mut material) = materials_query.get_mut(out.entity) {
         material.0 = new_material;
     };
@@ -68,12 +179,20 @@ pub fn on_vertex_out(
 /// see the docs at the resource declaration.
 pub fn on_edge_hovered(
     over: On<Pointer<Over>>,
+    edges: Query<(), With<DirectedEdge>>,
     mut hovered_entity: ResMut<HoveredEntity>,
     mut materials_query: Query<&mut MeshMaterial2d<ColorMaterial>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut assets: GraphAssets,
+    styles: Query<&EdgeStyle>,
 ) {
+    if !edges.contains(over.entity) {
+        return;
+    }
     hovered_entity.0 = Some(over.entity);
-    let new_material = materials.add(HOVERED_EDGE_COLOR);
+    let new_material = assets.material(hovered_edge_color(
+        styles.get(over.entity).ok(),
+        assets.palette(),
+    ));
     if let Ok(mut material) = materials_query.get_mut(over.entity) {
         material.0 = new_material;
     };
@@ -84,38 +203,55 @@ pub fn on_edge_hovered(
 ///  For more information see the docs at the resource declaration.
 pub fn on_edge_out(
     out: On<Pointer<Out>>,
+    edges: Query<(), With<DirectedEdge>>,
     mut hovered_entity: ResMut<HoveredEntity>,
     mut materials_query: Query<&mut MeshMaterial2d<ColorMaterial>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut assets: GraphAssets,
+    styles: Query<&EdgeStyle>,
 ) {
+    if !edges.contains(out.entity) {
+        return;
+    }
     hovered_entity.0 = None;
-    let new_material = materials.add(EDGE_COLOR);
+    let new_material = assets.material(edge_color(styles.get(out.entity).ok(), assets.palette()));
     if let Ok(mut material) = materials_query.get_mut(out.entity) {
         material.0 = new_material;
     };
 }
 
 /// Clicking a vertex with the left click twice
-/// should start the renaming process.
+/// should start the renaming process. Shift + click
+/// toggles whether it is selected.
 pub fn on_vertex_clicked(
     click: On<Pointer<Click>>,
     camera: Single<(&Camera, &GlobalTransform)>,
     keyboard: Res<ButtonInput<KeyCode>>,
+    registry: Res<ActionRegistry>,
     vertices: Query<&Vertex>,
+    selected: Query<(), With<Selected>>,
     mut hovered_entity: ResMut<HoveredEntity>,
     mut undo_redo: ResMut<UndoRedoStack>,
     mut commands: Commands,
 ) {
+    if !vertices.contains(click.entity) {
+        return;
+    }
     let (camera, camera_transform) = camera.into_inner();
 
     if let Ok(world_pos) =
         camera.viewport_to_world_2d(camera_transform, click.pointer_location.position)
     {
-        // Check for deletion first.
-        let is_ctrl_held =
-            { keyboard.pressed(KeyCode::ControlLeft) || keyboard.pressed(KeyCode::ControlRight) };
+        if registry.gesture_held(EditorAction::ToggleSelected, click.button, &keyboard) {
+            if selected.contains(click.entity) {
+                commands.entity(click.entity).remove::<Selected>();
+            } else {
+                commands.entity(click.entity).insert(Selected);
+            }
+            return;
+        }
 
-        if is_ctrl_held {
+        // Check for deletion first.
+        if registry.gesture_held(EditorAction::DeleteVertex, click.button, &keyboard) {
             if let Ok(vertex) = vertices.get(click.entity) {
                 commands
                     .entity(click.entity)
@@ -137,22 +273,30 @@ pub fn on_vertex_clicked(
 
         commands.trigger(VertexClickedEvent {
             entity: click.entity,
+            button: click.button,
             world_position: world_pos,
         });
     }
 }
 
-/// Clicking with the right click on a vertex
+/// Starting to draw an edge from a vertex (right drag by default)
 /// should make the temporary edge visible. In order
 /// to use the actual drag delta values, this event is necessary
 /// to set the `from` and `to` values.
 pub fn on_vertex_dragged(
     drag: On<Pointer<DragStart>>,
+    vertices: Query<(), With<Vertex>>,
     camera: Single<(&Camera, &GlobalTransform)>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    registry: Res<ActionRegistry>,
+    mut vertex_drag: ResMut<VertexDrag>,
     mut temp_edge: Single<&mut TemporaryDirectedEdge>,
     mut undo_redo: ResMut<UndoRedoStack>,
     mut commands: Commands,
 ) {
+    if !vertices.contains(drag.entity) {
+        return;
+    }
     let (camera, camera_transform) = camera.into_inner();
     let Ok(world_pos) =
         camera.viewport_to_world_2d(camera_transform, drag.pointer_location.position)
@@ -160,10 +304,14 @@ pub fn on_vertex_dragged(
         return;
     };
 
-    if drag.button == PointerButton::Secondary {
+    vertex_drag.0 = [EditorAction::DrawEdge, EditorAction::MoveVertex]
+        .into_iter()
+        .find(|action| registry.gesture_held(*action, drag.button, &keyboard));
+
+    if vertex_drag.0 == Some(EditorAction::DrawEdge) {
         temp_edge.from = Some(drag.entity);
         temp_edge.to = world_pos;
-    } else if drag.button == PointerButton::Primary {
+    } else if vertex_drag.0 == Some(EditorAction::MoveVertex) {
         undo_redo.push_undo(
             UndoAction::UndoVertexMoveAction(VertexMoveAction {
                 entity: drag.entity,
@@ -182,9 +330,17 @@ pub fn on_vertex_dragged(
 /// based on the outcome we also spawn a new vertex.
 pub fn on_vertex_drop(
     drag: On<Pointer<DragEnd>>,
+    vertices: Query<(), With<Vertex>>,
+    mut vertex_drag: ResMut<VertexDrag>,
     mut commands: Commands,
     camera: Single<(&Camera, &GlobalTransform)>,
 ) {
+    if !vertices.contains(drag.entity) {
+        return;
+    }
+    let Some(action) = vertex_drag.0.take() else {
+        return;
+    };
     let (camera, camera_transform) = camera.into_inner();
 
     if let Ok(world_pos) =
@@ -192,7 +348,7 @@ pub fn on_vertex_drop(
     {
         commands.trigger(VertexDragDroppedEvent {
             entity: drag.entity,
-            button: drag.button,
+            action,
             world_position: world_pos,
         });
     };
@@ -201,18 +357,28 @@ pub fn on_vertex_drop(
 /// Dragging event on a vertex can happen in one of two ways:
 /// - Left click -> move the vertex around
 /// - Right click -> Attempt to create a connection.
+///
+/// The buttons and modifiers come from the `ActionRegistry`.
 pub fn on_vertex_dragging(
     drag: On<Pointer<Drag>>,
+    vertices: Query<(), With<Vertex>>,
+    vertex_drag: Res<VertexDrag>,
     mut commands: Commands,
     camera: Single<(&Camera, &GlobalTransform)>,
 ) {
+    if !vertices.contains(drag.entity) {
+        return;
+    }
+    let Some(action) = vertex_drag.0 else {
+        return;
+    };
     let (camera, camera_transform) = camera.into_inner();
     if let Ok(world_pos) =
         camera.viewport_to_world_2d(camera_transform, drag.pointer_location.position)
     {
         commands.trigger(VertexDraggingEvent {
             entity: drag.entity,
-            button: drag.button,
+            action,
             world_position: world_pos,
         })
     };
@@ -224,9 +390,15 @@ pub fn on_vertex_dragging(
 // I might try that again in the future.
 pub fn on_edge_clicked(
     click: On<Pointer<Click>>,
+    edges: Query<(), With<DirectedEdge>>,
+    edge_end_drag: Res<EdgeEndDrag>,
     camera: Single<(&Camera, &GlobalTransform)>,
     mut commands: Commands,
 ) {
+    // Releasing a dragged edge end over its own edge is no click.
+    if !edges.contains(click.entity) || edge_end_drag.0.is_some() {
+        return;
+    }
     let (camera, camera_transform) = camera.into_inner();
 
     if let Ok(world_pos) =
@@ -239,3 +411,183 @@ pub fn on_edge_clicked(
         });
     };
 }
+
+/// Dragging one of the handles of an edge (left drag by default) picks
+/// up that end, the temporary edge then shows where it would connect.
+pub fn on_edge_drag_start(
+    drag: On<Pointer<DragStart>>,
+    edges: Query<&DirectedEdge>,
+    vertices: Query<(&Position, Option<&VertexStyle>), With<Vertex>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    registry: Res<ActionRegistry>,
+    settings: Res<Settings>,
+    mut edge_end_drag: ResMut<EdgeEndDrag>,
+    mut temp_edge: Single<&mut TemporaryDirectedEdge>,
+) {
+    let Ok(edge) = edges.get(drag.entity) else {
+        return;
+    };
+    if !registry.gesture_held(EditorAction::ReconnectEdge, drag.button, &keyboard) {
+        return;
+    }
+    let (Some(point), Some([from_handle, to_handle])) = (
+        drag.hit.position,
+        edge_handles(edge, &vertices, settings.vertex_radius),
+    ) else {
+        return;
+    };
+
+    let point = point.truncate();
+    let (end, handle, fixed) = if point.distance(to_handle) <= point.distance(from_handle) {
+        (EdgeEnd::To, to_handle, edge.from)
+    } else {
+        (EdgeEnd::From, from_handle, edge.to)
+    };
+    if point.distance(handle) > EDGE_HANDLE_GRAB_RADIUS {
+        return;
+    }
+    edge_end_drag.0 = Some((drag.entity, end));
+    temp_edge.from = Some(fixed);
+    temp_edge.to = point;
+}
+
+/// Moves the loose end of the temporary edge with the cursor,
+/// snapping it to the vertex it would connect to.
+pub fn on_edge_dragging(
+    drag: On<Pointer<Drag>>,
+    edge_end_drag: Res<EdgeEndDrag>,
+    index: Res<SpatialIndex>,
+    positions: Query<&Position, With<Vertex>>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    mut temp_edge: Single<&mut TemporaryDirectedEdge>,
+) {
+    if edge_end_drag.0.is_none_or(|(edge, _)| edge != drag.entity) {
+        return;
+    }
+    let (camera, camera_transform) = camera.into_inner();
+    let Ok(world_pos) =
+        camera.viewport_to_world_2d(camera_transform, drag.pointer_location.position)
+    else {
+        return;
+    };
+
+    temp_edge.to = index
+        .nearest_vertex(world_pos, VERTEX_SNAP_RADIUS)
+        .and_then(|vertex| positions.get(vertex).ok())
+        .map_or(world_pos, |position| position.0);
+}
+
+/// Dropping a dragged edge end, see `edge_end_dropped`.
+pub fn on_edge_drop(
+    drag: On<Pointer<DragEnd>>,
+    mut edge_end_drag: ResMut<EdgeEndDrag>,
+    mut temp_edge: Single<&mut TemporaryDirectedEdge>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    mut commands: Commands,
+) {
+    let Some((edge, end)) = edge_end_drag.0 else {
+        return;
+    };
+    if edge != drag.entity {
+        return;
+    }
+    edge_end_drag.0 = None;
+    temp_edge.from = None;
+    let (camera, camera_transform) = camera.into_inner();
+
+    if let Ok(world_pos) =
+        camera.viewport_to_world_2d(camera_transform, drag.pointer_location.position)
+    {
+        commands.trigger(EdgeEndDroppedEvent {
+            entity: edge,
+            end,
+            world_position: world_pos,
+        });
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        camera::NormalizedRenderTarget,
+        ecs::system::RunSystemOnce,
+        picking::{
+            backend::HitData,
+            pointer::{Location, PointerId},
+        },
+    };
+
+    use super::*;
+    use crate::graph::resources::{GraphAssets, HandleCache, VertexIds};
+
+    fn hover(app: &mut App, entity: Entity) {
+        let location = Location {
+            target: NormalizedRenderTarget::None {
+                width: 1,
+                height: 1,
+            },
+            position: Vec2::ZERO,
+        };
+        let hit = HitData::new(Entity::PLACEHOLDER, 0.0, None, None);
+        let world = app.world_mut();
+        world.trigger(Pointer::new(
+            PointerId::Mouse,
+            location.clone(),
+            Over { hit: hit.clone() },
+            entity,
+        ));
+        world.trigger(Pointer::new(
+            PointerId::Mouse,
+            location,
+            Out { hit },
+            entity,
+        ));
+        world.flush();
+    }
+
+    fn asset_counts(app: &App) -> (usize, usize) {
+        let world = app.world();
+        (
+            world.resource::<Assets<Mesh>>().len(),
+            world.resource::<Assets<ColorMaterial>>().len(),
+        )
+    }
+
+    #[test]
+    fn repeated_hovering_does_not_add_assets() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_asset::<ColorMaterial>()
+            .insert_resource(HoveredEntity(None))
+            .insert_resource(HandleCache::default())
+            .insert_resource(Settings::default())
+            .insert_resource(VertexIds::default())
+            .add_observer(on_vertex_hovered)
+            .add_observer(on_vertex_out);
+
+        let vertex = app
+            .world_mut()
+            .run_system_once(|mut commands: Commands, mut assets: GraphAssets| {
+                VertexBundle::spawn(&mut commands, &mut assets, Vec2::ZERO)
+            })
+            .unwrap();
+        // A second vertex shares everything with the first one.
+        app.world_mut()
+            .run_system_once(|mut commands: Commands, mut assets: GraphAssets| {
+                VertexBundle::spawn(&mut commands, &mut assets, Vec2::ONE);
+            })
+            .unwrap();
+        assert_eq!(asset_counts(&app), (1, 1));
+
+        // The first hover adds the hovered colour.
+        hover(&mut app, vertex);
+        let counts = asset_counts(&app);
+        assert_eq!(counts, (1, 2));
+
+        for _ in 0..100 {
+            hover(&mut app, vertex);
+        }
+        assert_eq!(asset_counts(&app), counts);
+    }
+}
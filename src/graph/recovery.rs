@@ -0,0 +1,118 @@
+use std::{fs, io::ErrorKind, path::PathBuf};
+
+use bevy::prelude::*;
+
+use crate::graph::{
+    constants::{AUTOSAVE_INTERVAL, CONFIG_DIRECTORY, RECOVERY_FILE, SESSION_LOCK_FILE},
+    document::GraphDocument,
+};
+
+/// Why the last session left autosaved work behind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryReason {
+    /// The editor crashed or was killed.
+    UncleanShutdown,
+    /// The editor was closed with unsaved changes.
+    UnsavedChanges,
+}
+
+/// Autosaving into the recovery file, and the offer to restore
+/// what the last session left in it.
+#[derive(Resource, Debug)]
+pub struct RecoveryState {
+    /// Set until the autosaved work of the last session is restored or discarded.
+    /// The recovery file isn't touched before that.
+    pub offer: Option<RecoveryReason>,
+    /// `UndoRedoStack::state` of the graph in the recovery file.
+    autosaved_state: Option<u64>,
+    pub timer: Timer,
+}
+
+impl RecoveryState {
+    /// Looks for what the last session left behind, and marks
+    /// this session as running until `end_session`.
+    pub fn start_session() -> Self {
+        let unclean = session_lock_path().is_some_and(|path| path.exists());
+        let recoverable = recovery_path().is_some_and(|path| path.exists());
+        if let Err(error) = write_file(session_lock_path(), "") {
+            warn!("Could not mark the session as running: {error}");
+        }
+
+        RecoveryState {
+            offer: recoverable.then_some(if unclean {
+                RecoveryReason::UncleanShutdown
+            } else {
+                RecoveryReason::UnsavedChanges
+            }),
+            autosaved_state: None,
+            timer: Timer::new(AUTOSAVE_INTERVAL, TimerMode::Repeating),
+        }
+    }
+
+    /// Writes the graph to the recovery file while it has unsaved changes,
+    /// unless that state is already there. Without any, the file is removed.
+    pub fn autosave(&mut self, state: u64, dirty: bool, document: impl FnOnce() -> GraphDocument) {
+        if self.offer.is_some() {
+            return;
+        }
+        if !dirty {
+            self.autosaved_state = None;
+            discard_recovery();
+            return;
+        }
+        if self.autosaved_state == Some(state) {
+            return;
+        }
+
+        let result = document()
+            .to_ron()
+            .map_err(|error| error.to_string())
+            .and_then(|text| write_file(recovery_path(), &text));
+        match result {
+            Ok(()) => self.autosaved_state = Some(state),
+            Err(error) => warn!("Autosave failed: {error}"),
+        }
+    }
+}
+
+/// Where autosaved work goes, e.g. `~/.local/share/body_graph/recovery.ron` on Linux.
+pub fn recovery_path() -> Option<PathBuf> {
+    data_path(RECOVERY_FILE)
+}
+
+pub fn discard_recovery() {
+    remove_file(recovery_path());
+}
+
+/// Marks the session as cleanly shut down.
+pub fn end_session() {
+    remove_file(session_lock_path());
+}
+
+fn session_lock_path() -> Option<PathBuf> {
+    data_path(SESSION_LOCK_FILE)
+}
+
+fn data_path(file: &str) -> Option<PathBuf> {
+    dirs::data_local_dir().map(|directory| directory.join(CONFIG_DIRECTORY).join(file))
+}
+
+fn write_file(path: Option<PathBuf>, text: &str) -> Result<(), String> {
+    let path = path.ok_or("No data directory found")?;
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory).map_err(|error| error.to_string())?;
+    }
+    fs::write(&path, text).map_err(|error| error.to_string())
+}
+
+fn remove_file(path: Option<PathBuf>) {
+    let Some(path) = path else {
+        return;
+    };
+    match fs::remove_file(&path) {
+        Err(error) if error.kind() != ErrorKind::NotFound => {
+            warn!("Could not remove {}: {error}", path.display());
+        }
+        _ => {}
+    }
+}
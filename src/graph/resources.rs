@@ -1,9 +1,34 @@
-use bevy::prelude::*;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
 
 use crate::graph::{
-    helpers::despawn_entity,
+    actions::EditorAction,
+    analysis::{DistanceStatistics, GraphStatistics, MatrixOrdering, Metric, VertexMetrics},
+    components::{
+        ArrowStyle, Attribute, AttributeValue, EdgeEnd, ElementStyle, VertexId, VertexShape,
+        VertexStyle,
+    },
+    constants::{DOCUMENT_PATH, EDGE_SHAPE},
+    document::GraphDocument,
+    generators::{GeneratorKind, GeneratorParameters},
+    helpers::{arrowhead_mesh, dashed_edge_mesh, despawn_entity, label_width, vertex_mesh},
+    search::SearchMatch,
+    settings::{Palette, Settings},
     undo_redo::{
-        RedoAction, RedoEdgeDeletionEvent, RedoEdgeDrawingEvent, RedoVertexDeletionEvent, RedoVertexMoveEvent, RedoVertexRenameEvent, RedoVertexSpawnEvent, UndoAction, UndoEdgeDeletionEvent, UndoEdgeDrawingEvent, UndoVertexDeletionEvent, UndoVertexMoveEvent, UndoVertexRenameEvent, UndoVertexSpawnEvent, UndoVertexInsertionEvent, RedoVertexInsertionEvent
+        RedoAction, RedoAttributesEditEvent, RedoBatchEditEvent, RedoEdgeDeletionEvent,
+        RedoEdgeDrawingEvent, RedoEdgeReconnectEvent, RedoEdgeWeightEditEvent,
+        RedoGraphDeletionEvent, RedoGraphInsertionEvent, RedoGraphReplaceEvent, RedoStyleEditEvent,
+        RedoVertexDeletionEvent, RedoVertexInsertionEvent, RedoVertexMergeEvent,
+        RedoVertexMoveEvent, RedoVertexRenameEvent, RedoVertexSpawnEvent, SubgraphAction,
+        UndoAction, UndoAttributesEditEvent, UndoBatchEditEvent, UndoEdgeDeletionEvent,
+        UndoEdgeDrawingEvent, UndoEdgeReconnectEvent, UndoEdgeWeightEditEvent,
+        UndoGraphDeletionEvent, UndoGraphInsertionEvent, UndoGraphReplaceEvent, UndoStyleEditEvent,
+        UndoVertexDeletionEvent, UndoVertexInsertionEvent, UndoVertexMergeEvent,
+        UndoVertexMoveEvent, UndoVertexRenameEvent, UndoVertexSpawnEvent,
     },
 };
 
@@ -17,6 +42,21 @@ use crate::graph::{
 #[derive(Resource)]
 pub struct HoveredEntity(pub Option<Entity>);
 
+/// Shift + dragging the canvas selects the vertices in a rectangle,
+/// Alt + dragging the ones inside of a lasso.
+#[derive(Resource, Default, Debug)]
+pub struct AreaSelection {
+    pub mode: Option<AreaSelectionMode>,
+    /// The two corners of the rectangle, or the outline of the lasso.
+    pub points: Vec<Vec2>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AreaSelectionMode {
+    Rectangle,
+    Lasso,
+}
+
 /// Used to determine the display features on renaming
 /// This can be used both for vertices and edges.
 /// TODO: Support multiple renames maybe.
@@ -28,6 +68,372 @@ pub struct RenamingState {
     pub screen_position: Vec2,
 }
 
+/// Metrics of every vertex, indexed the same way
+/// as the vertices of the `GraphSnapshot`. Only computed
+/// while something is using them as betweenness is costly.
+#[derive(Resource, Default, Debug)]
+pub struct GraphMetrics {
+    /// The snapshot revision the values were computed from.
+    pub revision: u64,
+    pub values: Vec<VertexMetrics>,
+}
+
+/// State of the metrics panel and of the heat-map
+/// mode that maps a metric onto the canvas.
+#[derive(Resource, Debug)]
+pub struct MetricsPanelState {
+    pub open: bool,
+    /// `None` sorts by label.
+    pub sort_by: Option<Metric>,
+    pub descending: bool,
+    /// The metric mapped onto the vertices, if any.
+    pub heat_map: Option<Metric>,
+    pub heat_map_color: bool,
+    pub heat_map_radius: bool,
+    pub export_status: Option<String>,
+}
+
+impl Default for MetricsPanelState {
+    fn default() -> Self {
+        MetricsPanelState {
+            open: false,
+            sort_by: None,
+            descending: false,
+            heat_map: None,
+            heat_map_color: true,
+            heat_map_radius: false,
+            export_status: None,
+        }
+    }
+}
+
+/// State of the always-available statistics side panel.
+#[derive(Resource, Debug)]
+pub struct StatisticsPanelState {
+    pub open: bool,
+    /// The snapshot revision the statistics belong to.
+    pub revision: u64,
+    pub statistics: GraphStatistics,
+    pub distances: DistanceStatistics,
+}
+
+impl Default for StatisticsPanelState {
+    fn default() -> Self {
+        StatisticsPanelState {
+            open: true,
+            // Forces a first computation even for the empty graph.
+            revision: u64::MAX,
+            statistics: GraphStatistics::default(),
+            distances: DistanceStatistics::default(),
+        }
+    }
+}
+
+/// Hands out `VertexId`s, each above every id handed out or read so far.
+#[derive(Resource, Default, Debug)]
+pub struct VertexIds {
+    next: u64,
+}
+
+impl VertexIds {
+    pub fn fresh(&mut self) -> VertexId {
+        self.next += 1;
+        VertexId(self.next - 1)
+    }
+
+    /// Keeps an id read from a document from being handed out again.
+    pub fn claim(&mut self, id: u64) -> VertexId {
+        self.next = self.next.max(id.saturating_add(1));
+        VertexId(id)
+    }
+}
+
+/// The last copied subgraph. The same is also put on the system
+/// clipboard as text, which takes precedence when pasting so
+/// graphs can be moved between two running instances.
+#[derive(Resource, Default, Debug)]
+pub struct GraphClipboard(pub Option<GraphDocument>);
+
+/// State of the graph generator dialog.
+#[derive(Resource, Debug)]
+pub struct GeneratorDialogState {
+    pub open: bool,
+    pub kind: GeneratorKind,
+    pub parameters: GeneratorParameters,
+}
+
+impl Default for GeneratorDialogState {
+    fn default() -> Self {
+        GeneratorDialogState {
+            open: false,
+            kind: GeneratorKind::Complete,
+            parameters: GeneratorParameters::default(),
+        }
+    }
+}
+
+/// Where the graph is saved to and opened from,
+/// and the outcome of the last attempt.
+#[derive(Resource, Debug)]
+pub struct DocumentState {
+    pub path: String,
+    pub status: Option<String>,
+    /// `UndoRedoStack::state` when the document was last saved or opened.
+    pub saved_state: u64,
+}
+
+impl Default for DocumentState {
+    fn default() -> Self {
+        DocumentState {
+            path: DOCUMENT_PATH.to_string(),
+            status: None,
+            saved_state: 0,
+        }
+    }
+}
+
+impl DocumentState {
+    /// Whether the graph changed since it was last saved or opened.
+    pub fn is_dirty(&self, undo_redo: &UndoRedoStack) -> bool {
+        undo_redo.state() != self.saved_state
+    }
+}
+
+/// Whether the minimap in the corner of the window is shown.
+#[derive(Resource, Debug)]
+pub struct MinimapState {
+    pub open: bool,
+}
+
+impl Default for MinimapState {
+    fn default() -> Self {
+        MinimapState { open: true }
+    }
+}
+
+/// The text editor pane. `text` is regenerated from the graph
+/// whenever the graph changed, unless it has unapplied edits.
+#[derive(Resource, Default, Debug)]
+pub struct TextEditorState {
+    pub open: bool,
+    pub text: String,
+    /// The text as it was generated, to tell whether it was edited.
+    pub generated: String,
+    /// The undo state `generated` was made at.
+    pub synced: Option<u64>,
+    /// Why the last apply failed.
+    pub error: Option<String>,
+}
+
+impl TextEditorState {
+    /// Drops the edits, the text is regenerated in the next frame.
+    pub fn reload(&mut self) {
+        self.text.clear();
+        self.generated.clear();
+        self.synced = None;
+    }
+
+    pub fn is_edited(&self) -> bool {
+        self.text != self.generated
+    }
+}
+
+/// The adjacency matrix panel. The edge of the hovered cell
+/// is highlighted on the canvas, whether it exists or not.
+#[derive(Resource, Default, Debug)]
+pub struct AdjacencyMatrixState {
+    pub open: bool,
+    pub ordering: MatrixOrdering,
+    /// The row and column vertex of the hovered cell.
+    pub hovered: Option<(Entity, Entity)>,
+}
+
+/// Which table the tables window shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TableKind {
+    #[default]
+    Vertices,
+    Edges,
+}
+
+/// The columns of both tables, each table shows some of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableColumn {
+    Label,
+    X,
+    Y,
+    Degree,
+    From,
+    To,
+    Weight,
+    Attributes,
+}
+
+impl TableColumn {
+    pub fn columns(kind: TableKind) -> &'static [TableColumn] {
+        match kind {
+            TableKind::Vertices => &[
+                TableColumn::Label,
+                TableColumn::X,
+                TableColumn::Y,
+                TableColumn::Degree,
+                TableColumn::Attributes,
+            ],
+            TableKind::Edges => &[
+                TableColumn::From,
+                TableColumn::To,
+                TableColumn::Weight,
+                TableColumn::Attributes,
+            ],
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TableColumn::Label => "Label",
+            TableColumn::X => "x",
+            TableColumn::Y => "y",
+            TableColumn::Degree => "Degree",
+            TableColumn::From => "From",
+            TableColumn::To => "To",
+            TableColumn::Weight => "Weight",
+            TableColumn::Attributes => "Attributes",
+        }
+    }
+
+    /// Double clicking these cells edits them.
+    pub fn is_editable(&self) -> bool {
+        matches!(
+            self,
+            TableColumn::Label | TableColumn::X | TableColumn::Y | TableColumn::Weight
+        )
+    }
+}
+
+/// The text of a table cell being edited, applied on Enter
+/// or when the cell loses focus, dropped on Escape.
+#[derive(Debug)]
+pub struct CellEdit {
+    pub entity: Entity,
+    pub column: TableColumn,
+    pub text: String,
+}
+
+/// The vertex and edge tables. Selected vertex rows are the canvas
+/// selection, the edge table has a selection of its own.
+#[derive(Resource, Debug)]
+pub struct TablesState {
+    pub open: bool,
+    pub kind: TableKind,
+    pub filter: String,
+    pub sort_by: TableColumn,
+    pub descending: bool,
+    pub selected_edges: HashSet<Entity>,
+    /// The last clicked row, Shift + click selects the rows up to it.
+    pub anchor: Option<Entity>,
+    pub editing: Option<CellEdit>,
+    /// What the bulk operations set on the selected rows.
+    pub attribute: Attribute,
+    pub color: [f32; 4],
+    /// Why the last cell edit was not applied.
+    pub error: Option<String>,
+}
+
+impl Default for TablesState {
+    fn default() -> Self {
+        TablesState {
+            open: false,
+            kind: TableKind::Vertices,
+            filter: String::new(),
+            sort_by: TableColumn::Label,
+            descending: false,
+            selected_edges: HashSet::new(),
+            anchor: None,
+            editing: None,
+            attribute: Attribute {
+                key: String::new(),
+                value: AttributeValue::String(String::new()),
+            },
+            color: [1.0, 1.0, 1.0, 1.0],
+            error: None,
+        }
+    }
+}
+
+/// The settings window. The status reports the last save.
+#[derive(Resource, Default, Debug)]
+pub struct SettingsWindowState {
+    pub open: bool,
+    pub status: Option<String>,
+}
+
+/// The Ctrl + F search box and its results. `update_search_matches`
+/// recomputes the matches when the query, a label or an attribute changes.
+#[derive(Resource, Default, Debug)]
+pub struct SearchState {
+    pub open: bool,
+    pub query: String,
+    pub regex: bool,
+    /// Focuses the query field the next time the box is shown.
+    pub focus: bool,
+    pub matches: Vec<SearchMatch>,
+    /// Index into `matches` of the vertex the camera was centred on.
+    pub current: Option<usize>,
+    /// Why the query is not a valid regex.
+    pub error: Option<String>,
+    /// The query and regex flag `matches` belong to.
+    pub searched: Option<(String, bool)>,
+}
+
+/// What the ongoing vertex drag does, moving the vertex or drawing
+/// an edge from it. Decided by the button and modifiers it started with.
+#[derive(Resource, Default, Debug)]
+pub struct VertexDrag(pub Option<EditorAction>);
+
+/// The edge whose end is being dragged to another vertex, and which end.
+#[derive(Resource, Default, Debug)]
+pub struct EdgeEndDrag(pub Option<(Entity, EdgeEnd)>);
+
+/// The Ctrl + Shift + P command palette.
+#[derive(Resource, Default, Debug)]
+pub struct CommandPaletteState {
+    pub open: bool,
+    pub query: String,
+    /// Focuses the query field the next time the palette is shown.
+    pub focus: bool,
+    /// Index into the filtered list, moved with the arrow keys.
+    pub highlighted: usize,
+}
+
+/// The inspected vertex or edge and the working copy of its data.
+/// Edits are applied as one undo step once no inspector widget
+/// is being edited, instead of on every keystroke or drag.
+#[derive(Resource, Debug)]
+pub struct InspectorState {
+    pub open: bool,
+    pub target: Option<Entity>,
+    pub label: String,
+    pub weight: Option<f32>,
+    pub attributes: Vec<Attribute>,
+    pub style: ElementStyle,
+    /// Whether a widget was being edited in the last frame.
+    pub editing: bool,
+}
+
+impl Default for InspectorState {
+    fn default() -> Self {
+        InspectorState {
+            open: true,
+            target: None,
+            label: String::new(),
+            weight: None,
+            attributes: Vec::new(),
+            style: ElementStyle::Vertex(VertexStyle::default()),
+            editing: false,
+        }
+    }
+}
+
 /// The stack resource for managing undo/redo operations
 /// Contains redo and undo separately, and pushes/pops
 /// fron one onto/from another.
@@ -38,6 +444,14 @@ pub struct UndoRedoStack {
     pub undo_stack: Vec<UndoAction>,
     /// Redo actions
     pub redo_stack: Vec<RedoAction>,
+    /// Ids of the actions on either stack, so two states of the graph
+    /// can be told apart even if the stacks are just as high.
+    undo_ids: Vec<u64>,
+    redo_ids: Vec<u64>,
+    next_id: u64,
+    /// Id of the last action that fell off the undo stack, which is the
+    /// state once everything left is undone. Zero until one falls off.
+    forgotten_id: u64,
 }
 
 impl Default for UndoRedoStack {
@@ -46,36 +460,65 @@ impl Default for UndoRedoStack {
             max_size: 64,
             undo_stack: Vec::default(),
             redo_stack: Vec::default(),
+            undo_ids: Vec::default(),
+            redo_ids: Vec::default(),
+            next_id: 1,
+            forgotten_id: 0,
         }
     }
 }
 
 impl UndoRedoStack {
+    /// Identifies the current state of the graph, it only comes back
+    /// by undoing or redoing to it. Zero before the first action.
+    pub fn state(&self) -> u64 {
+        self.undo_ids.last().copied().unwrap_or(self.forgotten_id)
+    }
+
     /// Pushing an undo also clears redo as a new action after multiple undo
     /// operations might invalidate a redo in the stack.
     pub fn push_undo(&mut self, undo_action: UndoAction, commands: &mut Commands) {
 
         if self.undo_stack.len() == self.max_size {
+            self.forgotten_id = self.undo_ids.remove(0);
             let action = self.undo_stack.remove(0);
             match action {
                 UndoAction::UndoVertexDeletion(deletion_action) => {
                     despawn_entity(commands, deletion_action.entity);
                 }
+                UndoAction::UndoGraphDeletionAction(deletion) => {
+                    despawn_subgraph(commands, &deletion);
+                }
+                UndoAction::UndoGraphReplaceAction(replace) => {
+                    despawn_subgraph(commands, &replace.removed);
+                }
+                UndoAction::UndoVertexMergeAction(merge) => {
+                    despawn_subgraph(commands, &merge.removed);
+                }
                 _ => {}
             }
         }
         self.undo_stack.push(undo_action);
+        self.undo_ids.push(self.next_id);
+        self.next_id += 1;
 
         for redo_action in self.redo_stack.iter_mut() {
             match redo_action {
                 RedoAction::RedoVertexSpawn(spawn_action) => {
                     despawn_entity(commands, spawn_action.entity);
                 }
+                RedoAction::RedoGraphInsertionAction(insertion) => {
+                    despawn_subgraph(commands, insertion);
+                }
+                RedoAction::RedoGraphReplaceAction(replace) => {
+                    despawn_subgraph(commands, &replace.inserted);
+                }
                 _ => {}
             }
         }
 
         self.redo_stack.clear();
+        self.redo_ids.clear();
     }
 
     /// This push does not clear the redo stack. It's used only for redo actions,
@@ -90,6 +533,8 @@ impl UndoRedoStack {
         let Some(undo_action) = self.undo_stack.pop() else {
             return;
         };
+        // The redo action itself is pushed by the observer of the event.
+        self.redo_ids.extend(self.undo_ids.pop());
 
         match undo_action {
             UndoAction::UndoVertexRename(rename) => {
@@ -128,6 +573,33 @@ impl UndoRedoStack {
                     action: insertion
                 })
             }
+            UndoAction::UndoGraphInsertionAction(insertion) => {
+                commands.trigger(UndoGraphInsertionEvent { action: insertion });
+            }
+            UndoAction::UndoGraphDeletionAction(deletion) => {
+                commands.trigger(UndoGraphDeletionEvent { action: deletion });
+            }
+            UndoAction::UndoGraphReplaceAction(replace) => {
+                commands.trigger(UndoGraphReplaceEvent { action: replace });
+            }
+            UndoAction::UndoAttributesEditAction(edit) => {
+                commands.trigger(UndoAttributesEditEvent { action: edit });
+            }
+            UndoAction::UndoEdgeWeightEditAction(edit) => {
+                commands.trigger(UndoEdgeWeightEditEvent { action: edit });
+            }
+            UndoAction::UndoStyleEditAction(edit) => {
+                commands.trigger(UndoStyleEditEvent { action: edit });
+            }
+            UndoAction::UndoBatchEditAction(edit) => {
+                commands.trigger(UndoBatchEditEvent { action: edit });
+            }
+            UndoAction::UndoEdgeReconnectAction(reconnect) => {
+                commands.trigger(UndoEdgeReconnectEvent { action: reconnect });
+            }
+            UndoAction::UndoVertexMergeAction(merge) => {
+                commands.trigger(UndoVertexMergeEvent { action: merge });
+            }
         }
     }
 
@@ -144,6 +616,7 @@ impl UndoRedoStack {
         let Some(redo_action) = self.redo_stack.pop() else {
             return;
         };
+        self.undo_ids.extend(self.redo_ids.pop());
 
         match redo_action {
             RedoAction::RedoVertexRename(rename) => {
@@ -180,6 +653,341 @@ impl UndoRedoStack {
             RedoAction::RedoVertexInsertionAction(insertion) => {
                 commands.trigger(RedoVertexInsertionEvent { action: insertion });
             }
+            RedoAction::RedoGraphInsertionAction(insertion) => {
+                commands.trigger(RedoGraphInsertionEvent { action: insertion });
+            }
+            RedoAction::RedoGraphDeletionAction(deletion) => {
+                commands.trigger(RedoGraphDeletionEvent { action: deletion });
+            }
+            RedoAction::RedoGraphReplaceAction(replace) => {
+                commands.trigger(RedoGraphReplaceEvent { action: replace });
+            }
+            RedoAction::RedoAttributesEditAction(edit) => {
+                commands.trigger(RedoAttributesEditEvent { action: edit });
+            }
+            RedoAction::RedoEdgeWeightEditAction(edit) => {
+                commands.trigger(RedoEdgeWeightEditEvent { action: edit });
+            }
+            RedoAction::RedoStyleEditAction(edit) => {
+                commands.trigger(RedoStyleEditEvent { action: edit });
+            }
+            RedoAction::RedoBatchEditAction(edit) => {
+                commands.trigger(RedoBatchEditEvent { action: edit });
+            }
+            RedoAction::RedoEdgeReconnectAction(reconnect) => {
+                commands.trigger(RedoEdgeReconnectEvent { action: reconnect });
+            }
+            RedoAction::RedoVertexMergeAction(merge) => {
+                commands.trigger(RedoVertexMergeEvent { action: merge });
+            }
+        }
+    }
+}
+
+/// Despawns every entity of a subgraph that can no longer come back.
+fn despawn_subgraph(commands: &mut Commands, subgraph: &SubgraphAction) {
+    for vertex in &subgraph.vertices {
+        despawn_entity(commands, vertex.entity);
+    }
+    for edge in &subgraph.edges {
+        despawn_entity(commands, edge.entity);
+    }
+}
+
+/// Identifies a mesh in the `HandleCache`. Floats are kept as their bits
+/// so keys can be hashed, and the label width only matters for rounded boxes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MeshKey {
+    Vertex {
+        shape: VertexShape,
+        radius: u32,
+        outset: u32,
+        label_width: u32,
+    },
+    Edge,
+    DashedEdge {
+        dashes: usize,
+        ratio: u32,
+    },
+    Arrowhead(ArrowStyle),
+}
+
+impl MeshKey {
+    pub fn vertex(
+        shape: VertexShape,
+        radius: f32,
+        label: &str,
+        font_size: f32,
+        outset: f32,
+    ) -> Self {
+        let label_width = match shape {
+            VertexShape::RoundedBox => label_width(label, font_size),
+            _ => 0.0,
+        };
+        MeshKey::Vertex {
+            shape,
+            radius: radius.to_bits(),
+            outset: outset.to_bits(),
+            label_width: label_width.to_bits(),
+        }
+    }
+
+    pub fn dashed_edge(dashes: usize, ratio: f32) -> Self {
+        MeshKey::DashedEdge {
+            dashes,
+            ratio: ratio.to_bits(),
+        }
+    }
+
+    fn build(self) -> Mesh {
+        match self {
+            MeshKey::Vertex {
+                shape,
+                radius,
+                outset,
+                label_width,
+            } => vertex_mesh(
+                shape,
+                f32::from_bits(radius),
+                f32::from_bits(label_width),
+                f32::from_bits(outset),
+            ),
+            MeshKey::Edge => EDGE_SHAPE.into(),
+            MeshKey::DashedEdge { dashes, ratio } => {
+                dashed_edge_mesh(dashes, f32::from_bits(ratio))
+            }
+            MeshKey::Arrowhead(style) => arrowhead_mesh(style),
         }
     }
 }
+
+/// Meshes and materials are shared between every vertex and edge that
+/// look the same, instead of adding a new asset for each entity and
+/// each hover. Materials are keyed by the bits of their sRGBA colour.
+#[derive(Resource, Default, Debug)]
+pub struct HandleCache {
+    meshes: HashMap<MeshKey, Handle<Mesh>>,
+    materials: HashMap<[u32; 4], Handle<ColorMaterial>>,
+}
+
+impl HandleCache {
+    /// Forgets the handles nothing else holds anymore, which frees their
+    /// assets. Otherwise every colour and radius ever shown, e.g. while
+    /// dragging a style slider, would stay loaded.
+    pub fn evict_unused(&mut self) {
+        self.meshes.retain(|_, handle| is_shared(handle));
+        self.materials.retain(|_, handle| is_shared(handle));
+    }
+}
+
+/// Whether anything besides the cache holds a strong handle.
+fn is_shared<A: Asset>(handle: &Handle<A>) -> bool {
+    match handle {
+        Handle::Strong(strong) => Arc::strong_count(strong) > 1,
+        Handle::Uuid(..) => true,
+    }
+}
+
+/// Everything needed to hand out shared handles, so observers and
+/// systems only need a single parameter for it. Also carries the
+/// `Settings`, since spawning and restyling both need them, and the
+/// `VertexIds` spawned vertices get.
+#[derive(SystemParam)]
+pub struct GraphAssets<'w> {
+    cache: ResMut<'w, HandleCache>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<ColorMaterial>>,
+    settings: Res<'w, Settings>,
+    vertex_ids: ResMut<'w, VertexIds>,
+}
+
+impl GraphAssets<'_> {
+    pub fn vertex_ids(&mut self) -> &mut VertexIds {
+        &mut self.vertex_ids
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn palette(&self) -> Palette {
+        self.settings.palette()
+    }
+
+    pub fn mesh(&mut self, key: MeshKey) -> Handle<Mesh> {
+        self.cache
+            .meshes
+            .entry(key)
+            .or_insert_with(|| self.meshes.add(key.build()))
+            .clone()
+    }
+
+    pub fn material(&mut self, color: Color) -> Handle<ColorMaterial> {
+        let key = color.to_srgba().to_f32_array().map(f32::to_bits);
+        self.cache
+            .materials
+            .entry(key)
+            .or_insert_with(|| self.materials.add(color))
+            .clone()
+    }
+}
+
+/// How a merged vertex is named, after one of the merged vertices
+/// or with all of their labels joined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergeLabel {
+    #[default]
+    Joined,
+    Vertex(Entity),
+}
+
+/// The merge dialog. Its options also apply to contracting an edge,
+/// which keeps the label of its `from` vertex unless labels are joined.
+#[derive(Resource, Debug)]
+pub struct MergeDialogState {
+    pub open: bool,
+    pub label: MergeLabel,
+    pub separator: String,
+    pub drop_loops: bool,
+    pub drop_duplicates: bool,
+}
+
+impl Default for MergeDialogState {
+    fn default() -> Self {
+        MergeDialogState {
+            open: false,
+            label: MergeLabel::Joined,
+            separator: "+".to_string(),
+            drop_loops: true,
+            drop_duplicates: true,
+        }
+    }
+}
+
+impl MergeDialogState {
+    /// The labels joined with the separator, leaving out empty ones.
+    pub fn joined_label<'a>(&self, labels: impl IntoIterator<Item = &'a str>) -> String {
+        labels
+            .into_iter()
+            .filter(|label| !label.is_empty())
+            .collect::<Vec<_>>()
+            .join(&self.separator)
+    }
+}
+
+/// The panels and dialogs that actions can open or toggle.
+#[derive(SystemParam)]
+pub struct PanelStates<'w> {
+    pub statistics: ResMut<'w, StatisticsPanelState>,
+    pub metrics: ResMut<'w, MetricsPanelState>,
+    pub inspector: ResMut<'w, InspectorState>,
+    pub minimap: ResMut<'w, MinimapState>,
+    pub search: ResMut<'w, SearchState>,
+    pub generator: ResMut<'w, GeneratorDialogState>,
+    pub palette: ResMut<'w, CommandPaletteState>,
+    pub settings: ResMut<'w, SettingsWindowState>,
+    pub text_editor: ResMut<'w, TextEditorState>,
+    pub matrix: ResMut<'w, AdjacencyMatrixState>,
+    pub tables: ResMut<'w, TablesState>,
+    pub merge: ResMut<'w, MergeDialogState>,
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::graph::undo_redo::VertexMoveAction;
+
+    fn push_move(stack: &mut UndoRedoStack, world: &mut World) {
+        let action = UndoAction::UndoVertexMoveAction(VertexMoveAction {
+            entity: Entity::PLACEHOLDER,
+            position: Vec2::ZERO,
+        });
+        stack.push_undo(action, &mut world.commands());
+        world.flush();
+    }
+
+    fn undo_all(stack: &mut UndoRedoStack, world: &mut World) {
+        while !stack.undo_stack.is_empty() {
+            stack.undo(world.commands());
+            world.flush();
+        }
+    }
+
+    #[test]
+    fn document_stays_dirty_once_actions_fall_off_the_stack() {
+        let mut world = World::new();
+        let mut stack = UndoRedoStack::default();
+        let document = DocumentState::default();
+        assert!(!document.is_dirty(&stack));
+
+        for _ in 0..=stack.max_size {
+            push_move(&mut stack, &mut world);
+        }
+        assert_eq!(stack.undo_stack.len(), stack.max_size);
+        assert!(document.is_dirty(&stack));
+
+        // The first action can't be undone anymore, so the graph isn't back
+        // to the saved one.
+        undo_all(&mut stack, &mut world);
+        assert!(document.is_dirty(&stack));
+        assert_ne!(stack.state(), 0);
+    }
+
+    #[test]
+    fn document_is_clean_at_a_saved_state_that_fell_off() {
+        let mut world = World::new();
+        let mut stack = UndoRedoStack::default();
+        push_move(&mut stack, &mut world);
+        let document = DocumentState {
+            saved_state: stack.state(),
+            ..default()
+        };
+
+        for _ in 0..stack.max_size {
+            push_move(&mut stack, &mut world);
+        }
+        assert!(document.is_dirty(&stack));
+        // Everything left undone, the graph is the saved one again.
+        undo_all(&mut stack, &mut world);
+        assert!(!document.is_dirty(&stack));
+    }
+
+    #[test]
+    fn unused_handles_are_evicted() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_asset::<ColorMaterial>()
+            .insert_resource(HandleCache::default())
+            .insert_resource(Settings::default())
+            .insert_resource(VertexIds::default());
+
+        // A colour and a radius per value, like a heat map of continuous values.
+        let kept = app
+            .world_mut()
+            .run_system_once(|mut assets: GraphAssets| {
+                for step in 0..100 {
+                    let t = step as f32 / 100.0;
+                    assets.material(Color::srgb(t, 0.0, 0.0));
+                    assets.mesh(MeshKey::vertex(VertexShape::Circle, 10.0 + t, "", 12.0, 0.0));
+                }
+                assets.material(Color::WHITE)
+            })
+            .unwrap();
+        app.world_mut()
+            .resource_mut::<HandleCache>()
+            .evict_unused();
+        app.update();
+
+        let world = app.world();
+        let cache = world.resource::<HandleCache>();
+        assert!(cache.meshes.is_empty());
+        assert_eq!(cache.materials.len(), 1);
+        assert!(world.resource::<Assets<Mesh>>().is_empty());
+        let materials = world.resource::<Assets<ColorMaterial>>();
+        assert_eq!(materials.len(), 1);
+        assert!(materials.contains(&kept));
+    }
+}
@@ -0,0 +1,83 @@
+use bevy::prelude::*;
+use regex::{Regex, RegexBuilder};
+
+use crate::graph::components::Attribute;
+
+/// What the search box looks for, always ignoring case.
+#[derive(Debug, Clone)]
+pub enum SearchPattern {
+    /// Kept in lowercase.
+    Substring(String),
+    Regex(Regex),
+}
+
+impl SearchPattern {
+    pub fn new(query: &str, regex: bool) -> Result<Self, regex::Error> {
+        if regex {
+            let regex = RegexBuilder::new(query).case_insensitive(true).build()?;
+            Ok(SearchPattern::Regex(regex))
+        } else {
+            Ok(SearchPattern::Substring(query.to_lowercase()))
+        }
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            SearchPattern::Substring(query) => text.to_lowercase().contains(query),
+            SearchPattern::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+/// Where a vertex matched, the label wins over its attributes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchedField {
+    Label,
+    /// The key of the first attribute whose value matched.
+    Attribute(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchMatch {
+    pub entity: Entity,
+    /// The label, or a placeholder for unnamed vertices.
+    pub name: String,
+    pub field: MatchedField,
+}
+
+/// Checks the label of a vertex first, then the values of its attributes.
+pub fn match_vertex(
+    pattern: &SearchPattern,
+    label: &str,
+    attributes: &[Attribute],
+) -> Option<MatchedField> {
+    if pattern.is_match(label) {
+        return Some(MatchedField::Label);
+    }
+    attributes
+        .iter()
+        .find(|attribute| pattern.is_match(&attribute.value.to_string()))
+        .map(|attribute| MatchedField::Attribute(attribute.key.clone()))
+}
+
+/// How well `query` matches `text` as a case-insensitive subsequence,
+/// `None` if it doesn't. Consecutive characters and word starts score
+/// higher, skipped characters lower. Whitespace in the query is ignored.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (next..text.len()).find(|index| text[*index] == wanted)?;
+        score += 1;
+        if found > 0 && found == next {
+            score += 4;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        score -= (found - next).min(3) as i32;
+        next = found + 1;
+    }
+    Some(score)
+}
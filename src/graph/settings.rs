@@ -0,0 +1,170 @@
+use std::{fs, io::ErrorKind, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::graph::constants::{CONFIG_DIRECTORY, SETTINGS_FILE};
+
+/// The colours everything on the canvas is drawn with, unless
+/// a vertex or an edge has a style of its own.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub background: Color,
+    pub vertex: Color,
+    pub hovered_vertex: Color,
+    pub vertex_stroke: Color,
+    pub edge: Color,
+    pub hovered_edge: Color,
+    pub temporary_edge: Color,
+    /// Vertex labels and edge weights.
+    pub text: Color,
+    pub selection: Color,
+    pub area_selection: Color,
+    pub search_match: Color,
+    pub minimap_viewport: Color,
+}
+
+impl Palette {
+    pub const DARK: Palette = Palette {
+        background: Color::srgb(0.15, 0.15, 0.17),
+        vertex: Color::srgb(0.3, 0.6, 0.9),
+        hovered_vertex: Color::srgb(0.4, 0.8, 1.0),
+        vertex_stroke: Color::srgb(0.9, 0.9, 0.95),
+        edge: Color::srgb(0.4, 0.45, 0.5),
+        hovered_edge: Color::srgb(0.6, 0.65, 0.6),
+        temporary_edge: Color::srgb(0.95, 0.7, 0.2),
+        text: Color::WHITE,
+        selection: Color::srgb(1.0, 0.85, 0.3),
+        area_selection: Color::srgb(0.6, 0.8, 1.0),
+        search_match: Color::srgb(0.4, 0.95, 0.6),
+        minimap_viewport: Color::srgb(0.95, 0.95, 0.95),
+    };
+
+    pub const LIGHT: Palette = Palette {
+        background: Color::srgb(0.96, 0.96, 0.94),
+        vertex: Color::srgb(0.2, 0.45, 0.8),
+        hovered_vertex: Color::srgb(0.35, 0.6, 0.95),
+        vertex_stroke: Color::srgb(0.15, 0.15, 0.2),
+        edge: Color::srgb(0.6, 0.62, 0.66),
+        hovered_edge: Color::srgb(0.35, 0.4, 0.45),
+        temporary_edge: Color::srgb(0.9, 0.5, 0.1),
+        text: Color::srgb(0.1, 0.1, 0.12),
+        selection: Color::srgb(0.9, 0.55, 0.0),
+        area_selection: Color::srgb(0.2, 0.45, 0.8),
+        search_match: Color::srgb(0.1, 0.6, 0.3),
+        minimap_viewport: Color::srgb(0.2, 0.2, 0.25),
+    };
+
+    pub const HIGH_CONTRAST: Palette = Palette {
+        background: Color::BLACK,
+        vertex: Color::srgb(1.0, 1.0, 0.0),
+        hovered_vertex: Color::srgb(0.0, 1.0, 1.0),
+        vertex_stroke: Color::WHITE,
+        edge: Color::WHITE,
+        hovered_edge: Color::srgb(0.0, 1.0, 1.0),
+        temporary_edge: Color::srgb(1.0, 0.5, 0.0),
+        text: Color::WHITE,
+        selection: Color::srgb(1.0, 0.0, 1.0),
+        area_selection: Color::srgb(0.0, 1.0, 0.0),
+        search_match: Color::srgb(0.0, 1.0, 0.0),
+        minimap_viewport: Color::WHITE,
+    };
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Dark, Theme::Light, Theme::HighContrast];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+            Theme::HighContrast => "High contrast",
+        }
+    }
+
+    pub fn palette(&self) -> Palette {
+        match self {
+            Theme::Dark => Palette::DARK,
+            Theme::Light => Palette::LIGHT,
+            Theme::HighContrast => Palette::HIGH_CONTRAST,
+        }
+    }
+}
+
+/// Application wide preferences, as opposed to the document. Stored in
+/// the config directory of the user, see `settings_path`. Missing fields
+/// keep their default, so older files still load.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: Theme,
+    pub vertex_label_font_size: f32,
+    pub edge_weight_font_size: f32,
+    /// Size of the vertices and edges without a style of their own.
+    pub vertex_radius: f32,
+    pub edge_width: f32,
+    /// Seconds between the clicks of a double click.
+    pub consecutive_click_time: f64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            theme: Theme::Dark,
+            vertex_label_font_size: 12.0,
+            edge_weight_font_size: 14.0,
+            vertex_radius: 10.0,
+            edge_width: 10.0,
+            consecutive_click_time: 0.3,
+        }
+    }
+}
+
+impl Settings {
+    pub fn palette(&self) -> Palette {
+        self.theme.palette()
+    }
+
+    /// The saved settings, the defaults if there are none or they can't be read.
+    pub fn load() -> Self {
+        let Some(path) = settings_path() else {
+            return Settings::default();
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Settings::default(),
+            Err(error) => {
+                warn!("Could not read {}: {error}", path.display());
+                return Settings::default();
+            }
+        };
+        ron::from_str(&text).unwrap_or_else(|error| {
+            warn!("Could not parse {}: {error}", path.display());
+            Settings::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let path = settings_path().ok_or("No config directory found")?;
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(|error| error.to_string())?;
+        }
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())?;
+        fs::write(&path, text).map_err(|error| error.to_string())?;
+        Ok(path)
+    }
+}
+
+/// Where the settings live, e.g. `~/.config/body_graph/settings.ron` on Linux.
+pub fn settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|directory| directory.join(CONFIG_DIRECTORY).join(SETTINGS_FILE))
+}
@@ -0,0 +1,499 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use crate::graph::{
+    components::VertexShape,
+    helpers::{label_width, rounded_box_length},
+};
+
+/// Hit area of a vertex: a capsule around its position, which is a
+/// circle for every shape except the rounded box around its label.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VertexBounds {
+    pub center: Vec2,
+    pub radius: f32,
+    /// Half of the straight part of a rounded box, zero otherwise.
+    pub half_length: f32,
+}
+
+impl VertexBounds {
+    pub fn new(center: Vec2, shape: VertexShape, radius: f32, label: &str, font_size: f32) -> Self {
+        let half_length = match shape {
+            VertexShape::RoundedBox => {
+                rounded_box_length(radius, label_width(label, font_size)) / 2.0
+            }
+            _ => 0.0,
+        };
+        VertexBounds {
+            center,
+            radius,
+            half_length,
+        }
+    }
+
+    /// Distance of `point` from the outline, negative inside of it.
+    pub fn distance(&self, point: Vec2) -> f32 {
+        let offset = point - self.center;
+        let along = offset.x.clamp(-self.half_length, self.half_length);
+        offset.distance(Vec2::new(along, 0.0)) - self.radius
+    }
+
+    fn rect(&self) -> Rect {
+        let half_size = Vec2::new(self.half_length + self.radius, self.radius);
+        Rect::from_center_half_size(self.center, half_size)
+    }
+}
+
+/// The segment of an edge between the centres of its vertices.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeBounds {
+    pub from: Vec2,
+    pub to: Vec2,
+    pub width: f32,
+}
+
+impl EdgeBounds {
+    /// Distance of `point` from the sides of the edge, negative inside of it.
+    pub fn distance(&self, point: Vec2) -> f32 {
+        closest_on_segment(self.from, self.to, point).distance(point) - self.width / 2.0
+    }
+
+    fn intersects_rect(&self, rect: Rect) -> bool {
+        let rect = rect.inflate(self.width / 2.0);
+        segment_intersects_rect(self.from, self.to, rect)
+    }
+}
+
+/// A uniform grid over the canvas, so hover, hit testing and area
+/// selections only look at the few vertices and edges around a point
+/// instead of all of them. Kept up to date from `Position` and the edge
+/// transforms by `update_spatial_index`.
+#[derive(Resource, Debug)]
+pub struct SpatialIndex {
+    cell_size: f32,
+    vertices: HashMap<Entity, (VertexBounds, Vec<IVec2>)>,
+    edges: HashMap<Entity, (EdgeBounds, Vec<IVec2>)>,
+    vertex_cells: HashMap<IVec2, Vec<Entity>>,
+    edge_cells: HashMap<IVec2, Vec<Entity>>,
+}
+
+impl SpatialIndex {
+    pub fn new(cell_size: f32) -> Self {
+        SpatialIndex {
+            cell_size,
+            vertices: HashMap::new(),
+            edges: HashMap::new(),
+            vertex_cells: HashMap::new(),
+            edge_cells: HashMap::new(),
+        }
+    }
+
+    /// Inserts a vertex, or moves it if it is already indexed.
+    pub fn insert_vertex(&mut self, entity: Entity, bounds: VertexBounds) {
+        self.remove_vertex(entity);
+        let cells: Vec<IVec2> = self.cells_in_rect(bounds.rect()).collect();
+        for cell in &cells {
+            self.vertex_cells.entry(*cell).or_default().push(entity);
+        }
+        self.vertices.insert(entity, (bounds, cells));
+    }
+
+    pub fn remove_vertex(&mut self, entity: Entity) {
+        if let Some((_, cells)) = self.vertices.remove(&entity) {
+            remove_from_cells(&mut self.vertex_cells, &cells, entity);
+        }
+    }
+
+    /// Inserts an edge, or moves it if it is already indexed.
+    pub fn insert_edge(&mut self, entity: Entity, bounds: EdgeBounds) {
+        self.remove_edge(entity);
+        let cells = self.cells_along_segment(bounds.from, bounds.to, bounds.width / 2.0);
+        for cell in &cells {
+            self.edge_cells.entry(*cell).or_default().push(entity);
+        }
+        self.edges.insert(entity, (bounds, cells));
+    }
+
+    pub fn remove_edge(&mut self, entity: Entity) {
+        if let Some((_, cells)) = self.edges.remove(&entity) {
+            remove_from_cells(&mut self.edge_cells, &cells, entity);
+        }
+    }
+
+    /// The vertex whose outline is closest to `point`, if it is at most
+    /// `radius` away. A `radius` of zero only finds vertices under the point.
+    pub fn nearest_vertex(&self, point: Vec2, radius: f32) -> Option<Entity> {
+        let area = Rect::from_center_half_size(point, Vec2::splat(radius));
+        self.vertex_candidates(area)
+            .into_iter()
+            .filter_map(|entity| {
+                let distance = self.vertices[&entity].0.distance(point);
+                (distance <= radius).then_some((entity, distance))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(entity, _)| entity)
+    }
+
+    /// The edge whose sides are closest to `point`, if it is at most `radius` away.
+    pub fn nearest_edge(&self, point: Vec2, radius: f32) -> Option<Entity> {
+        let area = Rect::from_center_half_size(point, Vec2::splat(radius));
+        self.edges_in_rect(area)
+            .into_iter()
+            .map(|entity| (entity, self.edges[&entity].0.distance(point)))
+            .filter(|(_, distance)| *distance <= radius)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(entity, _)| entity)
+    }
+
+    /// Vertices whose centre is inside of `rect`.
+    pub fn vertices_in_rect(&self, rect: Rect) -> Vec<Entity> {
+        self.vertex_candidates(rect)
+            .into_iter()
+            .filter(|entity| rect.contains(self.vertices[entity].0.center))
+            .collect()
+    }
+
+    /// Edges that cross or touch `rect`.
+    pub fn edges_in_rect(&self, rect: Rect) -> Vec<Entity> {
+        self.edge_candidates(rect)
+            .into_iter()
+            .filter(|entity| self.edges[entity].0.intersects_rect(rect))
+            .collect()
+    }
+
+    /// Vertices whose centre is inside of the polygon, e.g. a lasso.
+    /// The polygon is closed automatically and may intersect itself.
+    pub fn vertices_in_polygon(&self, polygon: &[Vec2]) -> Vec<Entity> {
+        if polygon.len() < 3 {
+            return Vec::new();
+        }
+        let bounding_rect = polygon.iter().fold(
+            Rect::from_center_size(polygon[0], Vec2::ZERO),
+            |rect, point| rect.union_point(*point),
+        );
+        self.vertex_candidates(bounding_rect)
+            .into_iter()
+            .filter(|entity| point_in_polygon(self.vertices[entity].0.center, polygon))
+            .collect()
+    }
+
+    fn vertex_candidates(&self, area: Rect) -> HashSet<Entity> {
+        self.candidates(&self.vertex_cells, area)
+    }
+
+    fn edge_candidates(&self, area: Rect) -> HashSet<Entity> {
+        self.candidates(&self.edge_cells, area)
+    }
+
+    /// Everything in the cells of `area`. An area with more cells than are
+    /// occupied, like a selection while zoomed out, goes through the
+    /// occupied cells instead of its own.
+    fn candidates(&self, cells: &HashMap<IVec2, Vec<Entity>>, area: Rect) -> HashSet<Entity> {
+        let (min, max) = (self.cell(area.min), self.cell(area.max));
+        let size = max.as_i64vec2() - min.as_i64vec2() + 1;
+        if size.x.saturating_mul(size.y) > cells.len() as i64 {
+            cells
+                .iter()
+                .filter(|(cell, _)| cell.cmpge(min).all() && cell.cmple(max).all())
+                .flat_map(|(_, entities)| entities)
+                .copied()
+                .collect()
+        } else {
+            self.cells_in_rect(area)
+                .filter_map(|cell| cells.get(&cell))
+                .flatten()
+                .copied()
+                .collect()
+        }
+    }
+
+    fn cell(&self, point: Vec2) -> IVec2 {
+        (point / self.cell_size).floor().as_ivec2()
+    }
+
+    fn cells_in_rect(&self, rect: Rect) -> impl Iterator<Item = IVec2> + use<> {
+        let (min, max) = (self.cell(rect.min), self.cell(rect.max));
+        (min.x..=max.x).flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+    }
+
+    /// Every cell a segment widened by `padding` passes through. Walks the
+    /// columns of the grid, so long edges don't cover their whole bounding box.
+    fn cells_along_segment(&self, from: Vec2, to: Vec2, padding: f32) -> Vec<IVec2> {
+        let (left, right) = if from.x <= to.x {
+            (from, to)
+        } else {
+            (to, from)
+        };
+        let height_at = |x: f32| {
+            if right.x - left.x <= f32::EPSILON {
+                return (left.y.min(right.y), left.y.max(right.y));
+            }
+            let t = ((x - left.x) / (right.x - left.x)).clamp(0.0, 1.0);
+            let y = left.y + (right.y - left.y) * t;
+            (y, y)
+        };
+
+        let mut cells = Vec::new();
+        let (first, last) = (
+            self.cell(Vec2::new(left.x - padding, 0.0)).x,
+            self.cell(Vec2::new(right.x + padding, 0.0)).x,
+        );
+        for column in first..=last {
+            let start = (column as f32 * self.cell_size).max(left.x - padding);
+            let end = ((column + 1) as f32 * self.cell_size).min(right.x + padding);
+            let (start_low, start_high) = height_at(start);
+            let (end_low, end_high) = height_at(end);
+            let low = self
+                .cell(Vec2::new(0.0, start_low.min(end_low) - padding))
+                .y;
+            let high = self
+                .cell(Vec2::new(0.0, start_high.max(end_high) + padding))
+                .y;
+            cells.extend((low..=high).map(|row| IVec2::new(column, row)));
+        }
+        cells
+    }
+}
+
+fn remove_from_cells(cells: &mut HashMap<IVec2, Vec<Entity>>, from: &[IVec2], entity: Entity) {
+    for cell in from {
+        if let Some(entities) = cells.get_mut(cell) {
+            entities.retain(|other| *other != entity);
+            if entities.is_empty() {
+                cells.remove(cell);
+            }
+        }
+    }
+}
+
+fn closest_on_segment(from: Vec2, to: Vec2, point: Vec2) -> Vec2 {
+    let direction = to - from;
+    let length_squared = direction.length_squared();
+    if length_squared <= f32::EPSILON {
+        return from;
+    }
+    let t = ((point - from).dot(direction) / length_squared).clamp(0.0, 1.0);
+    from + direction * t
+}
+
+/// Clips the segment against the rectangle (Liang–Barsky).
+fn segment_intersects_rect(from: Vec2, to: Vec2, rect: Rect) -> bool {
+    let direction = to - from;
+    let (mut enter, mut exit) = (0.0_f32, 1.0_f32);
+    for (delta, near, far) in [
+        (direction.x, rect.min.x - from.x, rect.max.x - from.x),
+        (direction.y, rect.min.y - from.y, rect.max.y - from.y),
+    ] {
+        if delta.abs() <= f32::EPSILON {
+            if near > 0.0 || far < 0.0 {
+                return false;
+            }
+            continue;
+        }
+        let (mut t0, mut t1) = (near / delta, far / delta);
+        if t0 > t1 {
+            std::mem::swap(&mut t0, &mut t1);
+        }
+        enter = enter.max(t0);
+        exit = exit.min(t1);
+        if enter > exit {
+            return false;
+        }
+    }
+    true
+}
+
+/// Even-odd rule, so self intersecting lassos behave like a fill would.
+fn point_in_polygon(point: Vec2, polygon: &[Vec2]) -> bool {
+    let mut inside = false;
+    let mut previous = polygon[polygon.len() - 1];
+    for current in polygon {
+        if (current.y > point.y) != (previous.y > point.y) {
+            let x = current.x
+                + (point.y - current.y) / (previous.y - current.y) * (previous.x - current.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+        previous = *current;
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CELL_SIZE: f32 = 100.0;
+
+    fn entities(count: usize) -> Vec<Entity> {
+        let mut world = World::new();
+        (0..count).map(|_| world.spawn_empty().id()).collect()
+    }
+
+    fn vertex(center: Vec2) -> VertexBounds {
+        VertexBounds::new(center, VertexShape::Circle, 5.0, "", 12.0)
+    }
+
+    fn edge(from: Vec2, to: Vec2) -> EdgeBounds {
+        EdgeBounds {
+            from,
+            to,
+            width: 2.0,
+        }
+    }
+
+    fn sorted(mut found: Vec<Entity>) -> Vec<Entity> {
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn nearest_vertex_across_cell_boundaries() {
+        let [right, left] = entities(2)[..] else {
+            unreachable!()
+        };
+        let mut index = SpatialIndex::new(CELL_SIZE);
+        // Just left of the boundary between two cells, and just below the origin.
+        index.insert_vertex(right, vertex(Vec2::new(99.0, 50.0)));
+        index.insert_vertex(left, vertex(Vec2::new(-1.0, -1.0)));
+
+        // The point is in the next cell, but still on the vertex.
+        assert_eq!(
+            index.nearest_vertex(Vec2::new(102.0, 50.0), 0.0),
+            Some(right)
+        );
+        assert_eq!(index.nearest_vertex(Vec2::new(1.0, 1.0), 0.0), Some(left));
+        // Within the radius of the outline, but not of the centre.
+        assert_eq!(
+            index.nearest_vertex(Vec2::new(110.0, 50.0), 6.0),
+            Some(right)
+        );
+        assert_eq!(index.nearest_vertex(Vec2::new(110.0, 50.0), 5.0), None);
+        // Both are in range, the closer one wins.
+        assert_eq!(
+            index.nearest_vertex(Vec2::new(40.0, 20.0), 100.0),
+            Some(left)
+        );
+
+        index.insert_vertex(right, vertex(Vec2::new(500.0, 500.0)));
+        assert_eq!(index.nearest_vertex(Vec2::new(102.0, 50.0), 0.0), None);
+        index.remove_vertex(left);
+        assert_eq!(index.nearest_vertex(Vec2::new(1.0, 1.0), 0.0), None);
+    }
+
+    #[test]
+    fn edges_crossing_a_rect_without_endpoints_inside() {
+        let [through, vertical, diagonal, outside, missing] = entities(5)[..] else {
+            unreachable!()
+        };
+        let mut index = SpatialIndex::new(CELL_SIZE);
+        // Both ends are several cells away from the rectangle.
+        index.insert_edge(
+            through,
+            edge(Vec2::new(-500.0, 50.0), Vec2::new(500.0, 50.0)),
+        );
+        index.insert_edge(
+            vertical,
+            edge(Vec2::new(50.0, -300.0), Vec2::new(50.0, 300.0)),
+        );
+        index.insert_edge(
+            diagonal,
+            edge(Vec2::new(-40.0, 120.0), Vec2::new(120.0, -40.0)),
+        );
+        index.insert_edge(
+            outside,
+            edge(Vec2::new(150.0, -300.0), Vec2::new(150.0, 300.0)),
+        );
+        // Its bounding box overlaps the rectangle, the segment passes its corner.
+        index.insert_edge(
+            missing,
+            edge(Vec2::new(60.0, 250.0), Vec2::new(250.0, 60.0)),
+        );
+
+        let rect = Rect::new(0.0, 0.0, 100.0, 100.0);
+        assert_eq!(
+            sorted(index.edges_in_rect(rect)),
+            sorted(vec![through, vertical, diagonal])
+        );
+        // Touching the sides counts, since edges have a width.
+        assert_eq!(
+            index.edges_in_rect(Rect::new(151.0, 260.0, 200.0, 290.0)),
+            vec![outside]
+        );
+        assert_eq!(
+            index.nearest_edge(Vec2::new(-200.0, 55.0), 10.0),
+            Some(through)
+        );
+        assert_eq!(index.nearest_edge(Vec2::new(120.0, 120.0), 10.0), None);
+    }
+
+    #[test]
+    fn huge_areas_only_look_at_occupied_cells() {
+        let [near, far, outside] = entities(3)[..] else {
+            unreachable!()
+        };
+        let mut index = SpatialIndex::new(CELL_SIZE);
+        index.insert_vertex(near, vertex(Vec2::new(10.0, 10.0)));
+        index.insert_vertex(far, vertex(Vec2::new(-4.0e7, 3.0e7)));
+        index.insert_vertex(outside, vertex(Vec2::new(6.0e7, 0.0)));
+        index.insert_edge(near, edge(Vec2::new(10.0, 10.0), Vec2::new(500.0, 10.0)));
+
+        // Far more cells than could be gone through one by one.
+        let area = Rect::new(-5.0e7, -5.0e7, 5.0e7, 5.0e7);
+        assert_eq!(
+            sorted(index.vertices_in_rect(area)),
+            sorted(vec![near, far])
+        );
+        assert_eq!(index.edges_in_rect(area), [near]);
+        let everything = Rect::new(f32::MIN, f32::MIN, f32::MAX, f32::MAX);
+        assert_eq!(index.vertices_in_rect(everything).len(), 3);
+    }
+
+    #[test]
+    fn vertices_in_concave_lasso() {
+        let [left_arm, notch, base, right_arm, outside] = entities(5)[..] else {
+            unreachable!()
+        };
+        let mut index = SpatialIndex::new(CELL_SIZE);
+        index.insert_vertex(left_arm, vertex(Vec2::new(50.0, 250.0)));
+        index.insert_vertex(notch, vertex(Vec2::new(150.0, 250.0)));
+        index.insert_vertex(base, vertex(Vec2::new(150.0, 50.0)));
+        index.insert_vertex(right_arm, vertex(Vec2::new(250.0, 250.0)));
+        index.insert_vertex(outside, vertex(Vec2::new(350.0, 50.0)));
+
+        // A U shape, the notch is inside of its bounding box but not of the lasso.
+        let lasso = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(300.0, 0.0),
+            Vec2::new(300.0, 300.0),
+            Vec2::new(200.0, 300.0),
+            Vec2::new(200.0, 100.0),
+            Vec2::new(100.0, 100.0),
+            Vec2::new(100.0, 300.0),
+            Vec2::new(0.0, 300.0),
+        ];
+        assert_eq!(
+            sorted(index.vertices_in_polygon(&lasso)),
+            sorted(vec![left_arm, base, right_arm])
+        );
+
+        // Where a self intersecting lasso overlaps itself counts as outside.
+        let overlapping = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(200.0, 0.0),
+            Vec2::new(200.0, 300.0),
+            Vec2::new(100.0, 300.0),
+            Vec2::new(100.0, 200.0),
+            Vec2::new(300.0, 200.0),
+            Vec2::new(300.0, 300.0),
+            Vec2::new(0.0, 300.0),
+        ];
+        assert_eq!(
+            sorted(index.vertices_in_polygon(&overlapping)),
+            sorted(vec![left_arm, base, right_arm])
+        );
+        assert!(index.vertices_in_polygon(&lasso[..2]).is_empty());
+    }
+}
@@ -1,9 +1,11 @@
-use bevy::prelude::*;
+use bevy::{camera::visibility::NoFrustumCulling, prelude::*};
 
 use crate::graph::{
-    components::{Canvas, TemporaryDirectedEdge},
-    constants::{BG_COLOR, EDGE_SHAPE, TEMP_EDGE_COLOR},
+    components::{Canvas, EdgeBatch, TemporaryDirectedEdge},
+    constants::{EDGE_BATCH_Z, EDGE_SHAPE},
+    helpers::edge_batch_mesh,
     picking_observers::*,
+    settings::Settings,
 };
 
 /// Spawns the canvas containing the graph.
@@ -11,14 +13,24 @@ pub fn spawn_canvas(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    window: Single<&Window>,
+    settings: Res<Settings>,
 ) {
-    let bg_mesh = meshes.add(Rectangle::from_size(window.size()));
-    let bg_material = materials.add(BG_COLOR);
+    // A unit square, `fit_canvas_to_camera` scales it to the visible area.
+    let bg_mesh = meshes.add(Rectangle::from_size(Vec2::ONE));
+    let bg_material = materials.add(settings.palette().background);
 
+    // Only entities with `Pickable` are mesh picked, see `MeshPickingSettings`.
     commands
-        .spawn((Canvas, Mesh2d(bg_mesh), MeshMaterial2d(bg_material)))
-        .observe(on_canvas_clicked);
+        .spawn((
+            Canvas,
+            Mesh2d(bg_mesh),
+            MeshMaterial2d(bg_material),
+            Pickable::default(),
+        ))
+        .observe(on_canvas_clicked)
+        .observe(on_canvas_drag_start)
+        .observe(on_canvas_dragging)
+        .observe(on_canvas_drag_end);
 }
 
 /// Spawns the temporary edge with a `None` source.
@@ -26,6 +38,7 @@ pub fn spawn_temporary_edge(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    settings: Res<Settings>,
 ) {
     commands.spawn((
         TemporaryDirectedEdge {
@@ -33,6 +46,23 @@ pub fn spawn_temporary_edge(
             to: Vec2::ZERO,
         },
         Mesh2d(meshes.add(EDGE_SHAPE)),
-        MeshMaterial2d(materials.add(TEMP_EDGE_COLOR)),
+        MeshMaterial2d(materials.add(settings.palette().temporary_edge)),
+    ));
+}
+
+/// Spawns the `EdgeBatch`, empty until the first edges are spawned.
+/// Its mesh changes as edges move, so its bounds are never up to date.
+pub fn spawn_edge_batch(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    settings: Res<Settings>,
+) {
+    commands.spawn((
+        EdgeBatch::default(),
+        Mesh2d(meshes.add(edge_batch_mesh(&[]))),
+        MeshMaterial2d(materials.add(settings.palette().edge)),
+        Transform::from_xyz(0.0, 0.0, EDGE_BATCH_Z),
+        NoFrustumCulling,
     ));
 }
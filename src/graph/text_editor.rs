@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+/// The id of a vertex in the text editor. Entity indices don't change while
+/// the editor runs, also not when a vertex is deleted and restored by undo.
+pub fn vertex_id(entity: Entity) -> String {
+    format!("n{}", entity.index())
+}
+
+/// A DOT-like `digraph` with a statement per vertex and per edge,
+/// sorted by id so the text doesn't jump around as the graph changes.
+pub fn graph_to_text<'a>(
+    vertices: impl Iterator<Item = (Entity, &'a str)>,
+    edges: impl Iterator<Item = (Entity, Entity, Option<f32>)>,
+) -> String {
+    let mut vertices: Vec<_> = vertices.collect();
+    vertices.sort_by_key(|(entity, _)| entity.index());
+    let mut edges: Vec<_> = edges.collect();
+    edges.sort_by_key(|(from, to, _)| (from.index(), to.index()));
+
+    let mut text = String::from("digraph {\n");
+    for (entity, label) in vertices {
+        text.push_str(&format!(
+            "    {} [label=\"{}\"]\n",
+            vertex_id(entity),
+            escape(label)
+        ));
+    }
+    for (from, to, weight) in edges {
+        text.push_str(&format!("    {} -> {}", vertex_id(from), vertex_id(to)));
+        if let Some(weight) = weight {
+            text.push_str(&format!(" [weight={weight}]"));
+        }
+        text.push('\n');
+    }
+    text.push_str("}\n");
+    text
+}
+
+/// A node of the text, `label` is `None` if no statement sets it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextNode {
+    pub id: String,
+    pub label: Option<String>,
+}
+
+/// Edges refer to nodes by their index in `GraphText::nodes`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdge {
+    pub from: usize,
+    pub to: usize,
+    pub weight: Option<f32>,
+}
+
+/// What the text describes, nodes in the order they first appear.
+#[derive(Debug, Default, PartialEq)]
+pub struct GraphText {
+    pub nodes: Vec<TextNode>,
+    pub edges: Vec<TextEdge>,
+}
+
+impl GraphText {
+    fn node(&mut self, ids: &mut HashMap<String, usize>, id: &str) -> usize {
+        *ids.entry(id.to_string()).or_insert_with(|| {
+            self.nodes.push(TextNode {
+                id: id.to_string(),
+                label: None,
+            });
+            self.nodes.len() - 1
+        })
+    }
+}
+
+/// Reads the subset of DOT the editor writes: node statements with a
+/// label, chains of `->` edges with a weight, optionally wrapped in
+/// `digraph { }`. Other attributes and default statements are ignored,
+/// semicolons are optional and `//`, `#` and `/* */` are comments.
+pub fn parse_graph_text(text: &str) -> Result<GraphText, String> {
+    let tokens = tokenize(text)?;
+    let mut tokens = tokens.iter().peekable();
+    let mut graph = GraphText::default();
+    let mut ids = HashMap::new();
+
+    let mut braced = false;
+    if let Some((_, Token::Id(keyword))) = tokens.peek()
+        && matches!(keyword.as_str(), "strict" | "digraph" | "graph")
+    {
+        for (line, token) in tokens.by_ref() {
+            match token {
+                Token::Symbol('{') => break,
+                Token::Id(_) => {}
+                _ => return Err(format!("Line {line}: expected '{{' after the graph header")),
+            }
+        }
+        braced = true;
+    }
+
+    loop {
+        let Some((line, token)) = tokens.next() else {
+            if braced {
+                return Err("The graph is missing its closing '}'".to_string());
+            }
+            return Ok(graph);
+        };
+        let line = *line;
+        let id = match token {
+            Token::Symbol(';') => continue,
+            Token::Symbol('}') if braced => {
+                return match tokens.next() {
+                    None => Ok(graph),
+                    Some((line, _)) => Err(format!("Line {line}: text after the closing '}}'")),
+                };
+            }
+            Token::Id(id) | Token::Quoted(id) => id,
+            _ => return Err(format!("Line {line}: expected a node id, found {token}")),
+        };
+
+        // Defaults for all nodes or edges, and attributes of the graph.
+        if matches!(id.as_str(), "node" | "edge" | "graph")
+            && matches!(tokens.peek(), Some((_, Token::Symbol('['))))
+        {
+            attributes(&mut tokens)?;
+            continue;
+        }
+        if matches!(tokens.peek(), Some((_, Token::Symbol('=')))) {
+            tokens.next();
+            value(&mut tokens, line)?;
+            continue;
+        }
+
+        let mut chain = vec![graph.node(&mut ids, id)];
+        while matches!(tokens.peek(), Some((_, Token::Arrow))) {
+            tokens.next();
+            match tokens.next() {
+                Some((_, Token::Id(id) | Token::Quoted(id))) => {
+                    chain.push(graph.node(&mut ids, id))
+                }
+                _ => return Err(format!("Line {line}: expected a node id after '->'")),
+            }
+        }
+        let attributes = if matches!(tokens.peek(), Some((_, Token::Symbol('[')))) {
+            attributes(&mut tokens)?
+        } else {
+            Vec::new()
+        };
+
+        if chain.len() == 1 {
+            if let Some((_, label)) = attributes.iter().rev().find(|(key, _)| key == "label") {
+                graph.nodes[chain[0]].label = Some(label.clone());
+            }
+            continue;
+        }
+        let weight = match attributes.iter().rev().find(|(key, _)| key == "weight") {
+            Some((_, weight)) => Some(
+                weight
+                    .parse()
+                    .map_err(|_| format!("Line {line}: '{weight}' is not a weight"))?,
+            ),
+            None => None,
+        };
+        for pair in chain.windows(2) {
+            graph.edges.push(TextEdge {
+                from: pair[0],
+                to: pair[1],
+                weight,
+            });
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Id(String),
+    Quoted(String),
+    Arrow,
+    Symbol(char),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Id(id) => write!(f, "'{id}'"),
+            Token::Quoted(text) => write!(f, "\"{text}\""),
+            Token::Arrow => write!(f, "'->'"),
+            Token::Symbol(symbol) => write!(f, "'{symbol}'"),
+        }
+    }
+}
+
+type Tokens<'a> = std::iter::Peekable<std::slice::Iter<'a, (usize, Token)>>;
+
+/// `[key=value, ...]`, commas and semicolons between them are optional.
+fn attributes(tokens: &mut Tokens) -> Result<Vec<(String, String)>, String> {
+    let mut attributes = Vec::new();
+    let Some((line, _)) = tokens.next() else {
+        return Ok(attributes);
+    };
+    loop {
+        match tokens.next() {
+            Some((_, Token::Symbol(']'))) => return Ok(attributes),
+            Some((_, Token::Symbol(',' | ';'))) => {}
+            Some((line, Token::Id(key) | Token::Quoted(key))) => {
+                match tokens.next() {
+                    Some((_, Token::Symbol('='))) => {}
+                    _ => return Err(format!("Line {line}: expected '=' after '{key}'")),
+                }
+                attributes.push((key.clone(), value(tokens, *line)?));
+            }
+            Some((line, token)) => {
+                return Err(format!("Line {line}: expected an attribute, found {token}"));
+            }
+            None => return Err(format!("Line {line}: the attribute list is never closed")),
+        }
+    }
+}
+
+fn value(tokens: &mut Tokens, line: usize) -> Result<String, String> {
+    match tokens.next() {
+        Some((_, Token::Id(value) | Token::Quoted(value))) => Ok(value.clone()),
+        _ => Err(format!("Line {line}: expected a value after '='")),
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, String> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut characters = text.chars().peekable();
+    while let Some(character) = characters.next() {
+        match character {
+            '\n' => line += 1,
+            _ if character.is_whitespace() => {}
+            '#' => while characters.next_if(|next| *next != '\n').is_some() {},
+            '/' if characters.next_if_eq(&'/').is_some() => {
+                while characters.next_if(|next| *next != '\n').is_some() {}
+            }
+            '/' if characters.next_if_eq(&'*').is_some() => {
+                let start = line;
+                let mut previous = ' ';
+                loop {
+                    match characters.next() {
+                        Some('/') if previous == '*' => break,
+                        Some(next) => {
+                            if next == '\n' {
+                                line += 1;
+                            }
+                            previous = next;
+                        }
+                        None => return Err(format!("Line {start}: the comment is never closed")),
+                    }
+                }
+            }
+            '-' if characters.next_if_eq(&'>').is_some() => tokens.push((line, Token::Arrow)),
+            '"' => {
+                let start = line;
+                let mut quoted = String::new();
+                loop {
+                    match characters.next() {
+                        Some('"') => break,
+                        Some('\\') => match characters.next() {
+                            Some('n') => quoted.push('\n'),
+                            Some(escaped) => quoted.push(escaped),
+                            None => {}
+                        },
+                        Some(next) => {
+                            if next == '\n' {
+                                line += 1;
+                            }
+                            quoted.push(next);
+                        }
+                        None => return Err(format!("Line {start}: the string is never closed")),
+                    }
+                }
+                tokens.push((start, Token::Quoted(quoted)));
+            }
+            '{' | '}' | '[' | ']' | '=' | ',' | ';' => {
+                tokens.push((line, Token::Symbol(character)))
+            }
+            _ if is_id_character(character) => {
+                let mut id = String::from(character);
+                while let Some(next) = characters.next_if(|next| is_id_character(*next)) {
+                    id.push(next);
+                }
+                // `a->b` without spaces.
+                if id.len() > 1 && id.ends_with('-') && characters.next_if_eq(&'>').is_some() {
+                    id.pop();
+                    tokens.push((line, Token::Id(id)));
+                    tokens.push((line, Token::Arrow));
+                } else {
+                    tokens.push((line, Token::Id(id)));
+                }
+            }
+            _ => return Err(format!("Line {line}: unexpected '{character}'")),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Letters, digits, underscores and what numbers like `-1.5` are made of.
+fn is_id_character(character: char) -> bool {
+    character.is_alphanumeric() || matches!(character, '_' | '.' | '-')
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str, label: Option<&str>) -> TextNode {
+        TextNode {
+            id: id.to_string(),
+            label: label.map(str::to_string),
+        }
+    }
+
+    fn edges(graph: &GraphText) -> Vec<(usize, usize, Option<f32>)> {
+        graph
+            .edges
+            .iter()
+            .map(|edge| (edge.from, edge.to, edge.weight))
+            .collect()
+    }
+
+    fn error_line(text: &str) -> String {
+        let error = parse_graph_text(text).unwrap_err();
+        error.split(':').next().unwrap().to_string()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut world = World::new();
+        let [a, b, c] = [(); 3].map(|_| world.spawn_empty().id());
+        let labels = ["plain", "say \"hi\" \\ o/", "two\nlines"];
+        let text = graph_to_text(
+            [(c, labels[2]), (a, labels[0]), (b, labels[1])].into_iter(),
+            [(b, c, None), (a, b, Some(-1.5)), (c, c, Some(2.0))].into_iter(),
+        );
+
+        let graph = parse_graph_text(&text).unwrap();
+        assert_eq!(
+            graph.nodes,
+            [
+                node(&vertex_id(a), Some(labels[0])),
+                node(&vertex_id(b), Some(labels[1])),
+                node(&vertex_id(c), Some(labels[2])),
+            ]
+        );
+        assert_eq!(
+            edges(&graph),
+            [(0, 1, Some(-1.5)), (1, 2, None), (2, 2, Some(2.0))]
+        );
+    }
+
+    #[test]
+    fn arrows_need_no_spaces() {
+        let graph = parse_graph_text("a->b->c [weight=3]; b_2->-1").unwrap();
+        let ids: Vec<_> = graph.nodes.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c", "b_2", "-1"]);
+        assert_eq!(
+            edges(&graph),
+            [(0, 1, Some(3.0)), (1, 2, Some(3.0)), (3, 4, None)]
+        );
+        assert!(graph.nodes.iter().all(|node| node.label.is_none()));
+    }
+
+    #[test]
+    fn quotes_can_be_escaped() {
+        let graph = parse_graph_text(r#"a [label="say \"hi\"\n\\"]; "quoted id" -> a"#).unwrap();
+        assert_eq!(
+            graph.nodes,
+            [node("a", Some("say \"hi\"\n\\")), node("quoted id", None)]
+        );
+        assert_eq!(edges(&graph), [(1, 0, None)]);
+    }
+
+    #[test]
+    fn comments_and_defaults_are_skipped() {
+        let text = "// A graph\nstrict digraph name {\n  # a comment\n  rankdir=LR\n  \
+            node [shape=box, color=red]; edge [weight=9]\n  /* spanning\n  lines */ a -> b\n  \
+            a [label=first label=second, color=blue]\n}\n";
+        let graph = parse_graph_text(text).unwrap();
+        assert_eq!(graph.nodes, [node("a", Some("second")), node("b", None)]);
+        assert_eq!(edges(&graph), [(0, 1, None)]);
+        assert_eq!(error_line("/* one\ntwo */\n\n@"), "Line 4");
+    }
+
+    #[test]
+    fn errors_name_their_line() {
+        for (text, line) in [
+            ("a [label=\"open\n\nb", "Line 1"),
+            ("a\n/* never closed", "Line 2"),
+            ("digraph x -> {", "Line 1"),
+            ("digraph {\na -> b", "The graph is missing its closing '}'"),
+            ("digraph {}\n\na", "Line 3"),
+            ("a ->\n", "Line 1"),
+            ("a\nb [label]", "Line 2"),
+            ("a [label=\n]", "Line 1"),
+            ("a [label=x\n", "Line 1"),
+            ("a [=x]", "Line 1"),
+            ("a -> b [weight=heavy]", "Line 1"),
+            ("a\n\n\n}", "Line 4"),
+            ("a @ b", "Line 1"),
+        ] {
+            assert_eq!(error_line(text), line, "{text:?}");
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use bevy::prelude::*;
 
+use crate::graph::components::{Attribute, ElementStyle};
+
 // Three main parts:
 // - Actions
 // - Undo/Redo enums containing actions
@@ -9,7 +11,7 @@ use bevy::prelude::*;
 // Actions are arbitrary actions performed during the program
 // by the user. They contain all the necessary info around the action
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct VertexRenameAction {
     pub entity: Entity,
     pub name: String,
@@ -58,6 +60,90 @@ pub struct VertexInsertionAction {
     pub to: Entity
 }
 
+/// A vertex that was part of a bulk insertion or deletion.
+#[derive(Debug, Clone)]
+pub struct SubgraphVertex {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub label: String,
+}
+
+/// An edge that was part of a bulk insertion or deletion.
+#[derive(Debug, Clone)]
+pub struct SubgraphEdge {
+    pub entity: Entity,
+    pub from: Entity,
+    pub to: Entity,
+}
+
+/// A whole subgraph inserted or deleted as a single step,
+/// e.g. by a generator or by cutting the selection.
+#[derive(Debug, Clone)]
+pub struct SubgraphAction {
+    pub vertices: Vec<SubgraphVertex>,
+    pub edges: Vec<SubgraphEdge>,
+}
+
+/// The whole graph replaced by another one as a single step,
+/// e.g. by opening a file. Edits from the text editor only hold
+/// what differs, with the labels and weights to put back.
+#[derive(Debug, Clone)]
+pub struct GraphReplaceAction {
+    pub removed: SubgraphAction,
+    pub inserted: SubgraphAction,
+    pub relabeled: Vec<VertexRenameAction>,
+    pub reweighted: Vec<EdgeWeightEditAction>,
+}
+
+/// The previous attributes of a vertex or an edge.
+#[derive(Debug, Clone)]
+pub struct AttributesEditAction {
+    pub entity: Entity,
+    pub attributes: Vec<Attribute>,
+}
+
+/// The previous weight of an edge.
+#[derive(Debug, Clone)]
+pub struct EdgeWeightEditAction {
+    pub entity: Entity,
+    pub weight: Option<f32>,
+}
+
+/// The previous style of a vertex or an edge.
+#[derive(Debug, Clone)]
+pub struct StyleEditAction {
+    pub entity: Entity,
+    pub style: ElementStyle,
+}
+
+/// The ends of an edge before one of them was dragged to another vertex.
+#[derive(Debug)]
+pub struct EdgeReconnectAction {
+    pub entity: Entity,
+    pub from: Entity,
+    pub to: Entity,
+}
+
+/// Vertices merged into `survivor`, with the label and position it had
+/// before, the merged vertices and dropped edges, and the previous ends
+/// of the edges moved onto it.
+#[derive(Debug)]
+pub struct VertexMergeAction {
+    pub survivor: Entity,
+    pub label: String,
+    pub position: Vec2,
+    pub removed: SubgraphAction,
+    pub reconnected: Vec<EdgeReconnectAction>,
+}
+
+/// Attributes and styles of several vertices and edges
+/// edited at once, e.g. from the tables, as a single step.
+#[derive(Debug, Clone, Default)]
+pub struct BatchEditAction {
+    pub attributes: Vec<AttributesEditAction>,
+    pub styles: Vec<StyleEditAction>,
+}
+
 // Undo/Redo enums
 // These enums contain some action variant are they themselves are
 // stored in the `UndoRedoStack` stacks.
@@ -71,6 +157,15 @@ pub enum UndoAction {
     UndoEdgeDrawingAction(EdgeDrawingAction),
     UndoEdgeDeletionAction(EdgeDeletionAction),
     UndoVertexInsertionAction(VertexInsertionAction),
+    UndoGraphInsertionAction(SubgraphAction),
+    UndoGraphDeletionAction(SubgraphAction),
+    UndoGraphReplaceAction(GraphReplaceAction),
+    UndoAttributesEditAction(AttributesEditAction),
+    UndoEdgeWeightEditAction(EdgeWeightEditAction),
+    UndoStyleEditAction(StyleEditAction),
+    UndoBatchEditAction(BatchEditAction),
+    UndoEdgeReconnectAction(EdgeReconnectAction),
+    UndoVertexMergeAction(VertexMergeAction),
 }
 
 #[derive(Debug)]
@@ -81,7 +176,16 @@ pub enum RedoAction {
     RedoVertexMoveAction(VertexMoveAction),
     RedoEdgeDrawingAction(EdgeDrawingAction),
     RedoEdgeDeletionAction(EdgeDeletionAction),
-    RedoVertexInsertionAction(VertexInsertionAction)
+    RedoVertexInsertionAction(VertexInsertionAction),
+    RedoGraphInsertionAction(SubgraphAction),
+    RedoGraphDeletionAction(SubgraphAction),
+    RedoGraphReplaceAction(GraphReplaceAction),
+    RedoAttributesEditAction(AttributesEditAction),
+    RedoEdgeWeightEditAction(EdgeWeightEditAction),
+    RedoStyleEditAction(StyleEditAction),
+    RedoBatchEditAction(BatchEditAction),
+    RedoEdgeReconnectAction(EdgeReconnectAction),
+    RedoVertexMergeAction(VertexMergeAction),
 }
 
 // Events
@@ -169,4 +273,93 @@ pub struct UndoVertexInsertionEvent {
 #[derive(Event)]
 pub struct RedoVertexInsertionEvent {
     pub action: VertexInsertionAction,
-}
\ No newline at end of file
+}
+#[derive(Event)]
+pub struct UndoGraphInsertionEvent {
+    pub action: SubgraphAction,
+}
+
+#[derive(Event)]
+pub struct RedoGraphInsertionEvent {
+    pub action: SubgraphAction,
+}
+
+#[derive(Event)]
+pub struct UndoGraphDeletionEvent {
+    pub action: SubgraphAction,
+}
+
+#[derive(Event)]
+pub struct RedoGraphDeletionEvent {
+    pub action: SubgraphAction,
+}
+
+#[derive(Event)]
+pub struct UndoGraphReplaceEvent {
+    pub action: GraphReplaceAction,
+}
+
+#[derive(Event)]
+pub struct RedoGraphReplaceEvent {
+    pub action: GraphReplaceAction,
+}
+
+#[derive(Event)]
+pub struct UndoAttributesEditEvent {
+    pub action: AttributesEditAction,
+}
+
+#[derive(Event)]
+pub struct RedoAttributesEditEvent {
+    pub action: AttributesEditAction,
+}
+
+#[derive(Event)]
+pub struct UndoEdgeWeightEditEvent {
+    pub action: EdgeWeightEditAction,
+}
+
+#[derive(Event)]
+pub struct RedoEdgeWeightEditEvent {
+    pub action: EdgeWeightEditAction,
+}
+
+#[derive(Event)]
+pub struct UndoStyleEditEvent {
+    pub action: StyleEditAction,
+}
+
+#[derive(Event)]
+pub struct RedoStyleEditEvent {
+    pub action: StyleEditAction,
+}
+
+#[derive(Event)]
+pub struct UndoBatchEditEvent {
+    pub action: BatchEditAction,
+}
+
+#[derive(Event)]
+pub struct RedoBatchEditEvent {
+    pub action: BatchEditAction,
+}
+
+#[derive(Event)]
+pub struct UndoEdgeReconnectEvent {
+    pub action: EdgeReconnectAction,
+}
+
+#[derive(Event)]
+pub struct RedoEdgeReconnectEvent {
+    pub action: EdgeReconnectAction,
+}
+
+#[derive(Event)]
+pub struct UndoVertexMergeEvent {
+    pub action: VertexMergeAction,
+}
+
+#[derive(Event)]
+pub struct RedoVertexMergeEvent {
+    pub action: VertexMergeAction,
+}
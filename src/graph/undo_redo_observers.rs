@@ -2,11 +2,27 @@ use bevy::prelude::*;
 
 use crate::graph::{
     bundles::{DirectedEdgeBundle, VertexBundle},
-    components::{DirectedEdge, Position, Vertex},
-    events::VertexRenamedEvent,
-    resources::UndoRedoStack,
+    components::{
+        Attribute, Attributes, DirectedEdge, EdgeStyle, EdgeWeight, Position, Vertex, VertexStyle,
+    },
+    custom_observers::current_style,
+    events::{AttributesEditedEvent, EdgeWeightEditedEvent, StyleEditedEvent, VertexRenamedEvent},
+    resources::{GraphAssets, UndoRedoStack},
+    settings::Settings,
     undo_redo::{
-        EdgeDeletionAction, EdgeDrawingAction, RedoAction, RedoEdgeDeletionEvent, RedoEdgeDrawingEvent, RedoVertexDeletionEvent, RedoVertexInsertionEvent, RedoVertexMoveEvent, RedoVertexRenameEvent, RedoVertexSpawnEvent, UndoAction, UndoEdgeDeletionEvent, UndoEdgeDrawingEvent, UndoVertexDeletionEvent, UndoVertexInsertionEvent, UndoVertexMoveEvent, UndoVertexRenameEvent, UndoVertexSpawnEvent, VertexDeletionAction, VertexInsertionAction, VertexMoveAction, VertexRenameAction, VertexSpawnAction
+        AttributesEditAction, BatchEditAction, EdgeDeletionAction, EdgeDrawingAction,
+        EdgeReconnectAction, EdgeWeightEditAction, GraphReplaceAction, RedoAction,
+        RedoAttributesEditEvent, RedoBatchEditEvent, RedoEdgeDeletionEvent, RedoEdgeDrawingEvent,
+        RedoEdgeReconnectEvent, RedoEdgeWeightEditEvent, RedoGraphDeletionEvent,
+        RedoGraphInsertionEvent, RedoGraphReplaceEvent, RedoStyleEditEvent, RedoVertexDeletionEvent,
+        RedoVertexInsertionEvent, RedoVertexMergeEvent, RedoVertexMoveEvent, RedoVertexRenameEvent,
+        RedoVertexSpawnEvent, StyleEditAction, SubgraphAction, SubgraphVertex, UndoAction,
+        UndoAttributesEditEvent, UndoBatchEditEvent, UndoEdgeDeletionEvent, UndoEdgeDrawingEvent,
+        UndoEdgeReconnectEvent, UndoEdgeWeightEditEvent, UndoGraphDeletionEvent,
+        UndoGraphInsertionEvent, UndoGraphReplaceEvent, UndoStyleEditEvent, UndoVertexDeletionEvent,
+        UndoVertexInsertionEvent, UndoVertexMergeEvent, UndoVertexMoveEvent, UndoVertexRenameEvent,
+        UndoVertexSpawnEvent, VertexDeletionAction, VertexInsertionAction, VertexMergeAction,
+        VertexMoveAction, VertexRenameAction, VertexSpawnAction,
     },
 };
 
@@ -60,19 +76,22 @@ pub fn on_undo_vertex_deletion(
     event: On<UndoVertexDeletionEvent>,
     mut commands: Commands,
     mut undo_redo: ResMut<UndoRedoStack>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut assets: GraphAssets,
 ) {
     let respawned_vertex_id = commands
         .entity(event.entity)
         .insert(VertexBundle::new_with_label(
-            &mut meshes,
-            &mut materials,
+            &mut assets,
             event.position,
             &event.vertex_label,
         ))
         .id();
-    VertexBundle::add_children_with_label(&mut commands, respawned_vertex_id, &event.vertex_label);
+    VertexBundle::add_children_with_label(
+        &mut commands,
+        assets.settings(),
+        respawned_vertex_id,
+        &event.vertex_label,
+    );
     undo_redo.push_redo(RedoAction::RedoVertexDeletion(VertexDeletionAction {
         entity: event.entity,
         position: event.position,
@@ -115,18 +134,16 @@ pub fn on_redo_vertex_spawn(
     event: On<RedoVertexSpawnEvent>,
     mut commands: Commands,
     mut undo_redo: ResMut<UndoRedoStack>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut assets: GraphAssets,
 ) {
     let respawned_vertex_id = commands
         .entity(event.entity)
         .insert(VertexBundle::new(
-            &mut meshes,
-            &mut materials,
+            &mut assets,
             event.position,
         ))
         .id();
-    VertexBundle::add_children(&mut commands, respawned_vertex_id);
+    VertexBundle::add_children(&mut commands, assets.settings(), respawned_vertex_id);
     undo_redo.push_undo_without_clear(UndoAction::UndoVertexSpawn(VertexSpawnAction {
         entity: event.entity,
         position: event.position,
@@ -171,19 +188,15 @@ pub fn on_redo_edge_draw(
     event: On<RedoEdgeDrawingEvent>,
     mut commands: Commands,
     mut undo_redo: ResMut<UndoRedoStack>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut assets: GraphAssets,
 ) {
-    let respawned_edge_id = commands
+    commands
         .entity(event.action.entity)
         .insert(DirectedEdgeBundle::new(
             event.action.from,
             event.action.to,
-            &mut meshes,
-            &mut materials,
-        ))
-        .id();
-    DirectedEdgeBundle::add_children(&mut commands, respawned_edge_id);
+            &mut assets,
+        ));
     undo_redo.push_undo_without_clear(UndoAction::UndoEdgeDrawingAction(EdgeDrawingAction {
         entity: event.action.entity,
         from: event.action.from,
@@ -211,19 +224,15 @@ pub fn on_undo_edge_deletion(
     event: On<UndoEdgeDeletionEvent>,
     mut commands: Commands,
     mut undo_redo: ResMut<UndoRedoStack>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut assets: GraphAssets,
 ) {
-    let respawned_edge_id = commands
+    commands
         .entity(event.action.entity)
         .insert(DirectedEdgeBundle::new(
             event.action.from,
             event.action.to,
-            &mut meshes,
-            &mut materials
-        ))
-        .id();
-    DirectedEdgeBundle::add_children(&mut commands, respawned_edge_id);
+            &mut assets,
+        ));
     undo_redo.push_redo(RedoAction::RedoEdgeDeletionAction(EdgeDeletionAction {
         entity: event.action.entity,
         from: event.action.from,
@@ -278,8 +287,7 @@ pub fn on_redo_vertex_insertion(
     event: On<RedoVertexInsertionEvent>,
     mut commands: Commands,
     mut undo_redo: ResMut<UndoRedoStack>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut assets: GraphAssets,
     mut edges: Query<&mut DirectedEdge>
 ) {
     let Ok(mut edge) = edges.get_mut(event.action.edge_entity) else {
@@ -289,12 +297,11 @@ pub fn on_redo_vertex_insertion(
     let respawned_vertex_id = commands
         .entity(event.action.vertex_entity)
         .insert(VertexBundle::new(
-            &mut meshes,
-            &mut materials,
+            &mut assets,
             event.action.vertex_position,
         ))
         .id();
-    VertexBundle::add_children(&mut commands, respawned_vertex_id);
+    VertexBundle::add_children(&mut commands, assets.settings(), respawned_vertex_id);
 
     edge.from = event.action.from;
     edge.to = respawned_vertex_id;
@@ -306,4 +313,536 @@ pub fn on_redo_vertex_insertion(
         from: event.action.from,
         to: event.action.to,
     }));
-}
\ No newline at end of file
+}
+
+/// Undoing an insertion logically deletes the whole subgraph.
+pub fn on_undo_graph_insertion(
+    event: On<UndoGraphInsertionEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    vertices: Query<(&Vertex, &Position)>,
+) {
+    let removed = remove_subgraph(&mut commands, &event.action, vertices);
+    undo_redo.push_redo(RedoAction::RedoGraphInsertionAction(removed));
+}
+
+/// Redoing an insertion brings back every vertex and edge of it.
+pub fn on_redo_graph_insertion(
+    event: On<RedoGraphInsertionEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    mut assets: GraphAssets,
+) {
+    restore_subgraph(&mut commands, &mut assets, &event.action);
+    undo_redo.push_undo_without_clear(UndoAction::UndoGraphInsertionAction(
+        event.action.clone(),
+    ));
+}
+
+/// Undoing a bulk deletion brings back the whole subgraph.
+pub fn on_undo_graph_deletion(
+    event: On<UndoGraphDeletionEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    mut assets: GraphAssets,
+) {
+    restore_subgraph(&mut commands, &mut assets, &event.action);
+    undo_redo.push_redo(RedoAction::RedoGraphDeletionAction(event.action.clone()));
+}
+
+/// Redoing a bulk deletion logically deletes the subgraph again.
+pub fn on_redo_graph_deletion(
+    event: On<RedoGraphDeletionEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    vertices: Query<(&Vertex, &Position)>,
+) {
+    let removed = remove_subgraph(&mut commands, &event.action, vertices);
+    undo_redo.push_undo_without_clear(UndoAction::UndoGraphDeletionAction(removed));
+}
+
+/// Undoing a replacement removes the inserted graph
+/// and brings back the replaced one.
+pub fn on_undo_graph_replace(
+    event: On<UndoGraphReplaceEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    mut assets: GraphAssets,
+    vertices: Query<(&Vertex, &Position)>,
+    weights: Query<&EdgeWeight>,
+) {
+    let (relabeled, reweighted) =
+        restore_labels_and_weights(&mut commands, &event.action, &vertices, &weights);
+    let inserted = remove_subgraph(&mut commands, &event.action.inserted, vertices);
+    restore_subgraph(&mut commands, &mut assets, &event.action.removed);
+    undo_redo.push_redo(RedoAction::RedoGraphReplaceAction(GraphReplaceAction {
+        removed: event.action.removed.clone(),
+        inserted,
+        relabeled,
+        reweighted,
+    }));
+}
+
+/// Redoing a replacement removes the original graph again.
+pub fn on_redo_graph_replace(
+    event: On<RedoGraphReplaceEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    mut assets: GraphAssets,
+    vertices: Query<(&Vertex, &Position)>,
+    weights: Query<&EdgeWeight>,
+) {
+    let (relabeled, reweighted) =
+        restore_labels_and_weights(&mut commands, &event.action, &vertices, &weights);
+    let removed = remove_subgraph(&mut commands, &event.action.removed, vertices);
+    restore_subgraph(&mut commands, &mut assets, &event.action.inserted);
+    undo_redo.push_undo_without_clear(UndoAction::UndoGraphReplaceAction(GraphReplaceAction {
+        removed,
+        inserted: event.action.inserted.clone(),
+        relabeled,
+        reweighted,
+    }));
+}
+
+/// Puts back the labels and weights a replacement changed in place,
+/// returning the current ones for the opposite action.
+fn restore_labels_and_weights(
+    commands: &mut Commands,
+    action: &GraphReplaceAction,
+    vertices: &Query<(&Vertex, &Position)>,
+    weights: &Query<&EdgeWeight>,
+) -> (Vec<VertexRenameAction>, Vec<EdgeWeightEditAction>) {
+    let relabeled = action
+        .relabeled
+        .iter()
+        .filter_map(|rename| {
+            let (vertex, _) = vertices.get(rename.entity).ok()?;
+            commands.trigger(VertexRenamedEvent {
+                entity: rename.entity,
+                new_label: rename.name.clone(),
+                manual: false,
+            });
+            Some(VertexRenameAction {
+                entity: rename.entity,
+                name: vertex.label.clone(),
+            })
+        })
+        .collect();
+    let reweighted = action
+        .reweighted
+        .iter()
+        .map(|edit| {
+            commands.trigger(EdgeWeightEditedEvent {
+                entity: edit.entity,
+                weight: edit.weight,
+                manual: false,
+            });
+            EdgeWeightEditAction {
+                entity: edit.entity,
+                weight: weights.get(edit.entity).ok().map(|weight| weight.0),
+            }
+        })
+        .collect();
+    (relabeled, reweighted)
+}
+
+/// Undoing an attribute edit puts the previous attributes back.
+pub fn on_undo_attributes_edit(
+    event: On<UndoAttributesEditEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    attributes: Query<&Attributes>,
+) {
+    undo_redo.push_redo(RedoAction::RedoAttributesEditAction(AttributesEditAction {
+        entity: event.action.entity,
+        attributes: current_attributes(&attributes, event.action.entity),
+    }));
+    commands.trigger(AttributesEditedEvent {
+        entity: event.action.entity,
+        attributes: event.action.attributes.clone(),
+        manual: false,
+    });
+}
+
+pub fn on_redo_attributes_edit(
+    event: On<RedoAttributesEditEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    attributes: Query<&Attributes>,
+) {
+    undo_redo.push_undo_without_clear(UndoAction::UndoAttributesEditAction(
+        AttributesEditAction {
+            entity: event.action.entity,
+            attributes: current_attributes(&attributes, event.action.entity),
+        },
+    ));
+    commands.trigger(AttributesEditedEvent {
+        entity: event.action.entity,
+        attributes: event.action.attributes.clone(),
+        manual: false,
+    });
+}
+
+fn current_attributes(attributes: &Query<&Attributes>, entity: Entity) -> Vec<Attribute> {
+    attributes
+        .get(entity)
+        .map(|attributes| attributes.0.clone())
+        .unwrap_or_default()
+}
+
+/// Undoing a weight edit puts the previous weight back.
+pub fn on_undo_edge_weight_edit(
+    event: On<UndoEdgeWeightEditEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    weights: Query<&EdgeWeight>,
+) {
+    undo_redo.push_redo(RedoAction::RedoEdgeWeightEditAction(EdgeWeightEditAction {
+        entity: event.action.entity,
+        weight: weights.get(event.action.entity).ok().map(|weight| weight.0),
+    }));
+    commands.trigger(EdgeWeightEditedEvent {
+        entity: event.action.entity,
+        weight: event.action.weight,
+        manual: false,
+    });
+}
+
+pub fn on_redo_edge_weight_edit(
+    event: On<RedoEdgeWeightEditEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    weights: Query<&EdgeWeight>,
+) {
+    undo_redo.push_undo_without_clear(UndoAction::UndoEdgeWeightEditAction(
+        EdgeWeightEditAction {
+            entity: event.action.entity,
+            weight: weights.get(event.action.entity).ok().map(|weight| weight.0),
+        },
+    ));
+    commands.trigger(EdgeWeightEditedEvent {
+        entity: event.action.entity,
+        weight: event.action.weight,
+        manual: false,
+    });
+}
+
+/// Undoing a style edit puts the previous style back.
+pub fn on_undo_style_edit(
+    event: On<UndoStyleEditEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    vertex_styles: Query<&VertexStyle>,
+    edge_styles: Query<&EdgeStyle>,
+    settings: Res<Settings>,
+) {
+    let entity = event.action.entity;
+    undo_redo.push_redo(RedoAction::RedoStyleEditAction(StyleEditAction {
+        entity,
+        style: current_style(
+            entity,
+            &event.action.style,
+            &vertex_styles,
+            &edge_styles,
+            &settings,
+        ),
+    }));
+    commands.trigger(StyleEditedEvent {
+        entity,
+        style: event.action.style.clone(),
+        manual: false,
+    });
+}
+
+pub fn on_redo_style_edit(
+    event: On<RedoStyleEditEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    vertex_styles: Query<&VertexStyle>,
+    edge_styles: Query<&EdgeStyle>,
+    settings: Res<Settings>,
+) {
+    let entity = event.action.entity;
+    undo_redo.push_undo_without_clear(UndoAction::UndoStyleEditAction(StyleEditAction {
+        entity,
+        style: current_style(
+            entity,
+            &event.action.style,
+            &vertex_styles,
+            &edge_styles,
+            &settings,
+        ),
+    }));
+    commands.trigger(StyleEditedEvent {
+        entity,
+        style: event.action.style.clone(),
+        manual: false,
+    });
+}
+
+/// Undoing a batch edit puts back the attributes and styles of every entity.
+pub fn on_undo_batch_edit(
+    event: On<UndoBatchEditEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    attributes: Query<&Attributes>,
+    vertex_styles: Query<&VertexStyle>,
+    edge_styles: Query<&EdgeStyle>,
+    settings: Res<Settings>,
+) {
+    let previous = apply_batch_edit(
+        &mut commands,
+        &event.action,
+        &attributes,
+        &vertex_styles,
+        &edge_styles,
+        &settings,
+    );
+    undo_redo.push_redo(RedoAction::RedoBatchEditAction(previous));
+}
+
+pub fn on_redo_batch_edit(
+    event: On<RedoBatchEditEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    attributes: Query<&Attributes>,
+    vertex_styles: Query<&VertexStyle>,
+    edge_styles: Query<&EdgeStyle>,
+    settings: Res<Settings>,
+) {
+    let previous = apply_batch_edit(
+        &mut commands,
+        &event.action,
+        &attributes,
+        &vertex_styles,
+        &edge_styles,
+        &settings,
+    );
+    undo_redo.push_undo_without_clear(UndoAction::UndoBatchEditAction(previous));
+}
+
+/// Undoing a reconnect puts the previous ends back.
+pub fn on_undo_edge_reconnect(
+    event: On<UndoEdgeReconnectEvent>,
+    mut edges: Query<&mut DirectedEdge>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+) {
+    let Ok(mut edge) = edges.get_mut(event.action.entity) else {
+        return;
+    };
+
+    undo_redo.push_redo(RedoAction::RedoEdgeReconnectAction(EdgeReconnectAction {
+        entity: event.action.entity,
+        from: edge.from,
+        to: edge.to,
+    }));
+    edge.from = event.action.from;
+    edge.to = event.action.to;
+}
+
+pub fn on_redo_edge_reconnect(
+    event: On<RedoEdgeReconnectEvent>,
+    mut edges: Query<&mut DirectedEdge>,
+    mut undo_redo: ResMut<UndoRedoStack>,
+) {
+    let Ok(mut edge) = edges.get_mut(event.action.entity) else {
+        return;
+    };
+
+    undo_redo.push_undo_without_clear(UndoAction::UndoEdgeReconnectAction(EdgeReconnectAction {
+        entity: event.action.entity,
+        from: edge.from,
+        to: edge.to,
+    }));
+    edge.from = event.action.from;
+    edge.to = event.action.to;
+}
+
+/// Undoing a merge brings back the merged vertices and dropped edges,
+/// and moves the edges and the survivor back to where they were.
+pub fn on_undo_vertex_merge(
+    event: On<UndoVertexMergeEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    mut assets: GraphAssets,
+    vertices: Query<(&Vertex, &Position)>,
+    mut edges: Query<&mut DirectedEdge>,
+) {
+    restore_subgraph(&mut commands, &mut assets, &event.action.removed);
+    let merged = swap_merge(&mut commands, &event.action, &vertices, &mut edges);
+    undo_redo.push_redo(RedoAction::RedoVertexMergeAction(merged));
+}
+
+pub fn on_redo_vertex_merge(
+    event: On<RedoVertexMergeEvent>,
+    mut commands: Commands,
+    mut undo_redo: ResMut<UndoRedoStack>,
+    vertices: Query<(&Vertex, &Position)>,
+    mut edges: Query<&mut DirectedEdge>,
+) {
+    let split = swap_merge(&mut commands, &event.action, &vertices, &mut edges);
+    let removed = remove_subgraph(&mut commands, &event.action.removed, vertices);
+    undo_redo.push_undo_without_clear(UndoAction::UndoVertexMergeAction(VertexMergeAction {
+        removed,
+        ..split
+    }));
+}
+
+/// Gives the survivor and the moved edges of a merge the label, position
+/// and ends of the action, and returns the ones they had for the opposite step.
+fn swap_merge(
+    commands: &mut Commands,
+    merge: &VertexMergeAction,
+    vertices: &Query<(&Vertex, &Position)>,
+    edges: &mut Query<&mut DirectedEdge>,
+) -> VertexMergeAction {
+    let (label, position) = vertices
+        .get(merge.survivor)
+        .map(|(vertex, position)| (vertex.label.clone(), position.0))
+        .unwrap_or((merge.label.clone(), merge.position));
+    if label != merge.label {
+        commands.trigger(VertexRenamedEvent {
+            entity: merge.survivor,
+            new_label: merge.label.clone(),
+            manual: false,
+        });
+    }
+    commands
+        .entity(merge.survivor)
+        .insert(Position(merge.position));
+
+    let reconnected = merge
+        .reconnected
+        .iter()
+        .filter_map(|reconnect| {
+            let mut edge = edges.get_mut(reconnect.entity).ok()?;
+            let previous = EdgeReconnectAction {
+                entity: reconnect.entity,
+                from: edge.from,
+                to: edge.to,
+            };
+            edge.from = reconnect.from;
+            edge.to = reconnect.to;
+            Some(previous)
+        })
+        .collect();
+
+    VertexMergeAction {
+        survivor: merge.survivor,
+        label,
+        position,
+        removed: merge.removed.clone(),
+        reconnected,
+    }
+}
+
+/// Gives every entity of the batch its attributes and style,
+/// returning what they had before for the opposite action.
+pub fn apply_batch_edit(
+    commands: &mut Commands,
+    edit: &BatchEditAction,
+    attributes: &Query<&Attributes>,
+    vertex_styles: &Query<&VertexStyle>,
+    edge_styles: &Query<&EdgeStyle>,
+    settings: &Settings,
+) -> BatchEditAction {
+    let mut previous = BatchEditAction::default();
+    for attributes_edit in &edit.attributes {
+        previous.attributes.push(AttributesEditAction {
+            entity: attributes_edit.entity,
+            attributes: current_attributes(attributes, attributes_edit.entity),
+        });
+        commands.trigger(AttributesEditedEvent {
+            entity: attributes_edit.entity,
+            attributes: attributes_edit.attributes.clone(),
+            manual: false,
+        });
+    }
+    for style_edit in &edit.styles {
+        previous.styles.push(StyleEditAction {
+            entity: style_edit.entity,
+            style: current_style(
+                style_edit.entity,
+                &style_edit.style,
+                vertex_styles,
+                edge_styles,
+                settings,
+            ),
+        });
+        commands.trigger(StyleEditedEvent {
+            entity: style_edit.entity,
+            style: style_edit.style.clone(),
+            manual: false,
+        });
+    }
+    previous
+}
+
+/// Logically deletes every vertex and edge of the subgraph. The current
+/// positions and labels are returned so restoring brings them back as they were.
+pub fn remove_subgraph(
+    commands: &mut Commands,
+    subgraph: &SubgraphAction,
+    vertices: Query<(&Vertex, &Position)>,
+) -> SubgraphAction {
+    let removed_vertices = subgraph
+        .vertices
+        .iter()
+        .map(|removed| {
+            let (label, position) = vertices
+                .get(removed.entity)
+                .map(|(vertex, position)| (vertex.label.clone(), position.0))
+                .unwrap_or((removed.label.clone(), removed.position));
+            commands
+                .entity(removed.entity)
+                .despawn_children()
+                .remove::<VertexBundle>();
+            SubgraphVertex {
+                entity: removed.entity,
+                position,
+                label,
+            }
+        })
+        .collect();
+
+    for edge in &subgraph.edges {
+        commands
+            .entity(edge.entity)
+            .despawn_children()
+            .remove::<DirectedEdgeBundle>();
+    }
+
+    SubgraphAction {
+        vertices: removed_vertices,
+        edges: subgraph.edges.clone(),
+    }
+}
+
+/// Re-inserts the bundles of every vertex and edge of the subgraph.
+pub fn restore_subgraph(
+    commands: &mut Commands,
+    assets: &mut GraphAssets,
+    subgraph: &SubgraphAction,
+) {
+    for vertex in &subgraph.vertices {
+        commands.entity(vertex.entity).insert(VertexBundle::new_with_label(
+            assets,
+            vertex.position,
+            &vertex.label,
+        ));
+        VertexBundle::add_children_with_label(
+            commands,
+            assets.settings(),
+            vertex.entity,
+            &vertex.label,
+        );
+    }
+
+    for edge in &subgraph.edges {
+        commands.entity(edge.entity).insert(DirectedEdgeBundle::new(
+            edge.from,
+            edge.to,
+            assets,
+        ));
+    }
+}
@@ -1,19 +1,52 @@
+use std::{collections::HashSet, time::Instant};
+
 use bevy::{
+    mesh::VertexAttributeValues,
+    picking::backend::{HitData, PointerHits, ray::RayMap},
     prelude::*,
     window::{CursorIcon, SystemCursorIcon},
 };
-use bevy_egui::{EguiContexts, egui};
+use bevy_egui::{EguiContexts, egui, input::EguiWantsInput};
 
 use crate::graph::{
-    components::{Canvas, DirectedEdge, Position, TemporaryDirectedEdge, Vertex},
-    constants::{EDGE_WIDTH, EDGE_Z, HIDDEN_EDGE_Z, VERTEX_Z},
-    events::{UpdateCursorIconEvent, VertexRenamedEvent},
-    resources::{HoveredEntity, RenamingState, UndoRedoStack},
+    actions::{ActionRegistry, EditorAction},
+    analysis::{DistanceStatistics, GraphSnapshot, Metric, graph_statistics, vertex_metrics},
+    components::{
+        ArrowStyle, Attributes, Canvas, DirectedEdge, EdgeArrowhead, EdgeBatch, EdgePattern,
+        EdgeStyle, EdgeWeight, EdgeWeightLabel, HasArrowhead, HasWeightLabel, HeatMapStyle,
+        HiddenEdge, Position, Selected, TemporaryDirectedEdge, Vertex, VertexShape, VertexStroke,
+        VertexStyle,
+    },
+    constants::{
+        ARROWHEAD_MIN_SIZE, ARROWHEAD_WIDTH_FACTOR, ARROWHEAD_Z, CANVAS_Z, EDGE_HANDLE_RADIUS,
+        EDGE_WEIGHT_LABEL_OFFSET, EDGE_Z, HEAT_MAP_COLD_COLOR, HEAT_MAP_HOT_COLOR,
+        HEAT_MAP_MAX_RADIUS, HEAT_MAP_MIN_RADIUS, HEAT_MAP_STEPS, HIDDEN_EDGE_Z, SEARCH_RING_GAP,
+        SELECTION_RING_GAP, STATISTICS_FRAME_BUDGET, VERTEX_STROKE_Z, VERTEX_TEXT_Z, VERTEX_Z,
+        WINDOW_TITLE,
+    },
+    document::{EdgeData, GraphDocument, VertexData},
+    events::{RunActionEvent, UpdateCursorIconEvent, VertexRenamedEvent},
+    helpers::{
+        dash_pattern, edge_batch_mesh, edge_color, edge_handles, edge_quad, hovered_edge_color,
+        hovered_vertex_fill, vertex_display_name, vertex_fill, vertex_label_offset, visible_rect,
+    },
+    recovery::{RecoveryState, end_session},
+    resources::{
+        AdjacencyMatrixState, AreaSelection, AreaSelectionMode, DocumentState, EdgeEndDrag,
+        GraphAssets, GraphMetrics, HandleCache, HoveredEntity, MeshKey, MetricsPanelState,
+        RenamingState, SearchState, StatisticsPanelState, TablesState, UndoRedoStack,
+    },
+    search::{SearchMatch, SearchPattern, match_vertex},
+    settings::Settings,
+    spatial::{EdgeBounds, SpatialIndex, VertexBounds},
 };
 
 /// Using an inner Position component for readability's sake, which is a `Vec2`
 /// that needs to be transformed into a proper `Transform`.
-pub fn project_positions(mut positionables: Query<(&mut Transform, &Position), Without<Canvas>>) {
+/// Only moved vertices are touched, so large graphs stay cheap.
+pub fn project_positions(
+    mut positionables: Query<(&mut Transform, &Position), (Without<Canvas>, Changed<Position>)>,
+) {
     for (mut transform, position) in &mut positionables {
         transform.translation = position.0.extend(VERTEX_Z);
     }
@@ -25,21 +58,24 @@ pub fn cursor_icon_manager(
     mut commands: Commands,
     keyboard: Res<ButtonInput<KeyCode>>,
     hovered: Res<HoveredEntity>,
+    registry: Res<ActionRegistry>,
     vertices: Query<Entity, With<Vertex>>,
     edges: Query<Entity, With<DirectedEdge>>,
 ) {
-    let is_ctrl_held =
-        { keyboard.pressed(KeyCode::ControlLeft) || keyboard.pressed(KeyCode::ControlRight) };
-
     let mut new_cursor_icon = CursorIcon::from(SystemCursorIcon::Default);
 
     if let Some(hovered_entity) = hovered.0 {
-        if let Ok(_vertex) = vertices.get(hovered_entity) {
+        // The crosshair warns that clicking deletes.
+        let deleting = if let Ok(_vertex) = vertices.get(hovered_entity) {
             new_cursor_icon = CursorIcon::from(SystemCursorIcon::Grab);
+            registry.modifiers_held(EditorAction::DeleteVertex, &keyboard)
         } else if let Ok(_edge) = edges.get(hovered_entity) {
             new_cursor_icon = CursorIcon::from(SystemCursorIcon::Cell);
-        }
-        if is_ctrl_held {
+            registry.modifiers_held(EditorAction::DeleteEdge, &keyboard)
+        } else {
+            false
+        };
+        if deleting {
             new_cursor_icon = CursorIcon::from(SystemCursorIcon::Crosshair);
         }
     }
@@ -93,35 +129,325 @@ pub fn show_rename_input(
         });
 }
 
-/// Checks for classic undo/redo shortcuts.
-pub fn undo_redo_system(
-    commands: Commands,
+/// Runs the actions whose shortcut was just pressed. Skipped while
+/// egui is typing, so text fields keep their own shortcuts.
+pub fn shortcut_system(
+    mut commands: Commands,
     keyboard: Res<ButtonInput<KeyCode>>,
-    mut undo_redo: ResMut<UndoRedoStack>,
+    egui_input: Res<EguiWantsInput>,
+    registry: Res<ActionRegistry>,
+) {
+    if egui_input.wants_keyboard_input() {
+        return;
+    }
+
+    for action in registry.just_pressed(&keyboard) {
+        commands.trigger(RunActionEvent { action });
+    }
+}
+
+/// The canvas always covers what the camera sees, so clicks reach it and
+/// hidden edges stay behind it wherever the camera is moved or zoomed to.
+pub fn fit_canvas_to_camera(
+    window: Single<&Window>,
+    camera: Single<(&Transform, &Projection), (With<Camera2d>, Without<Canvas>)>,
+    canvas: Single<&mut Transform, With<Canvas>>,
+) {
+    let (camera_transform, projection) = camera.into_inner();
+    let visible = visible_rect(camera_transform, projection, window.size());
+    canvas.into_inner().set_if_neq(Transform {
+        translation: visible.center().extend(CANVAS_Z),
+        scale: visible.size().extend(1.0),
+        ..default()
+    });
+}
+
+/// The window title names the document, with a `*` while it has unsaved changes.
+pub fn update_window_title(
+    mut window: Single<&mut Window>,
+    document_state: Res<DocumentState>,
+    undo_redo: Res<UndoRedoStack>,
+) {
+    let dirty = if document_state.is_dirty(&undo_redo) {
+        "*"
+    } else {
+        ""
+    };
+    let title = format!("{}{dirty} - {WINDOW_TITLE}", document_state.path);
+    if window.title != title {
+        window.title = title;
+    }
+}
+
+/// Keeps the recovery file in step with the graph every `AUTOSAVE_INTERVAL`.
+pub fn autosave(
+    time: Res<Time>,
+    mut recovery: ResMut<RecoveryState>,
+    document_state: Res<DocumentState>,
+    undo_redo: Res<UndoRedoStack>,
+    vertices: Query<VertexData>,
+    edges: Query<EdgeData>,
+) {
+    if !recovery.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    recovery.autosave(
+        undo_redo.state(),
+        document_state.is_dirty(&undo_redo),
+        || GraphDocument::from_entities(vertices.iter(), edges.iter()),
+    );
+}
+
+/// Autosaves one last time when the app exits, so closing it by accident
+/// keeps unsaved work, and marks the session as cleanly shut down.
+pub fn autosave_on_exit(
+    mut exits: MessageReader<AppExit>,
+    mut recovery: ResMut<RecoveryState>,
+    document_state: Res<DocumentState>,
+    undo_redo: Res<UndoRedoStack>,
+    vertices: Query<VertexData>,
+    edges: Query<EdgeData>,
+) {
+    if exits.read().count() == 0 {
+        return;
+    }
+
+    recovery.autosave(
+        undo_redo.state(),
+        document_state.is_dirty(&undo_redo),
+        || GraphDocument::from_entities(vertices.iter(), edges.iter()),
+    );
+    end_session();
+}
+
+/// Recomputes the search results while the search box is open, when
+/// the query changed or a vertex was added, removed, renamed or got
+/// different attributes. The current match is kept if it still matches.
+pub fn update_search_matches(
+    mut search: ResMut<SearchState>,
+    changed: Query<(), Or<(Changed<Vertex>, Changed<Attributes>)>>,
+    mut removed: RemovedComponents<Vertex>,
+    vertices: Query<(Entity, &Vertex, Option<&Attributes>)>,
+) {
+    let removed = removed.read().count() > 0;
+    let search = search.as_mut();
+    if !search.open {
+        search.searched = None;
+        return;
+    }
+
+    let key = (search.query.clone(), search.regex);
+    if search.searched.as_ref() == Some(&key) && changed.is_empty() && !removed {
+        return;
+    }
+    search.searched = Some(key);
+
+    let current = search
+        .current
+        .and_then(|index| search.matches.get(index))
+        .map(|found| found.entity);
+    search.matches.clear();
+    search.current = None;
+    search.error = None;
+    if search.query.is_empty() {
+        return;
+    }
+
+    let pattern = match SearchPattern::new(&search.query, search.regex) {
+        Ok(pattern) => pattern,
+        Err(error) => {
+            search.error = Some(error.to_string());
+            return;
+        }
+    };
+    search.matches = vertices
+        .iter()
+        .filter_map(|(entity, vertex, attributes)| {
+            let attributes = attributes.map_or(&[][..], |attributes| &attributes.0);
+            match_vertex(&pattern, &vertex.label, attributes).map(|field| SearchMatch {
+                entity,
+                name: vertex_display_name(&vertex.label, entity),
+                field,
+            })
+        })
+        .collect();
+    search.matches.sort_by(|a, b| a.name.cmp(&b.name));
+    search.current = current.and_then(|current| {
+        search
+            .matches
+            .iter()
+            .position(|found| found.entity == current)
+    });
+}
+
+/// Search matches get a ring while the search box is open,
+/// the one the camera was centred on gets a second one.
+pub fn draw_search_matches(
+    mut gizmos: Gizmos,
+    search: Res<SearchState>,
+    settings: Res<Settings>,
+    vertices: Query<(&Position, Option<&VertexStyle>), With<Vertex>>,
 ) {
-    let is_ctrl_held =
-        { keyboard.pressed(KeyCode::ControlLeft) || keyboard.pressed(KeyCode::ControlRight) };
+    if !search.open {
+        return;
+    }
 
-    if is_ctrl_held {
-        if keyboard.just_released(KeyCode::KeyZ) {
-            undo_redo.undo(commands);
-        } else if keyboard.just_released(KeyCode::KeyY) {
-            undo_redo.redo(commands);
+    let color = settings.palette().search_match;
+    for (index, found) in search.matches.iter().enumerate() {
+        let Ok((position, style)) = vertices.get(found.entity) else {
+            continue;
+        };
+        let radius = style.map_or(settings.vertex_radius, |style| style.radius) + SEARCH_RING_GAP;
+        gizmos.circle_2d(position.0, radius, color);
+        if search.current == Some(index) {
+            gizmos.circle_2d(position.0, radius + SEARCH_RING_GAP, color);
         }
     }
 }
 
+/// The edge of the hovered adjacency matrix cell, or where it would go.
+pub fn draw_matrix_highlight(
+    mut gizmos: Gizmos,
+    matrix: Res<AdjacencyMatrixState>,
+    settings: Res<Settings>,
+    vertices: Query<(&Position, Option<&VertexStyle>), With<Vertex>>,
+) {
+    let Some((from, to)) = matrix.hovered else {
+        return;
+    };
+    let (Ok((from, from_style)), Ok((to, to_style))) = (vertices.get(from), vertices.get(to))
+    else {
+        return;
+    };
+
+    let color = settings.palette().search_match;
+    let radius = |style: Option<&VertexStyle>| {
+        style.map_or(settings.vertex_radius, |style| style.radius) + SEARCH_RING_GAP
+    };
+    let (from_radius, to_radius) = (radius(from_style), radius(to_style));
+    gizmos.circle_2d(from.0, from_radius, color);
+    if from.0 == to.0 {
+        return;
+    }
+    gizmos.circle_2d(to.0, to_radius, color);
+    let direction = (to.0 - from.0).normalize_or_zero();
+    gizmos.arrow_2d(
+        from.0 + direction * from_radius,
+        to.0 - direction * to_radius,
+        color,
+    );
+}
+
+/// The hovered edge gets a handle near each end, dragging one of them
+/// moves that end to another vertex, see `on_edge_drag_start`.
+pub fn draw_edge_handles(
+    mut gizmos: Gizmos,
+    hovered: Res<HoveredEntity>,
+    edge_end_drag: Res<EdgeEndDrag>,
+    settings: Res<Settings>,
+    edges: Query<&DirectedEdge>,
+    vertices: Query<(&Position, Option<&VertexStyle>), With<Vertex>>,
+) {
+    if edge_end_drag.0.is_some() {
+        return;
+    }
+    let Some(edge) = hovered.0.and_then(|entity| edges.get(entity).ok()) else {
+        return;
+    };
+    let Some(handles) = edge_handles(edge, &vertices, settings.vertex_radius) else {
+        return;
+    };
+
+    let color = settings.palette().selection;
+    for handle in handles {
+        gizmos.circle_2d(handle, EDGE_HANDLE_RADIUS, color);
+    }
+}
+
+/// Selected vertices get a ring around them, edges selected in the
+/// tables a line along them, and the rectangle
+/// or lasso of an ongoing area selection is outlined.
+pub fn draw_selection(
+    mut gizmos: Gizmos,
+    selected: Query<(&Position, Option<&VertexStyle>), (With<Selected>, With<Vertex>)>,
+    area_selection: Res<AreaSelection>,
+    settings: Res<Settings>,
+    tables: Res<TablesState>,
+    edges: Query<&DirectedEdge>,
+    positions: Query<&Position, With<Vertex>>,
+) {
+    let palette = settings.palette();
+    for (position, style) in &selected {
+        let radius = style.map_or(settings.vertex_radius, |style| style.radius);
+        gizmos.circle_2d(position.0, radius + SELECTION_RING_GAP, palette.selection);
+    }
+    if tables.open {
+        for edge in tables
+            .selected_edges
+            .iter()
+            .filter_map(|edge| edges.get(*edge).ok())
+        {
+            if let (Ok(from), Ok(to)) = (positions.get(edge.from), positions.get(edge.to)) {
+                gizmos.line_2d(from.0, to.0, palette.selection);
+            }
+        }
+    }
+
+    match area_selection.mode {
+        Some(AreaSelectionMode::Rectangle) => {
+            let rect = Rect::from_corners(area_selection.points[0], area_selection.points[1]);
+            gizmos.rect_2d(rect.center(), rect.size(), palette.area_selection);
+        }
+        Some(AreaSelectionMode::Lasso) => {
+            let closing = area_selection.points.first().copied();
+            gizmos.linestrip_2d(
+                area_selection.points.iter().copied().chain(closing),
+                palette.area_selection,
+            );
+        }
+        None => {}
+    }
+}
+
 /// Each edge should form a segment between its vertices.
+/// Only edges that changed, or whose vertices moved or disappeared, are updated.
 pub fn update_edge_transforms(
     mut commands: Commands,
-    edges: Query<(&DirectedEdge, &mut Transform, Entity), Without<Vertex>>,
+    mut edges: Query<
+        (
+            Entity,
+            Ref<DirectedEdge>,
+            &mut Transform,
+            Option<Ref<EdgeStyle>>,
+            Has<HiddenEdge>,
+        ),
+        Without<Vertex>,
+    >,
+    moved: Query<Entity, Changed<Position>>,
+    mut removed: RemovedComponents<Position>,
     positions: Query<&Position>,
+    settings: Res<Settings>,
 ) {
-    for (edge, transform, entity) in edges {
+    let dirty: HashSet<Entity> = moved.iter().chain(removed.read()).collect();
+
+    for (entity, edge, mut transform, style, hidden) in &mut edges {
+        let outdated = edge.is_changed()
+            || style.as_ref().is_some_and(|style| style.is_changed())
+            || dirty.contains(&edge.from)
+            || dirty.contains(&edge.to);
+        if !outdated && !hidden {
+            continue;
+        }
+
         if let Ok(from_pos) = positions.get(edge.from)
             && let Ok(to_pos) = positions.get(edge.to)
         {
-            apply_edge_transform(from_pos.0, to_pos.0, transform.into_inner());
+            let width = style.map_or(settings.edge_width, |style| style.width);
+            apply_edge_transform(from_pos.0, to_pos.0, width, &mut transform);
+            if hidden {
+                commands.entity(entity).remove::<HiddenEdge>();
+            }
             continue;
         };
 
@@ -136,11 +462,14 @@ pub fn update_edge_transforms(
         if let Ok(_from_entity) = commands.get_entity(edge.from)
             && let Ok(_to_entity) = commands.get_entity(edge.to)
         {
-            apply_edge_transform_behind_canvas(
-                Vec2::default(),
-                Vec2::default(),
-                transform.into_inner(),
-            );
+            if !hidden {
+                apply_edge_transform_behind_canvas(
+                    Vec2::default(),
+                    Vec2::default(),
+                    &mut transform,
+                );
+                commands.entity(entity).insert(HiddenEdge);
+            }
             continue;
         }
 
@@ -152,11 +481,579 @@ pub fn update_edge_transforms(
     }
 }
 
+/// Plain edges are drawn by the `EdgeBatch` instead of their own mesh,
+/// which is hidden unless the edge is hovered. The batch is rebuilt when
+/// edges become plain or stop being plain, otherwise only the quads of
+/// moved edges are rewritten.
+pub fn update_edge_batch(
+    batch: Single<(&mut EdgeBatch, &Mesh2d)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut edges: Query<
+        (
+            Entity,
+            Ref<Transform>,
+            &mut Visibility,
+            Has<EdgeStyle>,
+            Has<HiddenEdge>,
+        ),
+        With<DirectedEdge>,
+    >,
+    added: Query<(), Or<(Added<DirectedEdge>, Added<EdgeStyle>, Added<HiddenEdge>)>>,
+    mut removed_edges: RemovedComponents<DirectedEdge>,
+    mut removed_styles: RemovedComponents<EdgeStyle>,
+    mut removed_hidden: RemovedComponents<HiddenEdge>,
+    hovered: Res<HoveredEntity>,
+    mut shown: Local<Option<Entity>>,
+) {
+    let (mut batch, mesh) = batch.into_inner();
+    let removed = removed_edges.read().count()
+        + removed_styles.read().count()
+        + removed_hidden.read().count();
+
+    if removed > 0 || !added.is_empty() {
+        batch.slots.clear();
+        let mut quads = Vec::new();
+        for (entity, transform, mut visibility, styled, hidden) in &mut edges {
+            let plain = !styled && !hidden;
+            visibility.set_if_neq(if plain && hovered.0 != Some(entity) {
+                Visibility::Hidden
+            } else {
+                Visibility::Inherited
+            });
+            if plain {
+                batch.slots.insert(entity, quads.len());
+                quads.push(edge_quad(&transform));
+            }
+        }
+        if let Some(mesh) = meshes.get_mut(&mesh.0) {
+            *mesh = edge_batch_mesh(&quads);
+        }
+        *shown = hovered.0;
+        return;
+    }
+
+    if *shown != hovered.0 {
+        for entity in [*shown, hovered.0].into_iter().flatten() {
+            if batch.slots.contains_key(&entity)
+                && let Ok((.., mut visibility, _, _)) = edges.get_mut(entity)
+            {
+                visibility.set_if_neq(if hovered.0 == Some(entity) {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                });
+            }
+        }
+        *shown = hovered.0;
+    }
+
+    // Only touched when an edge moved, the whole mesh is uploaded again.
+    let moved: Vec<(usize, [[f32; 3]; 4])> = edges
+        .iter()
+        .filter(|(_, transform, ..)| transform.is_changed())
+        .filter_map(|(entity, transform, ..)| {
+            let slot = batch.slots.get(&entity)?;
+            Some((*slot, edge_quad(&transform)))
+        })
+        .collect();
+    if moved.is_empty() {
+        return;
+    }
+    let Some(VertexAttributeValues::Float32x3(positions)) = meshes
+        .get_mut(&mesh.0)
+        .and_then(|mesh| mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION))
+    else {
+        return;
+    };
+    for (slot, quad) in moved {
+        positions[slot * 4..slot * 4 + 4].copy_from_slice(&quad);
+    }
+}
+
+/// Rebuilds the mesh, fill, stroke and label placement of styled
+/// vertices whose style or label changed, or that were (re)spawned.
+pub fn apply_vertex_styles(
+    mut commands: Commands,
+    mut vertices: Query<
+        (
+            Entity,
+            &Vertex,
+            Option<&VertexStyle>,
+            Option<&HeatMapStyle>,
+            &mut Mesh2d,
+            &mut MeshMaterial2d<ColorMaterial>,
+            Option<&Children>,
+        ),
+        Or<(Added<Vertex>, Changed<Vertex>, Changed<VertexStyle>)>,
+    >,
+    strokes: Query<(), With<VertexStroke>>,
+    mut labels: Query<&mut Transform, With<Text2d>>,
+    hovered: Res<HoveredEntity>,
+    mut assets: GraphAssets,
+) {
+    let font_size = assets.settings().vertex_label_font_size;
+    for (entity, vertex, style, heat_map, mut mesh, mut material, children) in &mut vertices {
+        // Unstyled vertices keep their colours, only their size
+        // and label follow the settings.
+        let Some(style) = style else {
+            let radius = heat_map
+                .and_then(|heat_map| heat_map.radius)
+                .unwrap_or(assets.settings().vertex_radius);
+            mesh.0 = assets.mesh(MeshKey::vertex(
+                VertexShape::default(),
+                radius,
+                &vertex.label,
+                font_size,
+                0.0,
+            ));
+            let offset = vertex_label_offset(VertexShape::default(), radius, font_size);
+            for child in children.into_iter().flat_map(|children| children.iter()) {
+                if let Ok(mut transform) = labels.get_mut(child) {
+                    transform.translation = offset.extend(VERTEX_TEXT_Z);
+                }
+            }
+            continue;
+        };
+
+        let radius = heat_map
+            .and_then(|heat_map| heat_map.radius)
+            .unwrap_or(style.radius);
+        mesh.0 = assets.mesh(MeshKey::vertex(
+            style.shape,
+            radius,
+            &vertex.label,
+            font_size,
+            0.0,
+        ));
+        material.0 = assets.material(if hovered.0 == Some(entity) {
+            hovered_vertex_fill(Some(style), assets.palette())
+        } else {
+            vertex_fill(Some(style), heat_map, assets.palette())
+        });
+
+        for child in children.into_iter().flat_map(|children| children.iter()) {
+            if strokes.contains(child) {
+                commands.entity(child).despawn();
+            } else if let Ok(mut transform) = labels.get_mut(child) {
+                transform.translation =
+                    vertex_label_offset(style.shape, radius, font_size).extend(VERTEX_TEXT_Z);
+            }
+        }
+
+        if style.stroke_width > 0.0 {
+            let stroke = commands
+                .spawn((
+                    VertexStroke,
+                    Mesh2d(assets.mesh(MeshKey::vertex(
+                        style.shape,
+                        radius,
+                        &vertex.label,
+                        font_size,
+                        style.stroke_width,
+                    ))),
+                    MeshMaterial2d(assets.material(style.stroke_color())),
+                    Transform::from_xyz(0.0, 0.0, VERTEX_STROKE_Z),
+                    Pickable::IGNORE,
+                ))
+                .id();
+            commands.entity(entity).add_child(stroke);
+        }
+    }
+}
+
+/// Recolours edges whose style changed, and keeps the dashes
+/// of dashed and dotted edges in step with their length.
+pub fn apply_edge_styles(
+    mut commands: Commands,
+    mut recolored: Query<
+        (Entity, &EdgeStyle, &mut MeshMaterial2d<ColorMaterial>),
+        Or<(Changed<EdgeStyle>, Added<DirectedEdge>)>,
+    >,
+    mut styled: Query<
+        (
+            Entity,
+            Ref<DirectedEdge>,
+            &EdgeStyle,
+            &Transform,
+            &mut Mesh2d,
+            Option<&EdgePattern>,
+        ),
+        Or<(
+            Changed<Transform>,
+            Changed<EdgeStyle>,
+            Changed<DirectedEdge>,
+        )>,
+    >,
+    hovered: Res<HoveredEntity>,
+    mut assets: GraphAssets,
+) {
+    for (entity, style, mut material) in &mut recolored {
+        material.0 = assets.material(if hovered.0 == Some(entity) {
+            hovered_edge_color(Some(style), assets.palette())
+        } else {
+            edge_color(Some(style), assets.palette())
+        });
+    }
+
+    for (entity, edge, style, transform, mut mesh, pattern) in &mut styled {
+        // A restored edge gets the plain mesh of the bundle back.
+        let pattern = pattern.filter(|_| !edge.is_added());
+        match dash_pattern(style.line, transform.scale.x, style.width) {
+            None => {
+                if pattern.is_some() {
+                    mesh.0 = assets.mesh(MeshKey::Edge);
+                    commands.entity(entity).remove::<EdgePattern>();
+                }
+            }
+            Some((dashes, ratio)) => {
+                let wanted = EdgePattern {
+                    line: style.line,
+                    dashes,
+                };
+                if pattern != Some(&wanted) {
+                    mesh.0 = assets.mesh(MeshKey::dashed_edge(dashes, ratio));
+                    commands.entity(entity).insert(wanted);
+                }
+            }
+        }
+    }
+}
+
+/// Restyles everything that follows the settings once they changed: the
+/// canvas, the temporary edge, unstyled vertices and edges, and all labels.
+/// Styled vertices and edges keep the colours and sizes they were given.
+pub fn apply_settings(
+    mut commands: Commands,
+    canvas: Single<Entity, With<Canvas>>,
+    temporary_edge: Single<Entity, With<TemporaryDirectedEdge>>,
+    edge_batch: Single<Entity, With<EdgeBatch>>,
+    mut vertices: Query<
+        (
+            Entity,
+            Option<&HeatMapStyle>,
+            &mut MeshMaterial2d<ColorMaterial>,
+        ),
+        (With<Vertex>, Without<VertexStyle>),
+    >,
+    mut edges: Query<
+        (Entity, &mut MeshMaterial2d<ColorMaterial>, &mut Transform),
+        (With<DirectedEdge>, Without<EdgeStyle>, Without<Vertex>),
+    >,
+    mut labels: Query<(&mut TextFont, &mut TextColor, Has<EdgeWeightLabel>), With<Text2d>>,
+    mut labelled: Query<&mut Vertex>,
+    mut layout: Local<Option<(f32, f32)>>,
+    hovered: Res<HoveredEntity>,
+    mut assets: GraphAssets,
+) {
+    // Label offsets, rounded boxes and hit areas follow the font size and
+    // the radius, so every vertex is laid out again when one of them changed.
+    let settings = assets.settings();
+    let vertex_layout = (settings.vertex_label_font_size, settings.vertex_radius);
+    if layout.replace(vertex_layout) != Some(vertex_layout) {
+        for mut vertex in &mut labelled {
+            vertex.set_changed();
+        }
+    }
+    let edge_width = settings.edge_width;
+
+    let palette = assets.palette();
+    let background = assets.material(palette.background);
+    commands.entity(*canvas).insert(MeshMaterial2d(background));
+    let temporary = assets.material(palette.temporary_edge);
+    commands
+        .entity(*temporary_edge)
+        .insert(MeshMaterial2d(temporary));
+    let edge = assets.material(palette.edge);
+    commands.entity(*edge_batch).insert(MeshMaterial2d(edge));
+
+    for (entity, heat_map, mut material) in &mut vertices {
+        material.0 = assets.material(if hovered.0 == Some(entity) {
+            hovered_vertex_fill(None, palette)
+        } else {
+            vertex_fill(None, heat_map, palette)
+        });
+    }
+    for (entity, mut material, mut transform) in &mut edges {
+        material.0 = assets.material(if hovered.0 == Some(entity) {
+            hovered_edge_color(None, palette)
+        } else {
+            edge_color(None, palette)
+        });
+        // Only touched when it differs, the spatial index follows changed transforms.
+        if transform.scale.y != edge_width {
+            transform.scale.y = edge_width;
+        }
+    }
+
+    let settings = assets.settings();
+    for (mut font, mut color, weight) in &mut labels {
+        font.font_size = if weight {
+            settings.edge_weight_font_size
+        } else {
+            settings.vertex_label_font_size
+        };
+        color.0 = palette.text;
+    }
+}
+
+/// Arrowheads sit where their edge reaches the target vertex, and share
+/// the material of the edge so hovering highlights them together.
+/// They are hidden the same way as weight labels.
+pub fn update_edge_arrowheads(
+    mut commands: Commands,
+    headless: Query<(Entity, &EdgeStyle), (With<DirectedEdge>, Without<HasArrowhead>)>,
+    mut arrowheads: Query<
+        (
+            Entity,
+            &mut EdgeArrowhead,
+            &mut Mesh2d,
+            &mut MeshMaterial2d<ColorMaterial>,
+            &mut Transform,
+            &mut Visibility,
+        ),
+        Without<DirectedEdge>,
+    >,
+    edges: Query<(&DirectedEdge, &EdgeStyle, &MeshMaterial2d<ColorMaterial>)>,
+    vertices: Query<(&Position, Option<&VertexStyle>), With<Vertex>>,
+    mut assets: GraphAssets,
+) {
+    for (edge, style) in &headless {
+        if style.arrow == ArrowStyle::None {
+            continue;
+        }
+        commands.spawn((
+            EdgeArrowhead {
+                edge,
+                style: ArrowStyle::None,
+            },
+            Mesh2d::default(),
+            MeshMaterial2d::<ColorMaterial>::default(),
+            Visibility::Hidden,
+            Pickable::IGNORE,
+        ));
+        commands.entity(edge).insert(HasArrowhead);
+    }
+
+    let vertex_radius = assets.settings().vertex_radius;
+    for (entity, mut arrowhead, mut mesh, mut material, mut transform, mut visibility) in
+        &mut arrowheads
+    {
+        if commands.get_entity(arrowhead.edge).is_err() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let shown = edges
+            .get(arrowhead.edge)
+            .ok()
+            .filter(|(_, style, _)| style.arrow != ArrowStyle::None)
+            .and_then(|(edge, style, edge_material)| {
+                let (from, _) = vertices.get(edge.from).ok()?;
+                let (to, target_style) = vertices.get(edge.to).ok()?;
+                let target_radius = target_style.map_or(vertex_radius, |style| style.radius);
+                Some((style, edge_material, from.0, to.0, target_radius))
+            });
+        let Some((style, edge_material, from, to, target_radius)) = shown else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+
+        if arrowhead.style != style.arrow {
+            arrowhead.style = style.arrow;
+            mesh.0 = assets.mesh(MeshKey::Arrowhead(style.arrow));
+        }
+        if material.0 != edge_material.0 {
+            material.0 = edge_material.0.clone();
+        }
+
+        let size = (style.width * ARROWHEAD_WIDTH_FACTOR).max(ARROWHEAD_MIN_SIZE);
+        let direction = (to - from).normalize_or_zero();
+        let center = to - direction * (target_radius + size / 2.0);
+        transform.set_if_neq(Transform {
+            translation: center.extend(ARROWHEAD_Z),
+            rotation: Quat::from_rotation_z(direction.to_angle()),
+            scale: Vec3::splat(size),
+        });
+        visibility.set_if_neq(Visibility::Visible);
+    }
+}
+
+/// Weight labels follow the middle of their edge, and are hidden while
+/// the edge has no weight or any part of it is logically deleted.
+pub fn update_edge_weight_labels(
+    mut commands: Commands,
+    unlabeled: Query<
+        Entity,
+        (
+            With<DirectedEdge>,
+            With<EdgeWeight>,
+            Without<HasWeightLabel>,
+        ),
+    >,
+    mut labels: Query<(
+        Entity,
+        &EdgeWeightLabel,
+        &mut Text2d,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+    edges: Query<(&DirectedEdge, Option<&EdgeWeight>)>,
+    positions: Query<&Position, With<Vertex>>,
+    settings: Res<Settings>,
+) {
+    for edge in &unlabeled {
+        commands.spawn((
+            EdgeWeightLabel { edge },
+            Text2d::default(),
+            TextFont::from_font_size(settings.edge_weight_font_size),
+            TextColor(settings.palette().text),
+            Visibility::Hidden,
+        ));
+        commands.entity(edge).insert(HasWeightLabel);
+    }
+
+    for (entity, label, mut text, mut transform, mut visibility) in &mut labels {
+        if commands.get_entity(label.edge).is_err() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let shown = edges.get(label.edge).ok().and_then(|(edge, weight)| {
+            let from = positions.get(edge.from).ok()?;
+            let to = positions.get(edge.to).ok()?;
+            Some((weight?.0, from.0, to.0))
+        });
+        let Some((weight, from, to)) = shown else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+
+        let normal = (to - from).normalize_or_zero().perp();
+        let middle = (from + to) / 2.0 + normal * EDGE_WEIGHT_LABEL_OFFSET;
+        transform.set_if_neq(Transform::from_translation(middle.extend(VERTEX_TEXT_Z)));
+        visibility.set_if_neq(Visibility::Visible);
+        let weight = weight.to_string();
+        if text.0 != weight {
+            text.0 = weight;
+        }
+    }
+}
+
+/// Keeps the `SpatialIndex` in step with moved, restyled, renamed and
+/// (logically) deleted vertices, and with every edge whose transform changed.
+pub fn update_spatial_index(
+    mut index: ResMut<SpatialIndex>,
+    vertices: Query<(
+        &Vertex,
+        &Position,
+        Option<&VertexStyle>,
+        Option<&HeatMapStyle>,
+    )>,
+    changed_vertices: Query<
+        Entity,
+        Or<(
+            Changed<Position>,
+            Changed<Vertex>,
+            Changed<VertexStyle>,
+            Changed<HeatMapStyle>,
+        )>,
+    >,
+    mut removed_positions: RemovedComponents<Position>,
+    mut removed_heat_maps: RemovedComponents<HeatMapStyle>,
+    edges: Query<(Entity, &DirectedEdge, Option<&EdgeStyle>), Changed<Transform>>,
+    mut removed_edges: RemovedComponents<DirectedEdge>,
+    positions: Query<&Position>,
+    settings: Res<Settings>,
+) {
+    for entity in removed_positions.read() {
+        index.remove_vertex(entity);
+    }
+    for entity in removed_edges.read() {
+        index.remove_edge(entity);
+    }
+
+    let changed: HashSet<Entity> = changed_vertices
+        .iter()
+        .chain(removed_heat_maps.read())
+        .collect();
+    for entity in changed {
+        let Ok((vertex, position, style, heat_map)) = vertices.get(entity) else {
+            continue;
+        };
+        let shape = style.map(|style| style.shape).unwrap_or_default();
+        let radius = heat_map
+            .and_then(|heat_map| heat_map.radius)
+            .or(style.map(|style| style.radius))
+            .unwrap_or(settings.vertex_radius);
+        index.insert_vertex(
+            entity,
+            VertexBounds::new(
+                position.0,
+                shape,
+                radius,
+                &vertex.label,
+                settings.vertex_label_font_size,
+            ),
+        );
+    }
+
+    for (entity, edge, style) in &edges {
+        // Edges of logically deleted vertices are hidden, not hoverable.
+        if let Ok(from) = positions.get(edge.from)
+            && let Ok(to) = positions.get(edge.to)
+        {
+            let width = style.map_or(settings.edge_width, |style| style.width);
+            index.insert_edge(
+                entity,
+                EdgeBounds {
+                    from: from.0,
+                    to: to.0,
+                    width,
+                },
+            );
+        } else {
+            index.remove_edge(entity);
+        }
+    }
+}
+
+/// A picking backend that hit tests vertices and edges through the
+/// `SpatialIndex`, instead of ray casting against every mesh. Only the
+/// canvas is left to mesh picking.
+pub fn pick_from_spatial_index(
+    ray_map: Res<RayMap>,
+    cameras: Query<&Camera>,
+    index: Res<SpatialIndex>,
+    mut hits: MessageWriter<PointerHits>,
+) {
+    for (ray_id, ray) in ray_map.iter() {
+        let Ok(camera) = cameras.get(ray_id.camera) else {
+            continue;
+        };
+        let point = ray.origin.truncate();
+        let hit = |entity: Entity, z: f32| {
+            let data = HitData::new(ray_id.camera, ray.origin.z - z, Some(point.extend(z)), None);
+            (entity, data)
+        };
+
+        let picks: Vec<(Entity, HitData)> = index
+            .nearest_vertex(point, 0.0)
+            .map(|vertex| hit(vertex, VERTEX_Z))
+            .into_iter()
+            .chain(index.nearest_edge(point, 0.0).map(|edge| hit(edge, EDGE_Z)))
+            .collect();
+        if !picks.is_empty() {
+            hits.write(PointerHits::new(ray_id.pointer, picks, camera.order as f32));
+        }
+    }
+}
+
 /// Each update the temporary edge is either visible or not.
 /// Non-visibility is done with 0 scale.
 pub fn update_temp_edge_transform(
     edge: Single<(&TemporaryDirectedEdge, &mut Transform)>,
     positions: Query<&Position>,
+    settings: Res<Settings>,
 ) {
     let (edge, mut transform) = edge.into_inner();
     let Some(from_vertex) = edge.from else {
@@ -168,11 +1065,16 @@ pub fn update_temp_edge_transform(
         return;
     };
 
-    apply_edge_transform(from_pos.0, edge.to, transform.into_inner());
+    apply_edge_transform(
+        from_pos.0,
+        edge.to,
+        settings.edge_width,
+        transform.into_inner(),
+    );
 }
 
 /// Transforms the edge such that it becomes a segment between its two position arguments
-fn apply_edge_transform(from_pos: Vec2, to_pos: Vec2, transform: &mut Transform) {
+fn apply_edge_transform(from_pos: Vec2, to_pos: Vec2, width: f32, transform: &mut Transform) {
     let direction = to_pos - from_pos;
     let length = direction.length();
     let angle = direction.y.atan2(direction.x);
@@ -180,7 +1082,7 @@ fn apply_edge_transform(from_pos: Vec2, to_pos: Vec2, transform: &mut Transform)
     transform.translation = (from_pos + direction / 2.0).extend(EDGE_Z);
     transform.rotation = Quat::from_rotation_z(angle);
     transform.scale.x = length;
-    transform.scale.y = EDGE_WIDTH;
+    transform.scale.y = width;
 }
 
 /// Transforms the edge such that it is not visible/pickable.
@@ -193,5 +1095,322 @@ fn apply_edge_transform_behind_canvas(from_pos: Vec2, to_pos: Vec2, transform: &
     transform.translation = (from_pos + direction / 2.0).extend(HIDDEN_EDGE_Z);
     transform.rotation = Quat::from_rotation_z(angle);
     transform.scale.x = length;
-    transform.scale.y = EDGE_WIDTH;
+}
+
+/// Rebuilds the `GraphSnapshot` whenever a vertex or an edge
+/// appears, disappears or gets rewired.
+pub fn update_graph_snapshot(
+    mut snapshot: ResMut<GraphSnapshot>,
+    added_vertices: Query<(), Added<Vertex>>,
+    changed_edges: Query<(), Changed<DirectedEdge>>,
+    mut removed_vertices: RemovedComponents<Vertex>,
+    mut removed_edges: RemovedComponents<DirectedEdge>,
+    vertices: Query<Entity, With<Vertex>>,
+    edges: Query<&DirectedEdge>,
+) {
+    let removed = removed_vertices.read().count() + removed_edges.read().count();
+    if removed == 0 && added_vertices.is_empty() && changed_edges.is_empty() {
+        return;
+    }
+
+    snapshot.rebuild(
+        vertices.iter(),
+        edges.iter().map(|edge| (edge.from, edge.to)),
+    );
+}
+
+/// Recomputes the metrics for the current snapshot, but only
+/// while the panel or the heat-map actually needs them.
+pub fn update_graph_metrics(
+    snapshot: Res<GraphSnapshot>,
+    panel: Res<MetricsPanelState>,
+    mut metrics: ResMut<GraphMetrics>,
+) {
+    let needed = panel.open || panel.heat_map.is_some();
+    if !needed || metrics.revision == snapshot.revision {
+        return;
+    }
+
+    metrics.values = vertex_metrics(&snapshot);
+    metrics.revision = snapshot.revision;
+}
+
+/// The cheap statistics are recomputed right after a change, the distance
+/// based ones keep running a few BFS passes per frame until they are done.
+pub fn update_graph_statistics(
+    snapshot: Res<GraphSnapshot>,
+    mut panel: ResMut<StatisticsPanelState>,
+) {
+    if !panel.open {
+        return;
+    }
+
+    if panel.revision != snapshot.revision {
+        panel.revision = snapshot.revision;
+        panel.statistics = graph_statistics(&snapshot);
+        panel.distances = DistanceStatistics::default();
+    }
+
+    let started = Instant::now();
+    while !panel.distances.is_complete(&snapshot) && started.elapsed() < STATISTICS_FRAME_BUDGET {
+        panel.distances.step(&snapshot);
+    }
+}
+
+/// Maps the chosen metric onto the colour and/or radius of each vertex.
+/// Only does any work if the metrics or the heat-map settings changed.
+pub fn apply_heat_map(
+    mut commands: Commands,
+    mut applied: Local<Option<(u64, Option<Metric>, bool, bool)>>,
+    panel: Res<MetricsPanelState>,
+    metrics: Res<GraphMetrics>,
+    snapshot: Res<GraphSnapshot>,
+    hovered: Res<HoveredEntity>,
+    styled: Query<Entity, With<HeatMapStyle>>,
+    mut vertices: Query<(
+        &Vertex,
+        Option<&VertexStyle>,
+        &mut Mesh2d,
+        &mut MeshMaterial2d<ColorMaterial>,
+    )>,
+    mut assets: GraphAssets,
+) {
+    let settings = (
+        metrics.revision,
+        panel.heat_map,
+        panel.heat_map_color,
+        panel.heat_map_radius,
+    );
+    if *applied == Some(settings) {
+        return;
+    }
+    // Wait for the metrics to catch up with the graph.
+    if panel.heat_map.is_some() && metrics.revision != snapshot.revision {
+        return;
+    }
+    *applied = Some(settings);
+
+    for entity in &styled {
+        commands.entity(entity).remove::<HeatMapStyle>();
+        if let Ok((vertex, vertex_style, mut mesh, mut material)) = vertices.get_mut(entity) {
+            let color = if hovered.0 == Some(entity) {
+                hovered_vertex_fill(vertex_style, assets.palette())
+            } else {
+                vertex_fill(vertex_style, None, assets.palette())
+            };
+            let vertex_style = vertex_style
+                .cloned()
+                .unwrap_or_else(|| VertexStyle::themed(assets.settings()));
+            mesh.0 = assets.mesh(MeshKey::vertex(
+                vertex_style.shape,
+                vertex_style.radius,
+                &vertex.label,
+                assets.settings().vertex_label_font_size,
+                0.0,
+            ));
+            material.0 = assets.material(color);
+        }
+    }
+
+    let Some(metric) = panel.heat_map else {
+        return;
+    };
+
+    let values: Vec<f32> = metrics.values.iter().map(|m| metric.value(m)).collect();
+    let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+
+    for (index, value) in values.iter().enumerate() {
+        let entity = snapshot.vertices[index];
+        let Ok((vertex, vertex_style, mut mesh, mut material)) = vertices.get_mut(entity) else {
+            continue;
+        };
+        // Snapped to a few steps, so vertices share their meshes and materials.
+        let t = if max > min {
+            ((value - min) / (max - min) * HEAT_MAP_STEPS).round() / HEAT_MAP_STEPS
+        } else {
+            0.5
+        };
+
+        let style = HeatMapStyle {
+            color: panel
+                .heat_map_color
+                .then(|| HEAT_MAP_COLD_COLOR.mix(&HEAT_MAP_HOT_COLOR, t)),
+            radius: panel
+                .heat_map_radius
+                .then(|| HEAT_MAP_MIN_RADIUS.lerp(HEAT_MAP_MAX_RADIUS, t)),
+        };
+
+        if let Some(radius) = style.radius {
+            let shape = vertex_style.map(|style| style.shape).unwrap_or_default();
+            mesh.0 = assets.mesh(MeshKey::vertex(
+                shape,
+                radius,
+                &vertex.label,
+                assets.settings().vertex_label_font_size,
+                0.0,
+            ));
+        }
+        if let Some(color) = style.color
+            && hovered.0 != Some(entity)
+        {
+            material.0 = assets.material(color);
+        }
+        commands.entity(entity).insert(style);
+    }
+}
+
+/// Frees the shared meshes and materials no vertex or edge uses anymore.
+pub fn evict_unused_handles(mut cache: ResMut<HandleCache>) {
+    cache.evict_unused();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::graph::{
+        bundles::{DirectedEdgeBundle, VertexBundle},
+        resources::VertexIds,
+    };
+
+    #[test]
+    fn settings_resize_unstyled_vertices_and_edges() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_asset::<ColorMaterial>()
+            .insert_resource(HoveredEntity(None))
+            .insert_resource(HandleCache::default())
+            .insert_resource(Settings::default())
+            .insert_resource(VertexIds::default());
+        app.world_mut().spawn(Canvas);
+        app.world_mut().spawn(TemporaryDirectedEdge {
+            from: None,
+            to: Vec2::ZERO,
+        });
+        app.world_mut().spawn(EdgeBatch::default());
+
+        let (vertex, plain, styled) = app
+            .world_mut()
+            .run_system_once(|mut commands: Commands, mut assets: GraphAssets| {
+                let from = VertexBundle::spawn(&mut commands, &mut assets, Vec2::ZERO);
+                let to = VertexBundle::spawn(&mut commands, &mut assets, Vec2::new(100.0, 0.0));
+                let plain = DirectedEdgeBundle::spawn(from, to, &mut commands, &mut assets);
+                let styled = DirectedEdgeBundle::spawn(to, from, &mut commands, &mut assets);
+                commands.entity(styled).insert(EdgeStyle {
+                    width: 3.0,
+                    ..default()
+                });
+                (from, plain, styled)
+            })
+            .unwrap();
+        app.world_mut()
+            .run_system_once(update_edge_transforms)
+            .unwrap();
+        let width = |app: &mut App, edge: Entity| {
+            app.world()
+                .get::<Transform>(edge)
+                .map(|transform| transform.scale.y)
+        };
+        assert_eq!(width(&mut app, plain), Some(10.0));
+        assert_eq!(width(&mut app, styled), Some(3.0));
+
+        {
+            let mut settings = app.world_mut().resource_mut::<Settings>();
+            settings.vertex_radius = 20.0;
+            settings.edge_width = 4.0;
+        }
+        app.world_mut().run_system_once(apply_settings).unwrap();
+        app.world_mut()
+            .run_system_once(apply_vertex_styles)
+            .unwrap();
+
+        assert_eq!(width(&mut app, plain), Some(4.0));
+        assert_eq!(width(&mut app, styled), Some(3.0));
+        let mesh = app
+            .world_mut()
+            .run_system_once(|mut assets: GraphAssets| {
+                assets.mesh(MeshKey::vertex(VertexShape::Circle, 20.0, "", 12.0, 0.0))
+            })
+            .unwrap();
+        assert_eq!(
+            app.world().get::<Mesh2d>(vertex).map(|mesh| &mesh.0),
+            Some(&mesh)
+        );
+    }
+
+    #[test]
+    fn plain_edges_are_drawn_by_the_batch() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_asset::<ColorMaterial>()
+            .insert_resource(HoveredEntity(None))
+            .insert_resource(HandleCache::default())
+            .insert_resource(Settings::default())
+            .insert_resource(VertexIds::default())
+            // Normally registered by the visibility plugin.
+            .register_required_components::<Mesh2d, Visibility>();
+        app.world_mut()
+            .run_system_once(crate::graph::startups::spawn_edge_batch)
+            .unwrap();
+
+        let (from, plain, styled) = app
+            .world_mut()
+            .run_system_once(|mut commands: Commands, mut assets: GraphAssets| {
+                let from = VertexBundle::spawn(&mut commands, &mut assets, Vec2::ZERO);
+                let to = VertexBundle::spawn(&mut commands, &mut assets, Vec2::new(100.0, 0.0));
+                let plain = DirectedEdgeBundle::spawn(from, to, &mut commands, &mut assets);
+                let styled = DirectedEdgeBundle::spawn(to, from, &mut commands, &mut assets);
+                commands.entity(styled).insert(EdgeStyle::default());
+                (from, plain, styled)
+            })
+            .unwrap();
+        app.world_mut()
+            .run_system_once(update_edge_transforms)
+            .unwrap();
+        let batch = app.world_mut().register_system(update_edge_batch);
+        app.world_mut().run_system(batch).unwrap();
+
+        let visibility = |app: &App, edge: Entity| app.world().get::<Visibility>(edge).copied();
+        let quad = |app: &mut App, slot: usize| {
+            let mut batches = app.world_mut().query::<(&EdgeBatch, &Mesh2d)>();
+            let (edge_batch, mesh) = batches.single(app.world()).unwrap();
+            assert_eq!(edge_batch.slots.get(&plain), Some(&slot));
+            let mesh = app.world().resource::<Assets<Mesh>>().get(&mesh.0).unwrap();
+            let Some(VertexAttributeValues::Float32x3(positions)) =
+                mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+            else {
+                panic!("the batch has no positions");
+            };
+            assert_eq!(positions.len(), 4 * edge_batch.slots.len());
+            positions[slot * 4..slot * 4 + 4].to_vec()
+        };
+        assert_eq!(visibility(&app, plain), Some(Visibility::Hidden));
+        assert_eq!(visibility(&app, styled), Some(Visibility::Inherited));
+        let before = quad(&mut app, 0);
+        assert!(
+            before
+                .iter()
+                .all(|corner| corner[0] <= 100.0 && corner[0] >= 0.0)
+        );
+
+        app.world_mut().get_mut::<Position>(from).unwrap().0 = Vec2::new(-100.0, 0.0);
+        app.world_mut()
+            .run_system_once(update_edge_transforms)
+            .unwrap();
+        app.world_mut().run_system(batch).unwrap();
+        let after = quad(&mut app, 0);
+        assert_ne!(before, after);
+        assert!(after.iter().any(|corner| corner[0] < 0.0));
+
+        app.world_mut().resource_mut::<HoveredEntity>().0 = Some(plain);
+        app.world_mut().run_system(batch).unwrap();
+        assert_eq!(visibility(&app, plain), Some(Visibility::Inherited));
+        app.world_mut().resource_mut::<HoveredEntity>().0 = None;
+        app.world_mut().run_system(batch).unwrap();
+        assert_eq!(visibility(&app, plain), Some(Visibility::Hidden));
+    }
 }
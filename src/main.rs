@@ -1,3 +1,6 @@
+// Bevy systems routinely take many parameters and nested query types.
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
 use bevy::{
     dev_tools::fps_overlay::{FpsOverlayConfig, FpsOverlayPlugin, FrameTimeGraphConfig},
     prelude::*,
@@ -6,7 +9,7 @@ use bevy::{
 mod graph;
 
 pub fn spawn_camera(mut commands: Commands) {
-    commands.spawn(Camera2d);
+    commands.spawn((Camera2d, MeshPickingCamera));
 }
 
 pub fn get_fps_overlay_plugin_config() -> FpsOverlayPlugin {